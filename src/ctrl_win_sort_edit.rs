@@ -1,11 +1,15 @@
 use fxhash::FxHashMap;
-use windows::Win32::UI::Controls::{HOVER_DEFAULT, WM_MOUSELEAVE};
+use windows::Win32::UI::Controls::{SetScrollInfo, HOVER_DEFAULT, WM_MOUSELEAVE};
 
 use super::*;
-use crate::{lib_window::WindowInfo, lib_gui_layout_container::sys_metrics};
+use crate::{lib_window::WindowInfo, lib_gui_layout_container::sys_metrics, lib_pattern::PathPattern, lib_property::{ProcHistory, PropertyHolder}};
 
 static ONCE: Once = Once::new();
 
+pub const SEN_SELCHANGE: u32 = 1; // 選択項目の変更(WM_COMMANDで親へ通知)
+const SORT_EDIT_MAX_ROWS: i32 = 16; // これを超える項目はスクロールで表示する
+const SORT_EDIT_WHEEL_LINES: i32 = 3;
+
 #[derive(::core::cmp::PartialEq)]
 enum MouseBtnState {
     NONE, LDOWN, MDOWN, RDOWN,
@@ -25,13 +29,22 @@ pub struct WinSortEditCtrl {
     btn_state: MouseBtnState,
     btn_idx_hover: i32,
     btn_idx_push: i32,
+    sel_idx: i32, // -1:未選択
+    top_idx: i32, // スクロール位置(先頭に表示する項目)
+    b_focus: bool,
 
     pad: i32,
     group_bar_width: i32,
 
     vec_candidate_list: Vec<String>,
+    vec_candidate_hidden: Vec<String>, // 絞り込みで非表示にした候補
+    filter: String, // 候補の絞り込み文字列(小文字)
     vec_sort_list: Vec<String>,
     map_icons: FxHashMap<String, Icon>,
+    vec_running: Vec<(String, HWND)>, // 実行中のプロセスイメージ(AppUserModelIDを持つものはID)と代表ウィンドウ
+    vec_history: Vec<ProcHistory>, // 過去に見かけたプロセス(新しい順)
+    history_days: Option<u64>, // 候補に出す履歴の期間(日)。None:履歴を候補に出さない
+    now: u64,
 }
 
 impl Drop for WinSortEditCtrl {
@@ -47,20 +60,89 @@ impl RcValueRef<WinSortEditCtrl> for WinSortEditCtrlRc {}
 pub trait WinSortEditCtrlBehavior {
     fn get_size(&self) -> (i32 /* w */, i32 /* h */);
     fn get_sortlist(&self) -> Vec<String>;
+    fn get_selected(&self) -> Option<(String, bool /* ソート対象か */)>;
+    fn get_running(&self) -> Vec<String>;
+    fn add_rule(&mut self, text: &str);
+    fn update_rule(&mut self, text: &str);
+    fn delete_rule(&mut self);
+    fn set_history(&mut self, history: Vec<ProcHistory>, days: Option<u64>);
+    fn set_filter(&mut self, text: &str);
 }
 
 impl WinSortEditCtrlBehavior for WinSortEditCtrlRc {
     fn get_size(&self) -> (i32 /* w */, i32 /* h */) {
-        (self.wnd_width, self.wnd_height)
+        (self.wnd_width + sys_metrics(self.handle, SM_CXVSCROLL), self.wnd_height)
     }
 
     fn get_sortlist(&self) -> Vec<String> {
         self.vec_sort_list.clone()
     }
+
+    fn get_selected(&self) -> Option<(String, bool /* ソート対象か */)> {
+        let num_cand = self.vec_candidate_list.len() as i32;
+        if self.sel_idx < 0 { return None }
+        if self.sel_idx < num_cand {
+            Some((self.vec_candidate_list[self.sel_idx as usize].clone(), false))
+        } else {
+            self.vec_sort_list.get((self.sel_idx - num_cand) as usize).map(|v| (v.clone(), true))
+        }
+    }
+
+    fn get_running(&self) -> Vec<String> {
+        self.vec_running.iter().map(|(v, _)| v.clone()).collect()
+    }
+
+    // 選択中のソート対象の次へ追加する(既にあれば選択のみ)
+    fn add_rule(&mut self, text: &str) {
+        let s = self.get_mut();
+        if text.is_empty() { return }
+        let pos = match s.vec_sort_list.iter().position(|v| v == text) {
+            Some(i) => i,
+            None => {
+                let i = s.sel_sort_pos().map_or(s.vec_sort_list.len(), |i| i + 1);
+                s.vec_sort_list.insert(i, text.to_string());
+                i
+            }
+        };
+        s.list_changed(Some(pos));
+    }
+
+    fn update_rule(&mut self, text: &str) {
+        let s = self.get_mut();
+        let Some(i) = s.sel_sort_pos() else { return };
+        if text.is_empty() { return }
+        s.vec_sort_list[i] = text.to_string();
+        s.list_changed(Some(i));
+    }
+
+    fn delete_rule(&mut self) {
+        let s = self.get_mut();
+        let Some(i) = s.sel_sort_pos() else { return };
+        s.vec_sort_list.remove(i);
+        s.list_changed(None);
+    }
+
+    fn set_history(&mut self, history: Vec<ProcHistory>, days: Option<u64>) {
+        let s = self.get_mut();
+        s.vec_history = history;
+        s.vec_history.sort_by_key(|h| std::cmp::Reverse(h.last_seen));
+        s.history_days = days;
+        s.list_changed(None);
+    }
+
+    // パスの部分一致で候補を絞り込む(ソート対象は常に表示)
+    fn set_filter(&mut self, text: &str) {
+        let s = self.get_mut();
+        let filter = text.trim().to_lowercase();
+        if s.filter == filter { return }
+        s.filter = filter;
+        s.list_changed(s.sel_sort_pos());
+    }
 }
 
 impl WinSortEditCtrl {
-    pub fn init(hparent: HWND, hfont: HFONT, sort_list: Vec<String>, cmdid: isize) -> WinSortEditCtrlWeak {
+    pub fn init(hparent: HWND, hfont: HFONT, sort_list: Vec<String>, mut history: Vec<ProcHistory>, history_days: Option<u64>, cmdid: isize) -> WinSortEditCtrlWeak {
+        history.sort_by_key(|h| std::cmp::Reverse(h.last_seen));
         let wnd = Rc::new(Self {
             handle: HWND(0), // WM_NCCREATEの処理の中で設定される
             hfont: hfont,
@@ -75,13 +157,22 @@ impl WinSortEditCtrl {
             btn_state: MouseBtnState::NONE,
             btn_idx_hover: -1,
             btn_idx_push: -1,
+            sel_idx: -1,
+            top_idx: 0,
+            b_focus: false,
 
             pad: 3,
             group_bar_width: 4,
 
             vec_candidate_list: Vec::<String>::default(),
+            vec_candidate_hidden: Vec::default(),
+            filter: String::new(),
             vec_sort_list: sort_list,
             map_icons: FxHashMap::<String, Icon>::default(),
+            vec_running: Vec::default(),
+            vec_history: history,
+            history_days: history_days,
+            now: ProcHistory::now(),
         });
 
         let window_class = w!("win_sort_edit_ctrl");
@@ -99,7 +190,7 @@ impl WinSortEditCtrl {
         });
 
         unsafe { CreateWindowExW(
-            WS_EX_COMPOSITED, window_class, w!(""), WS_CHILD | WS_VISIBLE,
+            WS_EX_COMPOSITED, window_class, w!(""), WS_CHILD | WS_VISIBLE | WS_TABSTOP | WS_VSCROLL,
             0, 0, 0, 0, hparent, HMENU(cmdid), None, Some(&wnd as *const _ as _)) };
 
         Rc::downgrade(&wnd)
@@ -111,40 +202,199 @@ impl WinSortEditCtrl {
         (s.icon_width, s.icon_height) = (sys_metrics(s.handle, SM_CXSMICON), sys_metrics(s.handle, SM_CYSMICON));
 
         s.windowlist_init();
+        s.size_refresh();
 
-        let textwidth = {
-            let w1 = Self::check_item_maxsize(&mut s.vec_candidate_list, unsafe { GetDC(s.handle) }, s.hfont);
-            let w2 = Self::check_item_maxsize(&mut s.vec_sort_list, unsafe { GetDC(s.handle) }, s.hfont);
-            if w1 < w2 { w2 } else { w1 }
-        };
+        Ok(())
+    }
+
+    // 項目数・文字列幅からコントロールのサイズを再計算する
+    fn size_refresh(&mut self) {
+        let s = self;
 
+        let labels = s.vec_candidate_list.iter().chain(&s.vec_candidate_hidden).chain(&s.vec_sort_list).map(|v| s.item_label(v)).collect::<Vec<_>>(); // 絞り込みで幅が変わらないよう非表示の候補も含める
+        let hdc = unsafe { GetDC(s.handle) };
+        let textwidth = Self::check_item_maxsize(&labels, hdc, s.hfont);
+        unsafe { ReleaseDC(s.handle, hdc) };
+
+        s.num_item = ( s.vec_candidate_list.len() + s.vec_sort_list.len() ) as i32;
         s.wnd_width = s.pad * 2 + s.group_bar_width + s.pad * 2 + s.icon_width + s.pad + textwidth + s.pad;
         s.item_height = s.pad + s.icon_height + s.pad;
-        s.wnd_height = s.item_height * s.num_item;
+        s.wnd_height = s.item_height * s.num_item.clamp(1, SORT_EDIT_MAX_ROWS);
+        s.scroll_to(s.top_idx);
+    }
 
-        Ok(())
+    fn page_rows(&self) -> i32 {
+        self.wnd_height / self.item_height.max(1)
+    }
+
+    // 先頭に表示する項目を変更してスクロールバーへ反映する
+    fn scroll_to(&mut self, top: i32) {
+        let s = self;
+
+        let top = top.min(s.num_item - s.page_rows()).max(0);
+        let b_changed = s.top_idx != top;
+        s.top_idx = top;
+        let si = SCROLLINFO {
+            cbSize: std::mem::size_of::<SCROLLINFO>() as u32,
+            fMask: SIF_ALL | SIF_DISABLENOSCROLL,
+            nMin: 0,
+            nMax: s.num_item - 1,
+            nPage: s.page_rows() as u32,
+            nPos: s.top_idx,
+            nTrackPos: 0,
+        };
+        unsafe { SetScrollInfo(s.handle, SB_VERT, &si, TRUE); }
+        if b_changed { unsafe { InvalidateRect(s.handle, None, TRUE); } }
+    }
+
+    fn ensure_visible(&mut self, idx: i32) {
+        let s = self;
+
+        if idx < 0 { return }
+        if idx < s.top_idx {
+            s.scroll_to(idx);
+        } else if idx >= s.top_idx + s.page_rows() {
+            s.scroll_to(idx - s.page_rows() + 1);
+        }
+    }
+
+    // 手入力でソート対象を変更した後、候補・アイコン・サイズを更新して選択しなおす
+    fn list_changed(&mut self, sel_pos: Option<usize>) {
+        let s = self;
+
+        s.candidate_refresh();
+        for v in s.vec_candidate_list.iter().chain(&s.vec_sort_list) {
+            if s.map_icons.contains_key(v) { continue }
+            let pat = PathPattern::parse(v);
+            let h = s.vec_running.iter().find(|(r, _)| pat.is_match(r)).map(|(_, h)| *h).unwrap_or_default();
+            s.map_icons.insert(v.clone(), Icon::load_win_icon(h, &s.icon_source(v)));
+        }
+        s.size_refresh();
+        s.select(sel_pos.map_or(-1, |i| (s.vec_candidate_list.len() + i) as i32));
+        s.ensure_visible(s.sel_idx);
+    }
+
+    // 実行中のプロセスと表示期間内の履歴のうち、どのソート対象にも一致しないものを候補とする(既存の候補の並びは保つ)
+    fn candidate_refresh(&mut self) {
+        let s = self;
+
+        s.vec_candidate_list.append(&mut s.vec_candidate_hidden);
+        let sort_list = &s.vec_sort_list;
+        let unmatched = |v: &str| PropertyHolder::contains_procimg_list(&mut sort_list.iter(), v).is_none();
+        let keep = s.vec_candidate_list.iter()
+            .map(|v| unmatched(v) && s.history_only(v).is_none_or(|h| s.history_visible(h))).collect::<Vec<_>>();
+        let mut it = keep.into_iter();
+        s.vec_candidate_list.retain(|_| it.next().unwrap_or(true));
+
+        let add = s.vec_running.iter().map(|(v, _)| v)
+            .chain(s.vec_history.iter().filter(|h| s.history_visible(h)).map(|h| &h.proc_img))
+            .filter(|v| unmatched(v)).cloned().collect::<Vec<_>>();
+        for v in add {
+            if !s.vec_candidate_list.contains(&v) { s.vec_candidate_list.push(v); }
+        }
+
+        if s.filter.is_empty() { return }
+        let (shown, hidden) = std::mem::take(&mut s.vec_candidate_list).into_iter().partition(|v| v.to_lowercase().contains(&s.filter));
+        (s.vec_candidate_list, s.vec_candidate_hidden) = (shown, hidden);
+    }
+
+    // 起動していない履歴のプロセス
+    fn history_only(&self, v: &str) -> Option<&ProcHistory> {
+        if self.vec_running.iter().any(|(r, _)| r == v) { return None }
+        self.vec_history.iter().find(|h| h.proc_img == v)
+    }
+
+    fn history_visible(&self, h: &ProcHistory) -> bool {
+        self.history_days.is_some_and(|d| h.days_ago(self.now) < d)
+    }
+
+    // 起動していなければ履歴に残したアイコン取得元を使う
+    fn icon_source(&self, v: &str) -> String {
+        match self.history_only(v) {
+            Some(h) => h.icon_path.clone(),
+            None if WindowInfo::is_aumid(v) => WindowInfo::aumid_shell_path(v),
+            None => v.to_string(),
+        }
+    }
+
+    // 一覧の表示文字列。AppUserModelIDはアプリの表示名を添え、起動していない履歴のプロセスには最後に見かけた日数を付ける
+    fn item_label(&self, v: &str) -> String {
+        let name = if WindowInfo::is_aumid(v) { WindowInfo::app_display_name(v) } else { v.to_string() };
+        let label = if name != v { format!("{}  [{}]", name, v) } else { name };
+        match self.history_only(v) {
+            Some(h) => format!("{}  ({}{})", label, h.days_ago(self.now), DLG_SE_DAYS_AGO),
+            None => label,
+        }
+    }
+
+    fn sel_sort_pos(&self) -> Option<usize> {
+        let num_cand = self.vec_candidate_list.len() as i32;
+        if self.sel_idx >= num_cand && self.sel_idx < self.num_item { Some((self.sel_idx - num_cand) as usize) } else { None }
+    }
+
+    fn select(&mut self, idx: i32) {
+        let s = self;
+
+        s.sel_idx = idx;
+        unsafe { InvalidateRect(s.handle, None, TRUE); }
+        let id = unsafe { GetDlgCtrlID(s.handle) } as usize;
+        unsafe { SendMessageW(GetParent(s.handle), WM_COMMAND, WPARAM((SEN_SELCHANGE as usize) << u16::BITS | id), LPARAM(s.handle.0)); }
+    }
+
+    // キーボードによる選択移動(範囲外は端の項目に合わせる)
+    fn key_select(&mut self, idx: i32) {
+        let s = self;
+
+        if s.num_item == 0 { return }
+        let idx = idx.clamp(0, s.num_item - 1);
+        if idx != s.sel_idx { s.select(idx); }
+        s.ensure_visible(idx);
+    }
+
+    // 選択中のソート対象を上下へ移動する
+    fn move_item(&mut self, dir: i32) {
+        let s = self;
+
+        let Some(pos) = s.sel_sort_pos() else { return };
+        let to = pos as i32 + dir;
+        if to < 0 || to >= s.vec_sort_list.len() as i32 { return }
+        s.vec_sort_list.swap(pos, to as usize);
+        s.sel_idx += dir;
+        s.ensure_visible(s.sel_idx);
+        unsafe { InvalidateRect(s.handle, None, TRUE); }
+    }
+
+    // 選択中の項目を候補⇔ソート対象へ移し、移した先で選択しなおす
+    fn toggle_selected(&mut self) {
+        let s = self;
+
+        if s.sel_idx < 0 { return }
+        let idx = s.change_group(s.sel_idx);
+        s.select(idx);
+        s.ensure_visible(idx);
     }
 
     fn windowlist_init(&mut self) {
-        let mut nowlist = Vec::<WindowInfo>::default();
-        let _ = unsafe { EnumWindows(Some(WindowInfo::enum_window), LPARAM(&mut nowlist as *mut _ as _)) };
+        let nowlist = WindowInfo::enum_windows(&[]); // 候補には表示ルールで除外したプロセスも含める
+        for wi in &nowlist {
+            if !self.vec_running.iter().any(|(v, _)| v == wi.group_key()) { self.vec_running.push((wi.group_key().to_string(), wi.handle)); }
+        }
 
         let r = WindowInfo::merge_proc_list(&nowlist, &self.vec_sort_list, &mut self.vec_candidate_list);
+        self.candidate_refresh();
         self.icon_init(r);
-
-        self.num_item = ( self.vec_candidate_list.len() + self.vec_sort_list.len() ) as i32;
     }
 
     fn icon_init(&mut self, mut handles: FxHashMap<String, HWND>) {
         let s = self;
 
         for v in &s.vec_candidate_list {
-            let ii = Icon::load_win_icon(HWND(0), &v);
+            let ii = Icon::load_win_icon(HWND(0), &s.icon_source(v));
             s.map_icons.insert(v.clone(), ii);
         }
         for v in &s.vec_sort_list {
             let h = handles.remove(v).unwrap_or_default();
-            let ii = Icon::load_win_icon(h, &v);
+            let ii = Icon::load_win_icon(h, &s.icon_source(v));
             s.map_icons.insert(v.clone(), ii);
         }
     }
@@ -162,35 +412,40 @@ impl WinSortEditCtrl {
         max_width
     }
 
-    fn sort_item(&mut self) {
+    fn sort_item(&mut self) -> bool {
         let s = self;
 
         let num_cand = s.vec_candidate_list.len() as i32;
-        if s.btn_idx_hover < num_cand || s.btn_idx_push == s.btn_idx_hover { return }
+        if s.btn_idx_hover < num_cand || s.btn_idx_push == s.btn_idx_hover { return false }
 
         let v = s.vec_sort_list.remove(( s.btn_idx_push - num_cand ) as usize);
         s.vec_sort_list.insert(( s.btn_idx_hover - num_cand ) as usize, v);
+        true
     }
 
-    fn change_group(&mut self) {
+    fn change_group(&mut self, idx: i32) -> i32 { // 戻り値:移した後の項目位置
         let s = self;
 
-        if s.btn_idx_push < 0 { return }
+        if idx < 0 { return -1 }
 
         let num_cand = s.vec_candidate_list.len() as i32;
-        if s.btn_idx_push < num_cand {
-            let v = s.vec_candidate_list.remove(s.btn_idx_push as usize);
+        if idx < num_cand {
+            let v = s.vec_candidate_list.remove(idx as usize);
             s.vec_sort_list.insert(0, v);
+            num_cand - 1
 
         } else {
-            let v = s.vec_sort_list.remove(( s.btn_idx_push - num_cand ) as usize);
+            let v = s.vec_sort_list.remove(( idx - num_cand ) as usize);
             s.vec_candidate_list.push(v);
+            num_cand
         }
     }
 
     fn calc_pt2idx(&self, mx: i32, my: i32) -> i32 { // -1:外
         let s = self;
-        if mx < 0 || my < 0 || mx >= s.wnd_width || my >= s.wnd_height { -1 } else { my / s.item_height }
+        if mx < 0 || my < 0 || mx >= s.wnd_width || my >= s.wnd_height { return -1 }
+        let i = my / s.item_height + s.top_idx;
+        if i < s.num_item { i } else { -1 }
     }
 
     fn calc_idx2rect(&self, i: i32) -> RECT {
@@ -199,9 +454,9 @@ impl WinSortEditCtrl {
 
         RECT {
             left: s.pad * 2 + s.group_bar_width + s.pad * 2,
-            top: s.item_height * i,
+            top: s.item_height * (i - s.top_idx),
             right: s.wnd_width,
-            bottom: s.item_height * i + s.item_height,
+            bottom: s.item_height * (i - s.top_idx) + s.item_height,
         }
     }
 
@@ -246,7 +501,8 @@ impl WinSortEditCtrl {
         }
 
         if btn == MouseBtnState::LDOWN { // 左クリック初回
-            if sel_idx >= s.vec_candidate_list.len() as i32 { // candidateの左クリックは無効
+            if sel_idx >= 0 { s.select(sel_idx); } // candidateも選択はできる(編集欄への取り込み用)
+            if sel_idx >= s.vec_candidate_list.len() as i32 { // candidateの左クリックによる並べ替えは無効
                 s.btn_idx_push = sel_idx;
                 s.btn_idx_hover = sel_idx;
             }
@@ -282,6 +538,16 @@ impl WinSortEditCtrl {
             SetDCBrushColor(hdc, COLOR_DEFAULT_CURSOR_HIGHLIGHT);
             SetDCPenColor(hdc, COLOR_DEFAULT_CURSOR_HIGHLIGHT);
             Rectangle(hdc, r.left, r.top, r.right, r.bottom);
+        }} else if *idx == s.sel_idx { unsafe {
+            let r = s.calc_idx2rect(*idx);
+            SetDCBrushColor(hdc, COLOR_DEFAULT_CURSOR_HIGHLIGHT);
+            SetDCPenColor(hdc, COLOR_DEFAULT_TEXT);
+            Rectangle(hdc, r.left, r.top, r.right, r.bottom);
+        }}
+        if s.b_focus && *idx == s.sel_idx { unsafe {
+            let mut r = s.calc_idx2rect(*idx);
+            r.left += 1; r.top += 1; r.right -= 1; r.bottom -= 1;
+            DrawFocusRect(hdc, &r);
         }}
 
         if s.btn_idx_push >= 0 && s.btn_idx_push != s.btn_idx_hover && *idx == s.btn_idx_hover { unsafe {
@@ -304,7 +570,10 @@ impl WinSortEditCtrl {
             let _ = unsafe { DrawIconEx(hdc, s.pad * 2 + s.group_bar_width + s.pad * 2, *y + s.pad, i.0, s.icon_width, s.icon_height, 0, None, DI_NORMAL) };
         }
         let mut rc = RECT{ left: s.pad * 2 + s.group_bar_width + s.pad * 2 + s.icon_width + s.pad, top: *y, right: s.wnd_width - s.pad, bottom: *y + s.item_height};
-        unsafe { DrawTextExW(hdc, &mut WSTR::from(text).0,  &mut rc, DT_TOP | DT_VCENTER |  DT_SINGLELINE |  DT_NOPREFIX | DT_PATH_ELLIPSIS | DT_END_ELLIPSIS, None); }
+        let b_history = s.history_only(text).is_some(); // 起動していないものは淡色
+        if b_history { unsafe { SetTextColor(hdc, COLOR_GROUPBOX); } }
+        unsafe { DrawTextExW(hdc, &mut WSTR::from(&s.item_label(text)).0,  &mut rc, DT_TOP | DT_VCENTER |  DT_SINGLELINE |  DT_NOPREFIX | DT_PATH_ELLIPSIS | DT_END_ELLIPSIS, None); }
+        if b_history { unsafe { SetTextColor(hdc, COLOR_DEFAULT_TEXT); } }
         *y += s.item_height;
         *idx += 1;
     }
@@ -319,7 +588,7 @@ impl WinSortEditCtrl {
         // このコントロールはダイアログ内で利用されるためダークモードには対応しない。文字色はデフォルト(黒のまま)。
 
         let mut idx = 0;
-        let mut y = 0;
+        let mut y = -s.top_idx * s.item_height;
         for v in &s.vec_candidate_list.clone() {
             s.item_draw2(hdc, true, v, &mut idx, &mut y);
         }
@@ -353,7 +622,7 @@ impl WndMsgHandler for WinSortEditCtrl {
                     let _ = unsafe { ReleaseCapture() };
                     s.btn_state = MouseBtnState::NONE;
 
-                    if s.btn_idx_push >= 0 && s.btn_idx_hover >= 0 { s.sort_item(); }
+                    if s.btn_idx_push >= 0 && s.btn_idx_hover >= 0 && s.sort_item() { s.sel_idx = s.btn_idx_hover; } // 選択は移動した項目に追従
                     (s.btn_idx_push, s.btn_idx_hover) = (-1, -1);
                     unsafe { InvalidateRect(s.handle, None, TRUE) };
                 }
@@ -363,7 +632,7 @@ impl WndMsgHandler for WinSortEditCtrl {
                     let _ = unsafe { ReleaseCapture() };
                     s.btn_state = MouseBtnState::NONE;
 
-                    if s.btn_idx_push == s.btn_idx_hover && s.btn_idx_hover >= 0 { s.change_group(); }
+                    if s.btn_idx_push == s.btn_idx_hover && s.btn_idx_hover >= 0 { s.change_group(s.btn_idx_push); s.select(-1); }
                     (s.btn_idx_push, s.btn_idx_hover) = (-1, -1);
                     unsafe { InvalidateRect(s.handle, None, TRUE) };
                 }
@@ -378,6 +647,7 @@ impl WndMsgHandler for WinSortEditCtrl {
             }
             WM_LBUTTONDOWN => {
                 if let MouseBtnState::NONE = s.btn_state {
+                    unsafe { SetFocus(s.handle); }
                     unsafe { SetCapture(s.handle); }
                     s.mouse_handle(lparam.0 as i32 & u16::MAX as i32, lparam.0 as i32 >> u16::BITS, MouseBtnState::LDOWN);
                     s.btn_state = MouseBtnState::LDOWN;
@@ -385,6 +655,7 @@ impl WndMsgHandler for WinSortEditCtrl {
             }
             WM_MBUTTONDOWN => {
                 if let MouseBtnState::NONE = s.btn_state {
+                    unsafe { SetFocus(s.handle); }
                     unsafe { SetCapture(s.handle); }
                     s.mouse_handle(lparam.0 as i32 & u16::MAX as i32, lparam.0 as i32 >> u16::BITS, MouseBtnState::MDOWN);
                     s.btn_state = MouseBtnState::MDOWN;
@@ -398,7 +669,17 @@ impl WndMsgHandler for WinSortEditCtrl {
                 }
             }
             WM_MOUSEMOVE => {
-                s.mouse_handle(lparam.0 as i32 & u16::MAX as i32, lparam.0 as i32 >> u16::BITS, MouseBtnState::NONE);
+                let mut my = lparam.0 as i32 >> u16::BITS;
+                if s.btn_state == MouseBtnState::LDOWN && s.btn_idx_push >= 0 { // 並べ替え中に上下端を越えたら1項目ずつスクロール
+                    if my < 0 {
+                        s.scroll_to(s.top_idx - 1);
+                        my = 0;
+                    } else if my >= s.wnd_height {
+                        s.scroll_to(s.top_idx + 1);
+                        my = s.wnd_height - 1;
+                    }
+                }
+                s.mouse_handle(lparam.0 as i32 & u16::MAX as i32, my, MouseBtnState::NONE);
             }
             WM_MOUSELEAVE => {
                 let old_idx = s.btn_idx_hover;
@@ -408,10 +689,52 @@ impl WndMsgHandler for WinSortEditCtrl {
                     unsafe { InvalidateRect(s.handle, None, TRUE); }
                 }
             }
+            WM_MOUSEWHEEL if s.num_item > s.page_rows() => {
+                let delta = (wparam.0 >> u16::BITS) as i16 as i32 / WHEEL_DELTA as i32;
+                s.scroll_to(s.top_idx - delta * SORT_EDIT_WHEEL_LINES);
+                return Some(LRESULT(0))
+            }
             WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
                 unsafe { SendMessageW(GetParent(s.handle), message, wparam, lparam); }
                 return Some(LRESULT(0))
             }
+            WM_VSCROLL => {
+                let pos = match SCROLLBAR_COMMAND((wparam.0 & u16::MAX as usize) as i32) {
+                    SB_TOP => 0,
+                    SB_BOTTOM => s.num_item,
+                    SB_LINEUP => s.top_idx - 1,
+                    SB_LINEDOWN => s.top_idx + 1,
+                    SB_PAGEUP => s.top_idx - s.page_rows(),
+                    SB_PAGEDOWN => s.top_idx + s.page_rows(),
+                    SB_THUMBTRACK | SB_THUMBPOSITION => (wparam.0 >> u16::BITS) as i32,
+                    _ => s.top_idx,
+                };
+                s.scroll_to(pos);
+                return Some(LRESULT(0))
+            }
+            WM_GETDLGCODE => {
+                return Some(LRESULT((DLGC_WANTARROWS | DLGC_WANTCHARS) as isize))
+            }
+            WM_SETFOCUS | WM_KILLFOCUS => {
+                s.b_focus = message == WM_SETFOCUS;
+                unsafe { InvalidateRect(s.handle, None, TRUE); }
+            }
+            WM_KEYDOWN => {
+                let b_ctrl = unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0;
+                match VIRTUAL_KEY(wparam.0 as u16) {
+                    VK_UP if b_ctrl => s.move_item(-1),
+                    VK_DOWN if b_ctrl => s.move_item(1),
+                    VK_UP => s.key_select(s.sel_idx - 1),
+                    VK_DOWN => s.key_select(s.sel_idx + 1),
+                    VK_PRIOR => s.key_select(s.sel_idx - s.page_rows()),
+                    VK_NEXT => s.key_select(s.sel_idx.max(0) + s.page_rows()),
+                    VK_HOME => s.key_select(0),
+                    VK_END => s.key_select(s.num_item - 1),
+                    VK_SPACE => s.toggle_selected(),
+                    _ => return None,
+                }
+                return Some(LRESULT(0))
+            }
             WM_PAINT => {
                 let mut ps = PAINTSTRUCT::default();
                 let hdc = unsafe { BeginPaint(s.handle, &mut ps) };
@@ -1,7 +1,12 @@
-use windows::Win32::UI::Controls::EM_SETSEL;
+use windows::Win32::{
+    System::Com::{CoCreateInstance, CoTaskMemFree, CLSCTX_INPROC_SERVER},
+    UI::{Controls::{EM_GETSEL, EM_SETSEL}, Shell::Common::COMDLG_FILTERSPEC},
+};
 
 use super::*;
 use lib_gui_layout_container::*;
+use lib_gui_layout_define::*;
+use lib_pattern::{wildcard_segment, PathPattern};
 use lib_property::*;
 use lib_shell::*;
 use ctrl_win_sort_edit::*;
@@ -13,17 +18,78 @@ const IDC_DUMMY: isize = 0xffff;
 const IDWC_ROOT: isize = 1;
 const IDWC_H1_1: isize = 11;
 const IDWC_H1_2: isize = 12;
+const IDWC_H1_3: isize = 13;
+const IDWC_H11_1: isize = 14;
 const IDWC_H12_1: isize = 16;
+const IDWC_H13_1: isize = 17;
+const IDWC_H13_2: isize = 18;
 
 const IDC_SORT_EDIT:isize = 601;
 
 const IDC_BT_OK: isize = 1001;
 const IDC_BT_CANCEL: isize = 1002;
 const IDC_ED_FILENAME:isize = 1003;
+const IDC_LB_FLO: isize = 1004;
+const IDC_BT_SEL_MISSING: isize = 1005;
+const IDC_BT_DELETE: isize = 1006;
+const IDC_BT_EDIT: isize = 1007;
+const IDC_BT_EXPORT: isize = 1008;
+const IDC_LB_RULE: isize = 1009;
+const IDC_DDL_RULE_ACTION: isize = 1010;
+const IDC_ED_RULE_CLASS: isize = 1011;
+const IDC_ED_RULE_TITLE: isize = 1012;
+const IDC_ED_RULE_PROC: isize = 1013;
+const IDC_CB_RULE_TOOL: isize = 1014;
+const IDC_CB_RULE_OWNED: isize = 1015;
+const IDC_CB_RULE_TOPMOST: isize = 1016;
+const IDC_BT_RULE_ADD: isize = 1017;
+const IDC_BT_RULE_UPDATE: isize = 1018;
+const IDC_BT_RULE_DELETE: isize = 1019;
+const IDC_BT_RULE_UP: isize = 1020;
+const IDC_BT_RULE_DOWN: isize = 1021;
+const IDC_ED_SORT_RULE: isize = 1022;
+const IDC_BT_SORT_ADD: isize = 1023;
+const IDC_BT_SORT_UPDATE: isize = 1024;
+const IDC_BT_SORT_DELETE: isize = 1025;
+const IDC_BT_SORT_WILDCARD: isize = 1026;
+const IDC_BT_SORT_BROWSE: isize = 1027;
+const IDC_LB_SORT_MATCH: isize = 1028;
+const IDC_CB_SORT_HISTORY: isize = 1029;
+const IDC_ED_SORT_HISTORY_DAYS: isize = 1030;
+const IDC_BT_SORT_PRUNE: isize = 1031;
+const IDC_ED_SORT_FILTER: isize = 1032;
+const IDC_LB_TITLE_RULE: isize = 1033;
+const IDC_DDL_TR_ACTION: isize = 1034;
+const IDC_ED_TR_PROC: isize = 1035;
+const IDC_ED_TR_FIND: isize = 1036;
+const IDC_ED_TR_REPLACE: isize = 1037;
+const IDC_ED_TR_SAMPLE: isize = 1038;
+const IDC_ED_TR_RESULT: isize = 1039;
+const IDC_BT_TR_ADD: isize = 1040;
+const IDC_BT_TR_UPDATE: isize = 1041;
+const IDC_BT_TR_DELETE: isize = 1042;
+const IDC_BT_TR_UP: isize = 1043;
+const IDC_BT_TR_DOWN: isize = 1044;
+const IDC_LB_SLOT: isize = 1045;
+const IDC_DDL_SLOT_NO: isize = 1046;
+const IDC_ED_SLOT_PROC: isize = 1047;
+const IDC_ED_SLOT_TITLE: isize = 1048;
+const IDC_BT_SLOT_ADD: isize = 1049;
+const IDC_BT_SLOT_UPDATE: isize = 1050;
+const IDC_BT_SLOT_DELETE: isize = 1051;
+const IDC_BT_SLOT_UP: isize = 1052;
+const IDC_BT_SLOT_DOWN: isize = 1053;
 
 const WINTITLE_PROP: PCWSTR = w!("Subfolder View Property");
 const WINTITLE_RENAME: PCWSTR = w!("File Rename");
 const WINTITLE_WINSORT: PCWSTR = w!("Window Task Sort Edit");
+const WINTITLE_FLO_MANAGE: PCWSTR = w!("Folder Settings");
+const WINTITLE_WIN_FILTER: PCWSTR = w!("Window List Filter Rules");
+const WINTITLE_TITLE_RULE: PCWSTR = w!("Window List Title Rules");
+const WINTITLE_WIN_SLOT: PCWSTR = w!("Window List Slots");
+
+// 動作のドロップダウンの並び(DLG_TR_DDL_ACTIONと対応)
+const TITLE_ACTIONS: [TitleAction; 4] = [TitleAction::StripSuffix, TitleAction::StripPrefix, TitleAction::Replace, TitleAction::Swap];
 
 pub struct FileViewPropWnd {
     app: AppWeak,
@@ -33,9 +99,108 @@ pub struct FileViewPropWnd {
     parent_parsename: String,
     target_filename: String,
     ctrl_dir_prop: Option<DirPropertyPanelRc>,
+    vec_flo: Vec<(String, Vec<String>, Option<bool>)>, // フォルダ設定の一覧(パス, 保存値, 存在するか。確認中はNone)
+    flo_probe: Option<StaWorker>, // フォルダの存在確認(Dropで中断)
+    win_filter: RuleListEditor<WinFilterRule>, // ウィンドウ表示ルールの編集
+    title_rule: RuleListEditor<TitleRule>, // タイトル書き換えルールの編集
+    win_slot: RuleListEditor<WinSlot>, // ウィンドウのスロットの編集
+}
+
+pub enum DlgType { Rename, DirProperty, SortEdit, FloManage, WinFilter, TitleRule, WinSlot }
+
+// フォルダの存在確認スレッドからの結果
+struct FloProbed {
+    path: String,
+    b_exists: bool,
+}
+
+// ルール編集のコントロール(一覧, 動作のドロップダウン, 操作ボタン)
+struct RuleListIds {
+    list: isize,
+    action: isize,
+    add: isize,
+    update: isize,
+    delete: isize,
+    up: isize,
+    down: isize,
 }
 
-pub enum DlgType { Rename, DirProperty, SortEdit }
+const WF_IDS: RuleListIds = RuleListIds { list: IDC_LB_RULE, action: IDC_DDL_RULE_ACTION,
+    add: IDC_BT_RULE_ADD, update: IDC_BT_RULE_UPDATE, delete: IDC_BT_RULE_DELETE, up: IDC_BT_RULE_UP, down: IDC_BT_RULE_DOWN };
+const TR_IDS: RuleListIds = RuleListIds { list: IDC_LB_TITLE_RULE, action: IDC_DDL_TR_ACTION,
+    add: IDC_BT_TR_ADD, update: IDC_BT_TR_UPDATE, delete: IDC_BT_TR_DELETE, up: IDC_BT_TR_UP, down: IDC_BT_TR_DOWN };
+const SL_IDS: RuleListIds = RuleListIds { list: IDC_LB_SLOT, action: IDC_DDL_SLOT_NO,
+    add: IDC_BT_SLOT_ADD, update: IDC_BT_SLOT_UPDATE, delete: IDC_BT_SLOT_DELETE, up: IDC_BT_SLOT_UP, down: IDC_BT_SLOT_DOWN };
+
+// 一覧と編集欄によるルールの編集(ウィンドウ表示ルール・タイトル書き換えルール・スロットで共用)
+struct RuleListEditor<T> {
+    ids: &'static RuleListIds,
+    vec: Vec<T>, // 編集中のルール
+    to_line: fn(&T) -> String, // 一覧の1行(タブ区切り)
+    to_ctrls: fn(HWND, &T),
+    from_ctrls: fn(HWND) -> T,
+}
+
+impl<T: Default> RuleListEditor<T> {
+    fn new(ids: &'static RuleListIds, to_line: fn(&T) -> String, to_ctrls: fn(HWND, &T), from_ctrls: fn(HWND) -> T) -> Self {
+        Self { ids, vec: Vec::default(), to_line, to_ctrls, from_ctrls }
+    }
+
+    fn init(&mut self, hwnd: HWND, vec: Vec<T>, tabstops: &[i32], actions: &[PCWSTR]) {
+        unsafe { SendMessageW(get_ctrl(hwnd, self.ids.list), LB_SETTABSTOPS, WPARAM(tabstops.len()), LPARAM(tabstops.as_ptr() as isize)); }
+        let hwnd_ddl = get_ctrl(hwnd, self.ids.action);
+        for text in actions {
+            unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(text.as_ptr() as _)); }
+        }
+        (self.to_ctrls)(hwnd, &T::default());
+        self.vec = vec;
+        self.refresh(hwnd, None);
+    }
+
+    fn refresh(&self, hwnd: HWND, sel: Option<usize>) {
+        let hlist = get_ctrl(hwnd, self.ids.list);
+        unsafe { SendMessageW(hlist, LB_RESETCONTENT, WPARAM(0), LPARAM(0)); }
+        for r in &self.vec {
+            let line = (self.to_line)(r);
+            unsafe { SendMessageW(hlist, LB_ADDSTRING, WPARAM(0), LPARAM(WSTR::from(&line).PCWSTR().as_ptr() as isize)); }
+        }
+        if let Some(i) = sel {
+            unsafe { SendMessageW(hlist, LB_SETCURSEL, WPARAM(i), LPARAM(0)); }
+        }
+    }
+
+    fn selected(&self, hwnd: HWND) -> Option<usize> {
+        let i = unsafe { SendMessageW(get_ctrl(hwnd, self.ids.list), LB_GETCURSEL, WPARAM(0), LPARAM(0)) }.0;
+        if 0 <= i && (i as usize) < self.vec.len() { Some(i as usize) } else { None }
+    }
+
+    // 一覧で選んだルールを編集欄へ
+    fn select_changed(&self, hwnd: HWND) {
+        if let Some(i) = self.selected(hwnd) { (self.to_ctrls)(hwnd, &self.vec[i]); }
+    }
+
+    // 追加・更新・削除・上下移動。先頭から順に照合・適用するため、並び順がそのまま優先順位になる
+    fn command(&mut self, hwnd: HWND, id: isize) {
+        if id == self.ids.add {
+            self.vec.push((self.from_ctrls)(hwnd));
+            self.refresh(hwnd, Some(self.vec.len() - 1));
+            return
+        }
+        let Some(i) = self.selected(hwnd) else { return };
+        if id == self.ids.update {
+            self.vec[i] = (self.from_ctrls)(hwnd);
+            self.refresh(hwnd, Some(i));
+        } else if id == self.ids.delete {
+            self.vec.remove(i);
+            self.refresh(hwnd, if self.vec.is_empty() { None } else { Some(i.min(self.vec.len() - 1)) });
+        } else if id == self.ids.up || id == self.ids.down {
+            let j = if id == self.ids.up { i.checked_sub(1) } else { Some(i + 1).filter(|&j| j < self.vec.len()) };
+            let Some(j) = j else { return };
+            self.vec.swap(i, j);
+            self.refresh(hwnd, Some(j));
+        }
+    }
+}
 
 pub type FileViewPropWndWeak= Weak<FileViewPropWnd>;
 pub type FileViewPropWndRc = Rc<FileViewPropWnd>;
@@ -60,6 +225,11 @@ impl FileViewPropWnd {
             parent_parsename: parent_parsename,
             target_filename: target_filename,
             ctrl_dir_prop: None,
+            vec_flo: Vec::default(),
+            flo_probe: None,
+            win_filter: RuleListEditor::new(&WF_IDS, Self::win_filter_line, Self::win_filter_to_ctrls, Self::win_filter_from_ctrls),
+            title_rule: RuleListEditor::new(&TR_IDS, Self::title_rule_line, Self::title_rule_to_ctrls, Self::title_rule_from_ctrls),
+            win_slot: RuleListEditor::new(&SL_IDS, Self::win_slot_line, Self::win_slot_to_ctrls, Self::win_slot_from_ctrls),
         });
 
         let window_class = w!("fileview_property_window");
@@ -101,10 +271,12 @@ impl FileViewPropWnd {
     }
 
     fn view_adjust_size_root(&mut self, c_root: &mut Rc<WindowContainer>) {
-        // 必要なクライアントサイズを決定する
+        // 必要なクライアントサイズを決定する(基準サイズと、縮小可能な要素を縮めた最小サイズ)
         c_root.recalc_layout();
-        let (w_rc, h_rc) = c_root.get_field_size();
+        let (w_rc, h_rc) = c_root.pref_layout();
+        let (w_rc_min, h_rc_min) = c_root.check_layout();
         let (mut win_w, mut win_h) = adjust_window_rect(self.handle, w_rc, h_rc);
+        let (win_w_min, win_h_min) = adjust_window_rect(self.handle, w_rc_min, h_rc_min);
 
         // 画面サイズを取得
         let mut pt = POINT::default();
@@ -116,20 +288,19 @@ impl FileViewPropWnd {
             GetMonitorInfoW(h, &mut mi);
         }
 
-        // ウィンドウサイズと比較(超過分を計算)。クライアントサイズを縮小させる(スクロール設定する)
+        // ウィンドウサイズと比較(超過分を計算)。クライアントサイズを縮小させる
+        // 最小サイズまで縮めても収まらない方向はスクロールさせるため、スクロールバーの分だけ反対方向を広げる
         let (mut b_add_vscr, mut b_add_hscr) = (false, false);
         for _ in 0..2 { // 初回の計算で一方にスクロールバーが生じ、もともと収まっていた他方が影響を受け両方を表示する必要があるケースへの対応
             let (w_over, h_over) = (win_w - (mi.rcWork.right - mi.rcWork.left), win_h - (mi.rcWork.bottom - mi.rcWork.top));
-            if h_over > 0 {
-                win_h -= h_over;
-                let w_vscrbar = if !b_add_vscr { sys_metrics(self.handle, SM_CXVSCROLL) } else { 0 };
-                win_w += w_vscrbar;
+            if h_over > 0 { win_h -= h_over; }
+            if w_over > 0 { win_w -= w_over; }
+            if !b_add_vscr && win_h < win_h_min {
+                win_w += sys_metrics(self.handle, SM_CXVSCROLL);
                 b_add_vscr = true;
             }
-            if w_over > 0 {
-                win_w -= w_over;
-                let h_hscrbar = if !b_add_hscr { sys_metrics(self.handle, SM_CYHSCROLL) } else { 0 };
-                win_h += h_hscrbar;
+            if !b_add_hscr && win_w < win_w_min {
+                win_h += sys_metrics(self.handle, SM_CYHSCROLL);
                 b_add_hscr = true;
             }
         }
@@ -143,22 +314,22 @@ impl FileViewPropWnd {
     }
 
     fn view_init_property(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
-        unsafe { SetWindowTextW(self.handle, WINTITLE_PROP) }?;
+        // プロファイル別の項目はプロファイル名をタイトルに出す(編集対象はそのプロファイルの設定)
+        let (_, profile) = PropertyHolder::split_profile_path(&self.parent_parsename);
+        if profile.is_empty() {
+            unsafe { SetWindowTextW(self.handle, WINTITLE_PROP) }?;
+        } else {
+            let title = format!("{} - {}", unsafe { WINTITLE_PROP.to_string() }.unwrap_or_default(), profile);
+            unsafe { SetWindowTextW(self.handle, WSTR::from(&title).PCWSTR()) }?;
+        }
 
         let hfont = self.hfont.0;
-        let (w, h) = text_size(self.handle, hfont, CHAR_FONT_WIDTH_MEASURE);
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, hfont, &dlg_def_property());
 
         // プロパティパネル
-        let mut c1 = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::LEFT, HeightAuto::FIX, IDWC_H1_1);
+        let mut c1 = dlg_cont(c_root.handle(), IDWC_H1_1);
         self.ctrl_dir_prop = Some(DirPropertyPanel::init(&mut c1, hfont, false));
 
-        // 設定・キャンセルボタン
-        let mut c2: Rc<WindowContainer> = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::RIGHT, HeightAuto::FIX, IDWC_H1_2);
-        init_item_hstack(&mut c2, hfont, 0, h * 2, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut c2, hfont, w * 5, h * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_FV_BT_TEXT_APPLY, WINDOW_STYLE::default() | WS_TABSTOP, IDC_BT_OK);
-        init_item_hstack(&mut c2, hfont, w * 5, h * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_FV_BT_TEXT_CANCEL, WINDOW_STYLE::default() | WS_TABSTOP, IDC_BT_CANCEL);
-        init_item_hstack(&mut c2, hfont, 0, h * 2, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-
         // 初期値セット
         let v = PropertyHolder::load_filesort_param(&self.parent_parsename);
         let mut p = PropertyHolder::parse_string(if v.len() > 0 { &v[0] } else { "" }); // レジストリに値が無ければデフォルト値を得る
@@ -166,12 +337,12 @@ impl FileViewPropWnd {
         self.ctrl_dir_prop.as_mut().unwrap().ctrl_setvalue_dir_property(&p);
 
         self.view_adjust_size_root(&mut c_root);
-        unsafe { SetFocus(get_ctrl(c2.handle(), IDC_BT_OK)); }
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_BT_OK)); }
         Ok(())
     }
 
     fn apply_property(&mut self) {
-        let mut p = self.ctrl_dir_prop.as_mut().unwrap().ctrl_getvalue_dir_property(true);
+        let mut p = self.ctrl_dir_prop.as_mut().unwrap().ctrl_getvalue_dir_property(&HotkeyType::ListLauncher);
         p.path = String::default();
         let _ = PropertyHolder::update_dir_param(&self.parent_parsename, p.to_string());
     }
@@ -179,30 +350,14 @@ impl FileViewPropWnd {
     fn view_init_rename(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
         unsafe { SetWindowTextW(self.handle, WINTITLE_RENAME) }?;
 
-        let hfont = self.hfont.0;
-        let (cw, ch) = text_size(self.handle, hfont, CHAR_FONT_WIDTH_MEASURE);
-
-        let mut ce = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H1_1);
-        let mut cb = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::RIGHT, HeightAuto::FIX, IDWC_H1_2);
-        // ce.set_sub_proc(Some(Box::new(wnd_instance::<Self>(self.handle)))); // コントロールのsubclassを使う場合
-
-        // ファイル名テキストボックス
-        init_item_hstack(&mut ce, hfont, 0, ch * 3, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut ce, hfont, -1, -1, WidthAuto::FIX, AlignV::CENTER, "STATIC", DLG_FV_ST_RENAME, WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut ce, hfont, cw * 15, ch, WidthAuto::FIX, AlignV::CENTER, "EDIT", &self.target_filename.clone(), WS_TABSTOP, IDC_ED_FILENAME);
-        init_item_hstack(&mut ce, hfont, 0, ch * 3, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-
-        // ＯＫ・キャンセルボタン
-        let mut cb2 = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut cb, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H12_1);
-        init_item_hstack(&mut cb2, hfont, 0, ch * 3, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut cb2, hfont, cw * 5, ch * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_FV_BT_TEXT_OK, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, IDC_BT_OK);
-        init_item_hstack(&mut cb2, hfont, cw * 5, ch * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_FV_BT_TEXT_CANCEL, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, IDC_BT_CANCEL);
-        init_item_hstack(&mut cb2, hfont, 0, ch * 3, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, self.hfont.0, &dlg_def_rename());
+        // dlg_cont(c_root.handle(), IDWC_H1_1).set_sub_proc(Some(Box::new(wnd_instance::<Self>(self.handle)))); // コントロールのsubclassを使う場合
 
         self.view_adjust_size_root(&mut c_root);
 
         // 初期値セット
-        let hedit = get_ctrl(ce.handle(), IDC_ED_FILENAME);
+        let hedit = get_ctrl(c_root.handle(), IDC_ED_FILENAME);
+        set_ctrl_text(c_root.handle(), IDC_ED_FILENAME, &self.target_filename);
         unsafe { SetFocus(hedit); }
         let idx = self.target_filename.chars().rev().enumerate().find(|(_, c)| '.'.eq(c)).or(Some((usize::MAX, '.'))).unwrap().0 as isize;
         unsafe { SendMessageW(hedit,  EM_SETSEL, WPARAM(0), LPARAM(self.target_filename.chars().count() as isize - idx - 1)); }
@@ -212,50 +367,395 @@ impl FileViewPropWnd {
 
     fn rename_shell_item(&mut self) {
         let newname = get_ctrl_text(self.handle, IDC_ED_FILENAME);
-        let _ = ObjectHolder::set_object_name(&self.parent_parsename, &self.target_filename, &newname);
+        if let Ok(parsename) = ObjectHolder::set_object_name(&self.parent_parsename, &self.target_filename, &newname) {
+            let _ = PropertyHolder::rename_filesort_param(&self.parent_parsename, &self.target_filename, &parsename);
+        }
     }
 
     fn view_init_sort_edit(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
         unsafe { SetWindowTextW(self.handle, WINTITLE_WINSORT) }?;
 
         let hfont = self.hfont.0;
-        let (cw, ch) = text_size(self.handle, hfont, CHAR_FONT_WIDTH_MEASURE);
-
-        let mut ce = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::FILL, HeightAuto::AUTO, IDWC_H1_1);
-        let mut cb = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::RIGHT, HeightAuto::FIX, IDWC_H1_2);
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, hfont, &dlg_def_sort_edit());
+        let mut ce = dlg_cont(c_root.handle(), IDWC_H1_1);
 
         // ウィンドウソート順の編集コントロール
         let sortlist = self.app().main_wnd().vec_window_sortlist.iter().map(|v| match v {
                 WinSortList::WILDCARD(i) => i.clone(),
                 WinSortList::IMGFILE(i) => i.clone(),
             }).collect::<Vec<_>>();
-        let ctrl = WinSortEditCtrl::init(ce.handle(), hfont, sortlist, IDC_SORT_EDIT).upgrade().unwrap();
+        let history = self.app().main_wnd().vec_proc_history.clone();
+        let ctrl = WinSortEditCtrl::init(ce.handle(), hfont, sortlist, history, Some(PROC_HISTORY_DAYS_DEFAULT), IDC_SORT_EDIT).upgrade().unwrap();
         let (w, h) = ctrl.get_size();
         ce.vstack(ctrl.handle(), w, h, 2, 0, 0, false, AlignH::FILL, HeightAuto::AUTO);
 
-        // ＯＫ・キャンセルボタン
-        let mut cb2 = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut cb, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H12_1);
-        init_item_hstack(&mut cb2, hfont, 0, ch * 3, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut cb2, hfont, cw * 5, ch * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_FV_BT_TEXT_OK, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, IDC_BT_OK);
-        init_item_hstack(&mut cb2, hfont, cw * 5, ch * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_FV_BT_TEXT_CANCEL, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, IDC_BT_CANCEL);
-        init_item_hstack(&mut cb2, hfont, 0, ch * 3, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
+        set_ctrl_enable(self.handle, IDC_BT_SORT_UPDATE, false);
+        set_ctrl_enable(self.handle, IDC_BT_SORT_DELETE, false);
+        set_ctrl_checked(self.handle, IDC_CB_SORT_HISTORY, true);
+        set_ctrl_int(self.handle, IDC_ED_SORT_HISTORY_DAYS, PROC_HISTORY_DAYS_DEFAULT as isize);
 
         self.view_adjust_size_root(&mut c_root);
 
-        unsafe { SetFocus(get_ctrl(cb2.handle(), IDC_BT_OK)); }
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_BT_OK)); }
+        Ok(())
+    }
+
+    fn sort_edit_ctrl(&self) -> WinSortEditCtrlRc {
+        wnd_instance::<WinSortEditCtrl>(get_ctrl(self.handle, IDC_SORT_EDIT)).upgrade().unwrap()
+    }
+
+    // 一覧で選択した項目を編集欄へ取り込む(一致一覧はEN_CHANGEで更新される)
+    fn sort_rule_selected(&self) {
+        let sel = self.sort_edit_ctrl().get_selected();
+        let b_sort = sel.as_ref().is_some_and(|(_, b)| *b);
+        set_ctrl_text(self.handle, IDC_ED_SORT_RULE, sel.as_ref().map_or("", |(v, _)| v.as_str()));
+        set_ctrl_enable(self.handle, IDC_BT_SORT_UPDATE, b_sort);
+        set_ctrl_enable(self.handle, IDC_BT_SORT_DELETE, b_sort);
+    }
+
+    // 編集欄のルールに一致する実行中のプロセスを一覧表示する(より具体的な他のルールが優先されるものには印を付ける)
+    fn sort_match_refresh(&self) {
+        let hlist = get_ctrl(self.handle, IDC_LB_SORT_MATCH);
+        unsafe { SendMessageW(hlist, LB_RESETCONTENT, WPARAM(0), LPARAM(0)); }
+        let add = |line: &str| unsafe { SendMessageW(hlist, LB_ADDSTRING, WPARAM(0), LPARAM(WSTR::from(line).PCWSTR().as_ptr() as isize)); };
+
+        let text = get_ctrl_text(self.handle, IDC_ED_SORT_RULE).trim().to_string();
+        if text.is_empty() { return }
+        let pat = PathPattern::parse(&text);
+        if let PathPattern::Invalid = pat { add(DLG_SE_INVALID_PATTERN); return }
+
+        let ctrl = self.sort_edit_ctrl();
+        let sortlist = ctrl.get_sortlist();
+        for v in ctrl.get_running().iter().filter(|v| pat.is_match(v)) {
+            let b_other = sortlist.contains(&text) && PropertyHolder::contains_procimg_list(&mut sortlist.iter(), v).is_some_and(|r| r != text);
+            add(&format!("{}{}", if b_other { DLG_SE_OTHER_RULE_MARK } else { "" }, v));
+        }
+    }
+
+    fn sort_rule_apply(&self, id: isize) {
+        let text = get_ctrl_text(self.handle, IDC_ED_SORT_RULE).trim().to_string();
+        let mut ctrl = self.sort_edit_ctrl();
+        match id {
+            IDC_BT_SORT_ADD => ctrl.add_rule(&text),
+            IDC_BT_SORT_UPDATE => ctrl.update_rule(&text),
+            _ => ctrl.delete_rule(),
+        }
+        self.sort_edit_relayout();
+    }
+
+    // 項目数が変わるとコントロールの大きさも変わるため配置しなおす
+    fn sort_edit_relayout(&self) {
+        let ctrl = self.sort_edit_ctrl();
+        let (w, h) = ctrl.get_size();
+        dlg_cont(self.handle, IDWC_H1_1).set_vstack_param(ctrl.handle(), w, h, 2, 0, 0, false, AlignH::FILL, HeightAuto::AUTO);
+        dlg_cont(self.handle, IDWC_ROOT).recalc_layout();
+    }
+
+    fn sort_history_days(&self) -> u64 {
+        get_ctrl_int(self.handle, IDC_ED_SORT_HISTORY_DAYS).max(1) as u64
+    }
+
+    // 履歴を候補に出すかと、その期間の変更を反映する
+    fn sort_history_filter(&self) {
+        let b_show = get_ctrl_checked(self.handle, IDC_CB_SORT_HISTORY);
+        set_ctrl_enable(self.handle, IDC_ED_SORT_HISTORY_DAYS, b_show);
+        let history = self.app().main_wnd().vec_proc_history.clone();
+        self.sort_edit_ctrl().set_history(history, b_show.then_some(self.sort_history_days()));
+        self.sort_edit_relayout();
+    }
+
+    // 期間内に見かけていないプロセスと、ファイルが無くなったプロセスを履歴から削除する(AppUserModelIDは期間のみで判定)
+    fn sort_history_prune(&self) {
+        if unsafe { MessageBoxW(self.handle, DLG_SE_CONFIRM_PRUNE, WINTITLE_WINSORT, MB_OKCANCEL) } != IDOK { return }
+        let mut main_wnd = self.app().main_wnd();
+        ProcHistory::prune(&mut main_wnd.get_mut().vec_proc_history, ProcHistory::now(), self.sort_history_days(),
+            |p| lib_window::WindowInfo::is_aumid(p) || std::path::Path::new(p).exists());
+        PropertyHolder::store_proc_history(&main_wnd.vec_proc_history);
+        self.sort_history_filter();
+    }
+
+    // 編集欄の選択範囲(未選択ならキャレット位置のフォルダ名)を*に置き換える
+    fn sort_rule_wildcard(&self) {
+        let hedit = get_ctrl(self.handle, IDC_ED_SORT_RULE);
+        let (mut start, mut end) = (0u32, 0u32);
+        unsafe { SendMessageW(hedit, EM_GETSEL, WPARAM(&mut start as *mut _ as usize), LPARAM(&mut end as *mut _ as isize)); }
+        let text = wildcard_segment(&get_ctrl_text(self.handle, IDC_ED_SORT_RULE), start as usize, end as usize);
+        set_ctrl_text(self.handle, IDC_ED_SORT_RULE, &text);
+        unsafe { SetFocus(hedit); }
+    }
+
+    // 起動していないプログラムもファイル選択で指定できる
+    fn sort_rule_browse(&self) -> Result<()> {
+        let dlg: IFileOpenDialog = unsafe { CoCreateInstance(&FileOpenDialog, None, CLSCTX_INPROC_SERVER) }?;
+        let filter = [COMDLG_FILTERSPEC { pszName: DLG_SE_FILTER_EXE, pszSpec: w!("*.exe") }];
+        unsafe {
+            dlg.SetFileTypes(&filter)?;
+            dlg.SetOptions(dlg.GetOptions()? | FOS_FILEMUSTEXIST | FOS_FORCEFILESYSTEM)?;
+            if dlg.Show(self.handle).is_err() { return Ok(()) } // キャンセル
+            let path = dlg.GetResult()?.GetDisplayName(SIGDN_FILESYSPATH)?;
+            set_ctrl_text(self.handle, IDC_ED_SORT_RULE, &path.to_string().unwrap_or_default());
+            CoTaskMemFree(Some(path.0 as _));
+        }
         Ok(())
     }
 
     fn apply_sort_setting(&mut self) {
-        let hctrl = get_ctrl(self.handle, IDC_SORT_EDIT);
-        let sortedit = wnd_instance::<WinSortEditCtrl>(hctrl).upgrade().unwrap();
-        let after = sortedit.get_sortlist();
+        let after = self.sort_edit_ctrl().get_sortlist();
         let sortlist = after.into_iter().map(|v|
-            if v.contains("*") { WinSortList::WILDCARD(v) } else { WinSortList::IMGFILE(v) }).collect::<Vec<_>>();
+            if PathPattern::is_pattern(&v) { WinSortList::WILDCARD(v) } else { WinSortList::IMGFILE(v) }).collect::<Vec<_>>();
         self.app().main_wnd().get_mut().vec_window_sortlist = sortlist;
         PropertyHolder::store_winsort_param(&self.app().main_wnd().vec_window_sortlist);
     }
 
+    fn view_init_flo_manage(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
+        unsafe { SetWindowTextW(self.handle, WINTITLE_FLO_MANAGE) }?;
+
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, self.hfont.0, &dlg_def_flo_manage());
+        let tabstops = [24i32, 96]; // ダイアログ単位
+        unsafe { SendMessageW(get_ctrl(self.handle, IDC_LB_FLO), LB_SETTABSTOPS, WPARAM(tabstops.len()), LPARAM(tabstops.as_ptr() as isize)); }
+        self.flo_list_refresh();
+
+        self.view_adjust_size_root(&mut c_root);
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_LB_FLO)); }
+        Ok(())
+    }
+
+    // 保存済みフォルダ設定を読み直してリストへ反映する。
+    // 存在確認はオフラインのネットワークパスなどで待たされるため別スレッドで行い、結果が届いた行に印を付ける
+    fn flo_list_refresh(&mut self) {
+        let hlist = get_ctrl(self.handle, IDC_LB_FLO);
+        self.vec_flo = PropertyHolder::enum_filesort_param().into_iter().map(|(path, v)| (path, v, None)).collect();
+        self.vec_flo.sort_by_key(|a| a.0.to_lowercase());
+
+        unsafe { SendMessageW(hlist, LB_RESETCONTENT, WPARAM(0), LPARAM(0)); }
+        let mut w_max = 0;
+        for i in 0..self.vec_flo.len() {
+            let line = self.flo_line(i);
+            w_max = w_max.max(text_size(hlist, self.hfont.0, &format!("{}{}", DLG_FM_MISSING_MARK, line.replace('\t', "    "))).0);
+            unsafe { SendMessageW(hlist, LB_ADDSTRING, WPARAM(0), LPARAM(WSTR::from(&line).PCWSTR().as_ptr() as isize)); }
+        }
+        unsafe { SendMessageW(hlist, LB_SETHORIZONTALEXTENT, WPARAM(w_max as usize + 8), LPARAM(0)); }
+
+        let mut paths = self.vec_flo.iter().map(|(path, _, _)| PropertyHolder::split_profile_path(path).0.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup(); // プロファイル別の項目はフォルダと一緒に確認する
+        let hwnd = self.handle;
+        self.flo_probe = Some(StaWorker::spawn(move |cancel| {
+            for path in paths {
+                if cancel.load(std::sync::atomic::Ordering::Relaxed) { break }
+                let b_exists = ObjectHolder::exists(&path);
+                post_boxed(hwnd, WMU_FLO_PROBED, WPARAM(0), Box::new(FloProbed { path, b_exists }));
+            }
+        }));
+    }
+
+    fn flo_line(&self, i: usize) -> String {
+        let (path, v, b_exists) = &self.vec_flo[i];
+        let param = PropertyHolder::parse_string(v.first().map(|s| s.as_str()).unwrap_or("")).to_string();
+        let mark = if *b_exists == Some(false) { DLG_FM_MISSING_MARK } else { "" };
+        format!("{}\t{}\t{}{}", v.len().saturating_sub(1), param, mark, path)
+    }
+
+    // 存在確認の結果を該当する行(フォルダとそのプロファイル別の項目)へ反映する。選択状態は保つ
+    fn flo_probed(&mut self, r: FloProbed) {
+        let hlist = get_ctrl(self.handle, IDC_LB_FLO);
+        for i in 0..self.vec_flo.len() {
+            if !PropertyHolder::split_profile_path(&self.vec_flo[i].0).0.eq(&r.path) { continue }
+            self.vec_flo[i].2 = Some(r.b_exists);
+            if r.b_exists { continue } // 表示は変わらない
+            let b_sel = unsafe { SendMessageW(hlist, LB_GETSEL, WPARAM(i), LPARAM(0)) }.0 > 0;
+            unsafe {
+                SendMessageW(hlist, LB_DELETESTRING, WPARAM(i), LPARAM(0));
+                SendMessageW(hlist, LB_INSERTSTRING, WPARAM(i), LPARAM(WSTR::from(&self.flo_line(i)).PCWSTR().as_ptr() as isize));
+                SendMessageW(hlist, LB_SETSEL, WPARAM(b_sel as usize), LPARAM(i as isize));
+            }
+        }
+    }
+
+    fn flo_selected(&self) -> Vec<usize> {
+        let hlist = get_ctrl(self.handle, IDC_LB_FLO);
+        (0..self.vec_flo.len()).filter(|&i| unsafe { SendMessageW(hlist, LB_GETSEL, WPARAM(i), LPARAM(0)) }.0 > 0).collect()
+    }
+
+    fn flo_select_missing(&self) {
+        let hlist = get_ctrl(self.handle, IDC_LB_FLO);
+        for (i, (_, _, b_exists)) in self.vec_flo.iter().enumerate() { // 確認中の項目は選ばない
+            unsafe { SendMessageW(hlist, LB_SETSEL, WPARAM((*b_exists == Some(false)) as usize), LPARAM(i as isize)); }
+        }
+    }
+
+    fn flo_delete(&mut self) {
+        let sel = self.flo_selected();
+        if sel.is_empty() { return }
+        if unsafe { MessageBoxW(self.handle, DLG_FM_CONFIRM_DELETE, WINTITLE_FLO_MANAGE, MB_OKCANCEL) } != IDOK { return }
+        for i in sel {
+            let _ = PropertyHolder::delete_filesort_param(&self.vec_flo[i].0);
+        }
+        self.flo_list_refresh();
+    }
+
+    // 選択中の先頭のフォルダについてプロパティダイアログを開く(このダイアログは閉じられる)。
+    // プロファイル別の項目は"パス|プロファイル"のまま渡し、そのプロファイルの保存値を編集する
+    fn flo_edit(&self) -> Result<()> {
+        let Some(&i) = self.flo_selected().first() else { return Ok(()) };
+        self.app().main_wnd().get_mut().lauch_propery_dirpath = self.vec_flo[i].0.clone();
+        unsafe { PostMessageW(self.app().main_wnd().handle(), WMU_DIR_PROPERTY, WPARAM(0), LPARAM(0)) }
+    }
+
+    // 選択中(未選択なら全部)の設定をテキストとしてクリップボードへ
+    fn flo_export(&self) -> Result<()> {
+        let sel = self.flo_selected();
+        let idxs = if sel.is_empty() { (0..self.vec_flo.len()).collect() } else { sel };
+        let text = idxs.into_iter().map(|i| {
+            let (path, v, _) = &self.vec_flo[i];
+            format!("[{}]\r\n{}\r\n", path, v.join("\r\n"))
+        }).collect::<Vec<_>>().join("\r\n");
+        set_clipboard_text(self.handle, &text)
+    }
+
+    fn view_init_win_filter(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
+        unsafe { SetWindowTextW(self.handle, WINTITLE_WIN_FILTER) }?;
+
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, self.hfont.0, &dlg_def_win_filter());
+        let vec = self.app().main_wnd().vec_window_filter.clone();
+        self.win_filter.init(self.handle, vec, &[16, 96, 192, 320], &DLG_WF_DDL_ACTION); // タブ位置はダイアログ単位
+
+        self.view_adjust_size_root(&mut c_root);
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_LB_RULE)); }
+        Ok(())
+    }
+
+    fn win_filter_line(r: &WinFilterRule) -> String {
+        let any = |s: &str| if s.is_empty() { "*".to_string() } else { s.to_string() };
+        format!("{}\t{}\t{}\t{}\t{}", if r.b_include { "+" } else { "-" },
+            any(&r.class), any(&r.title), any(&r.proc_img), r.style_string())
+    }
+
+    fn win_filter_to_ctrls(hwnd: HWND, r: &WinFilterRule) {
+        set_ctrl_cursel(hwnd, IDC_DDL_RULE_ACTION, r.b_include as usize);
+        set_ctrl_text(hwnd, IDC_ED_RULE_CLASS, &r.class);
+        set_ctrl_text(hwnd, IDC_ED_RULE_TITLE, &r.title);
+        set_ctrl_text(hwnd, IDC_ED_RULE_PROC, &r.proc_img);
+        set_ctrl_tristate(hwnd, IDC_CB_RULE_TOOL, r.tool);
+        set_ctrl_tristate(hwnd, IDC_CB_RULE_OWNED, r.owned);
+        set_ctrl_tristate(hwnd, IDC_CB_RULE_TOPMOST, r.topmost);
+    }
+
+    fn win_filter_from_ctrls(hwnd: HWND) -> WinFilterRule {
+        let text = |id| get_ctrl_text(hwnd, id).replace('\t', " ").trim().to_string(); // タブは保存形式の区切り
+        WinFilterRule {
+            b_include: get_ctrl_cursel(hwnd, IDC_DDL_RULE_ACTION) == 1,
+            class: text(IDC_ED_RULE_CLASS),
+            title: text(IDC_ED_RULE_TITLE),
+            proc_img: text(IDC_ED_RULE_PROC),
+            tool: get_ctrl_tristate(hwnd, IDC_CB_RULE_TOOL),
+            owned: get_ctrl_tristate(hwnd, IDC_CB_RULE_OWNED),
+            topmost: get_ctrl_tristate(hwnd, IDC_CB_RULE_TOPMOST),
+        }
+    }
+
+    fn apply_win_filter(&mut self) {
+        PropertyHolder::store_winfilter_param(&self.win_filter.vec);
+        self.app().main_wnd().get_mut().vec_window_filter = std::mem::take(&mut self.win_filter.vec);
+    }
+
+    fn view_init_title_rule(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
+        unsafe { SetWindowTextW(self.handle, WINTITLE_TITLE_RULE) }?;
+
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, self.hfont.0, &dlg_def_title_rule());
+        let vec = self.app().main_wnd().vec_title_rule.clone();
+        self.title_rule.init(self.handle, vec, &[48, 160, 256], &DLG_TR_DDL_ACTION); // タブ位置はダイアログ単位
+
+        self.view_adjust_size_root(&mut c_root);
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_LB_TITLE_RULE)); }
+        Ok(())
+    }
+
+    fn title_rule_line(r: &TitleRule) -> String {
+        let any = |s: &str| if s.is_empty() { "*".to_string() } else { s.to_string() };
+        format!("{}\t{}\t{}\t{}", r.action_name(), any(&r.proc_img), r.find, r.replace)
+    }
+
+    fn title_rule_to_ctrls(hwnd: HWND, r: &TitleRule) {
+        set_ctrl_cursel(hwnd, IDC_DDL_TR_ACTION, TITLE_ACTIONS.iter().position(|a| *a == r.action).unwrap_or(0));
+        set_ctrl_text(hwnd, IDC_ED_TR_PROC, &r.proc_img);
+        set_ctrl_text(hwnd, IDC_ED_TR_FIND, &r.find);
+        set_ctrl_text(hwnd, IDC_ED_TR_REPLACE, &r.replace);
+        Self::title_rule_preview(hwnd);
+    }
+
+    fn title_rule_from_ctrls(hwnd: HWND) -> TitleRule {
+        let text = |id| get_ctrl_text(hwnd, id).replace('\t', " "); // タブは保存形式の区切り。前後の空白は検索文字列の一部なので残す
+        TitleRule {
+            action: usize::try_from(get_ctrl_cursel(hwnd, IDC_DDL_TR_ACTION)).ok()
+                .and_then(|i| TITLE_ACTIONS.get(i)).copied().unwrap_or_default(),
+            proc_img: text(IDC_ED_TR_PROC).trim().to_string(),
+            find: text(IDC_ED_TR_FIND),
+            replace: text(IDC_ED_TR_REPLACE),
+        }
+    }
+
+    // 編集中のルールだけを見本のタイトルに適用して結果を表示する
+    fn title_rule_preview(hwnd: HWND) {
+        let sample = get_ctrl_text(hwnd, IDC_ED_TR_SAMPLE);
+        let r = Self::title_rule_from_ctrls(hwnd);
+        let text = if r.action == TitleAction::Replace && !r.find.is_empty() && lib_pattern::Regex::parse(&r.find).is_none() {
+            DLG_SE_INVALID_PATTERN.to_string()
+        } else {
+            TitleRule::rewrite(&[TitleRule { proc_img: String::default(), ..r }], "", "", &sample)
+        };
+        set_ctrl_text(hwnd, IDC_ED_TR_RESULT, &text);
+    }
+
+    fn apply_title_rule(&mut self) {
+        PropertyHolder::store_titlerule_param(&self.title_rule.vec);
+        self.app().main_wnd().get_mut().vec_title_rule = std::mem::take(&mut self.title_rule.vec);
+    }
+
+    fn view_init_win_slot(&mut self, mut c_root: Rc<WindowContainer>) -> Result<()> {
+        unsafe { SetWindowTextW(self.handle, WINTITLE_WIN_SLOT) }?;
+
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, self.hfont.0, &dlg_def_win_slot());
+        let vec = self.app().main_wnd().vec_win_slot.clone();
+        self.win_slot.init(self.handle, vec, &[40, 192], &DLG_SL_DDL_SLOT); // タブ位置はダイアログ単位
+
+        self.view_adjust_size_root(&mut c_root);
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_LB_SLOT)); }
+        Ok(())
+    }
+
+    fn win_slot_line(s: &WinSlot) -> String {
+        let any = |s: &str| if s.is_empty() { "*".to_string() } else { s.to_string() };
+        format!("{}\t{}\t{}", s.slot, s.proc_img, any(&s.title))
+    }
+
+    fn win_slot_to_ctrls(hwnd: HWND, s: &WinSlot) {
+        set_ctrl_cursel(hwnd, IDC_DDL_SLOT_NO, s.slot.saturating_sub(1) as usize);
+        set_ctrl_text(hwnd, IDC_ED_SLOT_PROC, &s.proc_img);
+        set_ctrl_text(hwnd, IDC_ED_SLOT_TITLE, &s.title);
+    }
+
+    // 割り当て中のウィンドウは持たない(適用後の一覧の更新で割り当て直す)
+    fn win_slot_from_ctrls(hwnd: HWND) -> WinSlot {
+        let text = |id| get_ctrl_text(hwnd, id).replace('\t', " "); // タブは保存形式の区切り
+        WinSlot {
+            slot: usize::try_from(get_ctrl_cursel(hwnd, IDC_DDL_SLOT_NO)).map_or(1, |i| i as u32 + 1),
+            proc_img: text(IDC_ED_SLOT_PROC).trim().to_string(),
+            title: text(IDC_ED_SLOT_TITLE),
+            hwnd: 0,
+        }
+    }
+
+    // プロセスが空のものは保存できないので除き、同じ番号は先のものを残す
+    fn apply_win_slot(&mut self) {
+        let mut slots = std::mem::take(&mut self.win_slot.vec);
+        slots.retain(|s| !s.proc_img.is_empty());
+        slots.sort_by_key(|s| s.slot);
+        slots.dedup_by_key(|s| s.slot);
+        PropertyHolder::store_winslot_param(&slots);
+        self.app().main_wnd().get_mut().vec_win_slot = slots;
+    }
+
     fn ctrl_resize(&self, w: i32, h: i32) -> Result<()> {
         let hcont = unsafe { GetDlgItem(self.handle, IDWC_ROOT as i32) };
         unsafe { MoveWindow(hcont, 0, 0, w, h, TRUE) }?;
@@ -310,6 +810,14 @@ impl WndMsgHandler for FileViewPropWnd {
                     self.view_init_rename(c_root.clone())
                 } else if let DlgType::DirProperty = self.dlg_type {
                     self.view_init_property(c_root.clone())
+                } else if let DlgType::FloManage = self.dlg_type {
+                    self.view_init_flo_manage(c_root.clone())
+                } else if let DlgType::WinFilter = self.dlg_type {
+                    self.view_init_win_filter(c_root.clone())
+                } else if let DlgType::TitleRule = self.dlg_type {
+                    self.view_init_title_rule(c_root.clone())
+                } else if let DlgType::WinSlot = self.dlg_type {
+                    self.view_init_win_slot(c_root.clone())
                 } else { // SortEdit
                     self.view_init_sort_edit(c_root.clone())
                 };
@@ -320,11 +828,34 @@ impl WndMsgHandler for FileViewPropWnd {
             WM_ACTIVATE | WM_SETFOCUS | WM_SHOWWINDOW | WM_SYSCOMMAND => {
                 return Some(unsafe { DefDlgProcW(self.handle, message, wparam, lparam) })
             }
+            WMU_FLO_PROBED => {
+                self.flo_probed(*take_boxed(lparam));
+            }
             WM_SIZE => {
                 self.ctrl_resize(lparam.0 as i32 & u16::MAX as i32, lparam.0 as i32 >> u16::BITS).ok()?;
             }
             WM_COMMAND => {
-                if wparam.0 >> u16::BITS == BN_CLICKED as usize {
+                if wparam.0 >> u16::BITS == LBN_DBLCLK as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_LB_FLO {
+                    self.flo_edit().ok()?;
+                } else if wparam.0 >> u16::BITS == SEN_SELCHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_SORT_EDIT {
+                    self.sort_rule_selected();
+                } else if wparam.0 >> u16::BITS == EN_CHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_ED_SORT_RULE {
+                    self.sort_match_refresh();
+                } else if wparam.0 >> u16::BITS == EN_CHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_ED_SORT_FILTER {
+                    self.sort_edit_ctrl().set_filter(&get_ctrl_text(self.handle, IDC_ED_SORT_FILTER));
+                    self.sort_edit_relayout();
+                } else if wparam.0 >> u16::BITS == EN_CHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_ED_SORT_HISTORY_DAYS {
+                    self.sort_history_filter();
+                } else if wparam.0 >> u16::BITS == LBN_SELCHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_LB_RULE {
+                    self.win_filter.select_changed(self.handle);
+                } else if wparam.0 >> u16::BITS == LBN_SELCHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_LB_TITLE_RULE {
+                    self.title_rule.select_changed(self.handle);
+                } else if wparam.0 >> u16::BITS == LBN_SELCHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_LB_SLOT {
+                    self.win_slot.select_changed(self.handle);
+                } else if (wparam.0 >> u16::BITS == EN_CHANGE as usize || wparam.0 >> u16::BITS == CBN_SELCHANGE as usize)
+                    && [IDC_DDL_TR_ACTION, IDC_ED_TR_FIND, IDC_ED_TR_REPLACE, IDC_ED_TR_SAMPLE].contains(&((wparam.0 & u16::MAX as usize) as isize)) {
+                    Self::title_rule_preview(self.handle);
+                } else if wparam.0 >> u16::BITS == BN_CLICKED as usize {
                     let mut id = (wparam.0 & u16::MAX as usize) as isize;
                     if id == IDOK.0 as isize { id = IDC_BT_OK; }
                     if id == IDCANCEL.0 as isize { id = IDC_BT_CANCEL; }
@@ -334,6 +865,14 @@ impl WndMsgHandler for FileViewPropWnd {
                                 self.rename_shell_item();
                             } else if let DlgType::DirProperty = self.dlg_type {
                                 self.apply_property();
+                            } else if let DlgType::FloManage = self.dlg_type {
+                                return Some(LRESULT(0)) // Enterで閉じない
+                            } else if let DlgType::WinFilter = self.dlg_type {
+                                self.apply_win_filter();
+                            } else if let DlgType::TitleRule = self.dlg_type {
+                                self.apply_title_rule();
+                            } else if let DlgType::WinSlot = self.dlg_type {
+                                self.apply_win_slot();
                             } else { // SortEdit
                                 self.apply_sort_setting();
                             }
@@ -342,6 +881,18 @@ impl WndMsgHandler for FileViewPropWnd {
                         IDC_BT_CANCEL => {
                             unsafe { PostMessageW(self.app().main_wnd().handle(), WMU_WINCLOSE, WPARAM(0), LPARAM(0)) }.ok()?;
                         }
+                        IDC_BT_SEL_MISSING => self.flo_select_missing(),
+                        IDC_BT_DELETE => self.flo_delete(),
+                        IDC_BT_EDIT => self.flo_edit().ok()?,
+                        IDC_BT_EXPORT => self.flo_export().ok()?,
+                        IDC_BT_RULE_ADD | IDC_BT_RULE_UPDATE | IDC_BT_RULE_DELETE | IDC_BT_RULE_UP | IDC_BT_RULE_DOWN => self.win_filter.command(self.handle, id),
+                        IDC_BT_TR_ADD | IDC_BT_TR_UPDATE | IDC_BT_TR_DELETE | IDC_BT_TR_UP | IDC_BT_TR_DOWN => self.title_rule.command(self.handle, id),
+                        IDC_BT_SLOT_ADD | IDC_BT_SLOT_UPDATE | IDC_BT_SLOT_DELETE | IDC_BT_SLOT_UP | IDC_BT_SLOT_DOWN => self.win_slot.command(self.handle, id),
+                        IDC_BT_SORT_ADD | IDC_BT_SORT_UPDATE | IDC_BT_SORT_DELETE => self.sort_rule_apply(id),
+                        IDC_BT_SORT_WILDCARD => self.sort_rule_wildcard(),
+                        IDC_BT_SORT_BROWSE => self.sort_rule_browse().ok()?,
+                        IDC_CB_SORT_HISTORY => self.sort_history_filter(),
+                        IDC_BT_SORT_PRUNE => self.sort_history_prune(),
                         _ => { }
                     }
                 }
@@ -355,3 +906,188 @@ impl WndMsgHandler for FileViewPropWnd {
         None
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+/// ダイアログ定義
+////////////////////////////////////////////////////////////////////////////////
+// ＯＫ・キャンセルボタン行
+fn dlg_def_ok_cancel(text_ok: &'static str, style: WINDOW_STYLE, h_spacer: i32) -> [DlgItem; 4] {
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(h_spacer, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    let button = |id, text| DlgItem::ctrl("BUTTON", id).caption(text).style(style)
+        .size(DlgSize::Char(5, 1), DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    [spacer(), button(IDC_BT_OK, text_ok), button(IDC_BT_CANCEL, DLG_FV_BT_TEXT_CANCEL), spacer()]
+}
+
+// プロパティ設定(プロパティパネルは実行時にIDWC_H1_1へ追加する)
+fn dlg_def_property() -> DlgItem {
+    DlgItem::cont(IDWC_ROOT)
+        .child(DlgItem::cont(IDWC_H1_1).vstack(AlignH::LEFT, HeightAuto::FIX))
+        .child(DlgItem::cont(IDWC_H1_2).vstack(AlignH::RIGHT, HeightAuto::FIX)
+            .children(dlg_def_ok_cancel(DLG_FV_BT_TEXT_APPLY, WS_TABSTOP, 2)))
+}
+
+// ファイル名変更
+fn dlg_def_rename() -> DlgItem {
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(3, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+
+    DlgItem::cont(IDWC_ROOT)
+        .child(DlgItem::cont(IDWC_H1_1).vstack(AlignH::FILL, HeightAuto::FIX)
+            .child(spacer())
+            .child(DlgItem::ctrl("STATIC", IDC_DUMMY).caption(DLG_FV_ST_RENAME).hstack(WidthAuto::FIX, AlignV::CENTER))
+            .child(DlgItem::ctrl("EDIT", IDC_ED_FILENAME).style(WS_TABSTOP)
+                .size(DlgSize::Char(15, 1), DlgSize::Char(1, 1)).hstack(WidthAuto::FIX, AlignV::CENTER))
+            .child(spacer()))
+        .child(DlgItem::cont(IDWC_H1_2).vstack(AlignH::RIGHT, HeightAuto::FIX)
+            .child(DlgItem::cont(IDWC_H12_1).vstack(AlignH::FILL, HeightAuto::FIX)
+                .children(dlg_def_ok_cancel(DLG_FV_BT_TEXT_OK, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, 3))))
+}
+
+// ウィンドウソート順編集(編集コントロールは実行時にIDWC_H1_1の絞り込み欄の下へ追加する)。下部はルールの手入力欄と一致するプロセスの一覧
+fn dlg_def_sort_edit() -> DlgItem {
+    let lb_style = WINDOW_STYLE((LBS_NOINTEGRALHEIGHT | LBS_NOSEL) as u32) | WS_VSCROLL | WS_BORDER;
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(1, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    let button = |id, text| DlgItem::ctrl("BUTTON", id).caption(text).style(WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP)
+        .size(DlgSize::Text, DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER);
+
+    DlgItem::cont(IDWC_ROOT)
+        .child(DlgItem::cont(IDWC_H1_1).vstack(AlignH::FILL, HeightAuto::AUTO)
+            .child(DlgItem::cont(IDWC_H11_1).vstack(AlignH::FILL, HeightAuto::FIX)
+                .child(DlgItem::ctrl("STATIC", IDC_DUMMY).caption(DLG_SE_ST_FILTER).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::ctrl("EDIT", IDC_ED_SORT_FILTER).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP)
+                    .size(DlgSize::Px(0), DlgSize::Text).hstack(WidthAuto::AUTO, AlignV::CENTER))))
+        .child(DlgItem::cont(IDWC_H1_3).vstack(AlignH::FILL, HeightAuto::FIX)
+            .grid_define(&vec![GridSize::AUTO; 4], &[GridSize::AUTO, GridSize::STAR(1)])
+            .child(DlgItem::ctrl("STATIC", IDC_DUMMY).caption(DLG_SE_ST_CAPTIONS[0]).grid(0, 0, 1, 1, AlignH::RIGHT, AlignV::CENTER))
+            .child(DlgItem::ctrl("EDIT", IDC_ED_SORT_RULE).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP)
+                .size(DlgSize::Char(50, 1), DlgSize::Text).grid(0, 1, 1, 1, AlignH::FILL, AlignV::CENTER))
+            .child(DlgItem::cont(IDWC_H13_1).grid(1, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+                .child(button(IDC_BT_SORT_ADD, DLG_SE_BT_ADD)).child(spacer())
+                .child(button(IDC_BT_SORT_UPDATE, DLG_SE_BT_UPDATE)).child(spacer())
+                .child(button(IDC_BT_SORT_DELETE, DLG_SE_BT_DELETE)).child(spacer())
+                .child(button(IDC_BT_SORT_WILDCARD, DLG_SE_BT_WILDCARD)).child(spacer())
+                .child(button(IDC_BT_SORT_BROWSE, DLG_SE_BT_BROWSE)))
+            .child(DlgItem::ctrl("STATIC", IDC_DUMMY).caption(DLG_SE_ST_CAPTIONS[1]).grid(2, 0, 1, 1, AlignH::RIGHT, AlignV::TOP))
+            .child(DlgItem::ctrl("LISTBOX", IDC_LB_SORT_MATCH).style(lb_style)
+                .size(DlgSize::Char(50, 1), DlgSize::Char(5, 1)).grid(2, 1, 1, 1, AlignH::FILL, AlignV::CENTER))
+            .child(DlgItem::ctrl("STATIC", IDC_DUMMY).caption(DLG_SE_ST_CAPTIONS[2]).grid(3, 0, 1, 1, AlignH::RIGHT, AlignV::CENTER))
+            .child(DlgItem::cont(IDWC_H13_2).grid(3, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+                .child(DlgItem::ctrl("BUTTON", IDC_CB_SORT_HISTORY).caption(DLG_SE_CB_HISTORY).style(WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP)
+                    .size(DlgSize::Text, DlgSize::Text).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::ctrl("EDIT", IDC_ED_SORT_HISTORY_DAYS).style(WINDOW_STYLE(ES_NUMBER as u32) | WS_TABSTOP)
+                    .size(DlgSize::Char(4, 1), DlgSize::Text).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::ctrl("STATIC", IDC_DUMMY).caption(DLG_SE_ST_DAYS).hstack(WidthAuto::FIX, AlignV::CENTER)).child(spacer())
+                .child(button(IDC_BT_SORT_PRUNE, DLG_SE_BT_PRUNE))))
+        .child(DlgItem::cont(IDWC_H1_2).vstack(AlignH::RIGHT, HeightAuto::FIX)
+            .child(DlgItem::cont(IDWC_H12_1).vstack(AlignH::FILL, HeightAuto::FIX)
+                .children(dlg_def_ok_cancel(DLG_FV_BT_TEXT_OK, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, 3))))
+}
+
+// フォルダ設定の管理(一覧と操作ボタン)
+fn dlg_def_flo_manage() -> DlgItem {
+    let lb_style = WINDOW_STYLE((LBS_EXTENDEDSEL | LBS_USETABSTOPS | LBS_NOINTEGRALHEIGHT | LBS_NOTIFY) as u32) | WS_VSCROLL | WS_HSCROLL | WS_BORDER | WS_TABSTOP;
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(1, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    let button = |id, text| DlgItem::ctrl("BUTTON", id).caption(text).style(WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP)
+        .size(DlgSize::Text, DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER);
+
+    DlgItem::cont(IDWC_ROOT)
+        .child(DlgItem::cont(IDWC_H1_1).vstack(AlignH::FILL, HeightAuto::AUTO)
+            .child(DlgItem::ctrl("LISTBOX", IDC_LB_FLO).style(lb_style)
+                .size(DlgSize::Char(60, 1), DlgSize::Char(20, 1)).vstack(AlignH::FILL, HeightAuto::AUTO)))
+        .child(DlgItem::cont(IDWC_H1_2).vstack(AlignH::RIGHT, HeightAuto::FIX)
+            .child(DlgItem::cont(IDWC_H12_1).vstack(AlignH::FILL, HeightAuto::FIX)
+                .child(button(IDC_BT_SEL_MISSING, DLG_FM_BT_SEL_MISSING)).child(spacer())
+                .child(button(IDC_BT_DELETE, DLG_FM_BT_DELETE)).child(spacer())
+                .child(button(IDC_BT_EDIT, DLG_FM_BT_EDIT)).child(spacer())
+                .child(button(IDC_BT_EXPORT, DLG_FM_BT_EXPORT)).child(spacer())
+                .child(button(IDC_BT_CANCEL, DLG_FM_BT_CLOSE)).child(spacer())))
+}
+
+// ルール編集の共通部分(一覧, 動作のドロップダウンと編集欄, 操作ボタン)。編集欄は2行目以降のグリッドに置く
+fn dlg_def_rule_list(ids: &RuleListIds, captions: &'static [&'static str], action_len: &'static str, fields: Vec<DlgItem>) -> DlgItem {
+    let lb_style = WINDOW_STYLE((LBS_USETABSTOPS | LBS_NOINTEGRALHEIGHT | LBS_NOTIFY) as u32) | WS_VSCROLL | WS_BORDER | WS_TABSTOP;
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(1, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    let button = |id, text| DlgItem::ctrl("BUTTON", id).caption(text).style(WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP)
+        .size(DlgSize::Text, DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER);
+
+    DlgItem::cont(IDWC_ROOT)
+        .child(DlgItem::cont(IDWC_H1_1).vstack(AlignH::FILL, HeightAuto::AUTO)
+            .child(DlgItem::ctrl("LISTBOX", ids.list).style(lb_style)
+                .size(DlgSize::Char(60, 1), DlgSize::Char(10, 1)).vstack(AlignH::FILL, HeightAuto::AUTO)))
+        .child(DlgItem::cont(IDWC_H1_3).vstack(AlignH::FILL, HeightAuto::FIX)
+            .grid_define(&vec![GridSize::AUTO; captions.len()], &[GridSize::AUTO, GridSize::STAR(1)])
+            .children(captions.iter().enumerate().map(|(i, text)|
+                DlgItem::ctrl("STATIC", IDC_DUMMY).caption(text).grid(i, 0, 1, 1, AlignH::RIGHT, AlignV::CENTER)))
+            .child(DlgItem::ctrl("COMBOBOX", ids.action).style(WINDOW_STYLE((CBS_HASSTRINGS | CBS_DROPDOWNLIST) as u32) | WS_TABSTOP)
+                .size(DlgSize::TextOf(action_len), DlgSize::Char(7, 5)).grid(0, 1, 1, 1, AlignH::LEFT, AlignV::CENTER))
+            .children(fields))
+        .child(DlgItem::cont(IDWC_H1_2).vstack(AlignH::RIGHT, HeightAuto::FIX)
+            .child(DlgItem::cont(IDWC_H12_1).vstack(AlignH::FILL, HeightAuto::FIX)
+                .child(button(ids.add, DLG_WF_BT_ADD)).child(spacer())
+                .child(button(ids.update, DLG_WF_BT_UPDATE)).child(spacer())
+                .child(button(ids.delete, DLG_WF_BT_DELETE)).child(spacer())
+                .child(button(ids.up, DLG_WF_BT_UP)).child(spacer())
+                .child(button(ids.down, DLG_WF_BT_DOWN)).child(spacer())
+                .children(dlg_def_ok_cancel(DLG_FV_BT_TEXT_OK, WINDOW_STYLE(BS_NOTIFY as u32) | WS_TABSTOP, 1))))
+}
+
+// ウィンドウ表示ルールの編集
+fn dlg_def_win_filter() -> DlgItem {
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(1, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    let edit = |id, row| DlgItem::ctrl("EDIT", id).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP)
+        .size(DlgSize::Char(30, 1), DlgSize::Text).grid(row, 1, 1, 1, AlignH::FILL, AlignV::CENTER);
+    let tristate = |id, text| DlgItem::ctrl("BUTTON", id).caption(text).style(WINDOW_STYLE(BS_AUTO3STATE as u32) | WS_TABSTOP)
+        .size(DlgSize::Text, DlgSize::Text).hstack(WidthAuto::FIX, AlignV::CENTER);
+
+    dlg_def_rule_list(&WF_IDS, &DLG_WF_ST_CAPTIONS, DLG_WF_DDL_ACTION_LENGTH, vec![
+        edit(IDC_ED_RULE_CLASS, 1),
+        edit(IDC_ED_RULE_TITLE, 2),
+        edit(IDC_ED_RULE_PROC, 3),
+        DlgItem::cont(IDWC_H13_1).grid(4, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+            .child(tristate(IDC_CB_RULE_TOOL, DLG_WF_CB_STYLE[0])).child(spacer())
+            .child(tristate(IDC_CB_RULE_OWNED, DLG_WF_CB_STYLE[1])).child(spacer())
+            .child(tristate(IDC_CB_RULE_TOPMOST, DLG_WF_CB_STYLE[2])),
+    ])
+}
+
+// タイトル書き換えルールの編集(見本による確認つき)
+fn dlg_def_title_rule() -> DlgItem {
+    let edit = |id, row, style| DlgItem::ctrl("EDIT", id).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | style)
+        .size(DlgSize::Char(40, 1), DlgSize::Text).grid(row, 1, 1, 1, AlignH::FILL, AlignV::CENTER);
+
+    dlg_def_rule_list(&TR_IDS, &DLG_TR_ST_CAPTIONS, DLG_TR_DDL_ACTION_LENGTH, vec![
+        edit(IDC_ED_TR_PROC, 1, WS_TABSTOP),
+        edit(IDC_ED_TR_FIND, 2, WS_TABSTOP),
+        edit(IDC_ED_TR_REPLACE, 3, WS_TABSTOP),
+        edit(IDC_ED_TR_SAMPLE, 4, WS_TABSTOP),
+        edit(IDC_ED_TR_RESULT, 5, WINDOW_STYLE(ES_READONLY as u32)),
+    ])
+}
+
+// スロットの編集(割り当て時のタイトルをワイルドカード・正規表現に書き換えられる)
+fn dlg_def_win_slot() -> DlgItem {
+    let edit = |id, row| DlgItem::ctrl("EDIT", id).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP)
+        .size(DlgSize::Char(40, 1), DlgSize::Text).grid(row, 1, 1, 1, AlignH::FILL, AlignV::CENTER);
+
+    dlg_def_rule_list(&SL_IDS, &DLG_SL_ST_CAPTIONS, DLG_SL_DDL_SLOT_LENGTH, vec![
+        edit(IDC_ED_SLOT_PROC, 1),
+        edit(IDC_ED_SLOT_TITLE, 2),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dialog_definitions_are_valid() {
+        assert_eq!(dlg_validate(&[&dlg_def_property(), &dlg_def_dir_property(false)]), Ok(()));
+        assert_eq!(dlg_validate(&[&dlg_def_rename()]), Ok(()));
+        assert_eq!(dlg_validate(&[&dlg_def_sort_edit()]), Ok(()));
+        assert_eq!(dlg_validate(&[&dlg_def_flo_manage()]), Ok(()));
+        assert_eq!(dlg_validate(&[&dlg_def_win_filter()]), Ok(()));
+        assert_eq!(dlg_validate(&[&dlg_def_title_rule()]), Ok(()));
+        assert_eq!(TITLE_ACTIONS.len(), DLG_TR_DDL_ACTION.len());
+        assert_eq!(dlg_validate(&[&dlg_def_win_slot()]), Ok(()));
+        assert_eq!(DLG_SL_DDL_SLOT.len(), WinSlot::MAX as usize);
+    }
+}
@@ -3,7 +3,9 @@ use windows::Win32::{System::SystemServices::{SS_PATHELLIPSIS, SS_SUNKEN, }, UI:
 
 use super::*;
 use lib_gui_layout_container::*;
+use lib_gui_layout_define::*;
 use lib_property::*;
+use lib_shell::ObjectHolder;
 
 static ONCE: Once = Once::new();
 
@@ -19,17 +21,20 @@ const IDWC_H1_2: isize = 12;
 const IDWC_H1_3: isize = 13;
 const IDWC_H1_4: isize = 14;
 const IDWC_H11_1: isize = 21;
-const IDWC_H11_2: isize = 22;
+const IDWC_H112_0: isize = 30;
 const IDWC_H112_1: isize = 31;
 const IDWC_H112_2: isize = 32;
 const IDWC_H112_3: isize = 33;
+const IDWC_H112_4: isize = 34;
 
 const IDWC_H2_1: isize = 101;
 const IDWC_H2_2: isize = 102;
 const IDWC_H21_1: isize = 103;
+const IDWC_H211_1: isize = 104;
 
 const IDC_ST_PATH:isize = 501;
 const IDC_ST_PANEL:isize = 502;
+const IDC_ST_PATH_RESOLVED:isize = 503;
 
 const IDC_BT_OK: isize = 1001;
 const IDC_BT_CANCEL: isize = 1002;
@@ -46,6 +51,17 @@ const IDC_DDL_ALT_SHIFT: isize = 1012;
 const IDC_BT_HOTKEY_DEL: isize = 1013;
 const IDC_BT_HOTKEY_ADD: isize = 1014;
 const IDC_ED_HOTKEY_CHR: isize = 1015;
+const IDC_CB_ATTENTION_TOP: isize = 1016;
+const IDC_DDL_SORT_MODE: isize = 1017;
+const IDC_CB_FOLDERS_FIRST: isize = 1018;
+const IDC_ED_SORT_PROFILE: isize = 1019;
+const IDC_CB_INHERIT_VIEW: isize = 1020;
+const IDC_CB_PROC_LINE: isize = 1021;
+const IDC_ED_SLOT: isize = 1022;
+
+// 種類の選択肢の並び(ランチャーのアイコン/リストはパネル側で選ぶ)
+static HOTKEY_KINDS: [HotkeyType; 6] = [HotkeyType::ListLauncher, HotkeyType::WinTaskList, HotkeyType::WinSlot,
+    HotkeyType::RunOrRaise, HotkeyType::CycleApp, HotkeyType::ToggleLast];
 
 pub struct HotkeyPropWnd {
     app: AppWeak,
@@ -115,10 +131,16 @@ impl HotkeyPropWnd {
     }
 
     fn view_adjust_size_root(&mut self, c_root: &mut Rc<WindowContainer>, b_nomove_x: bool) {
-        // 必要なクライアントサイズを決定する
+        // 必要なクライアントサイズを決定する(ホットキー一覧は最低1パネル分まで縮小可能)
+        // 最小サイズはパネルを追加した後でないと分からないため、ダイアログ定義ではなくここで指定する
+        let wc_hotkeys = wnd_instance::<WindowContainer>(get_ctrl(self.handle, IDWC_HOTKEYS)).upgrade().unwrap();
         c_root.recalc_layout();
-        let (w_rc, h_rc) = c_root.get_field_size();
+        let h_panel = self.ctrl_dir_prop.first().and_then(|p| wnd_instance::<WindowContainer>(p.hparent).upgrade()).map_or(0, |c| c.pref_layout().1);
+        c_root.set_stack_constraint(wc_hotkeys.handle(), h_panel, -1, 1);
+        let (w_rc, h_rc) = c_root.pref_layout();
+        let (_, h_rc_min) = c_root.check_layout();
         let (mut win_w, mut win_h) = adjust_window_rect(self.handle, w_rc, h_rc);
+        let (_, win_h_min) = adjust_window_rect(self.handle, w_rc, h_rc_min);
 
         // 画面サイズを取得
         let mut pt = POINT::default();
@@ -136,24 +158,23 @@ impl HotkeyPropWnd {
         }
 
         // ウィンドウサイズと比較(超過分を計算)。クライアントサイズを縮小させる(スクロール設定する)
+        // 縦方向の縮小はレイアウトの最小サイズ指定に従ってホットキー一覧が受け持つため、スクロールバーの分だけ調整する
         let (mut b_add_vscr, mut b_add_hscr) = (false, false);
         for _ in 0..2 { // 初回の計算で一方にスクロールバーが生じ、もともと収まっていた他方が影響を受け両方を表示する必要があるケースへの対応
             let (w_over, h_over) = (win_w - (mi.rcWork.right - mi.rcWork.left), win_h - (mi.rcWork.bottom - mi.rcWork.top));
             if h_over > 0 {
                 let w_vscrbar = if !b_add_vscr { sys_metrics(self.handle, SM_CXVSCROLL) } else { 0 };
-                win_h -= h_over;
+                win_h = (win_h - h_over).max(win_h_min);
                 win_w += w_vscrbar;
                 b_add_vscr = true;
-                let wc_hotkeys = wnd_instance::<WindowContainer>(get_ctrl(self.handle, IDWC_HOTKEYS)).upgrade().unwrap();
                 let p = c_root.get_vstack_param(wc_hotkeys.handle());
-                c_root.set_vstack_param(wc_hotkeys.handle(), p.0 + w_vscrbar, p.1 - h_over, p.2, p.3, p.4, p.5, p.6, p.7);
+                c_root.set_vstack_param(wc_hotkeys.handle(), p.0 + w_vscrbar, p.1, p.2, p.3, p.4, p.5, p.6, p.7);
             }
             if w_over > 0 {
                 let h_hscrbar = if !b_add_hscr { sys_metrics(self.handle, SM_CYHSCROLL) } else { 0 };
                 win_w -= w_over;
                 win_h += h_hscrbar;
                 b_add_hscr = true;
-                let wc_hotkeys = wnd_instance::<WindowContainer>(get_ctrl(self.handle, IDWC_HOTKEYS)).upgrade().unwrap();
                 let p = c_root.get_vstack_param(wc_hotkeys.handle());
                 c_root.set_vstack_param(wc_hotkeys.handle(), p.0, p.1 + h_hscrbar, p.2, p.3, p.4, p.5, p.6, p.7);
             }
@@ -172,10 +193,8 @@ impl HotkeyPropWnd {
         let _ = unsafe { SetWindowTextW(self.handle, WINTITLE) };
 
         let hfont = self.hfont.0;
-        let (w, h) = text_size(self.handle, hfont, CHAR_FONT_WIDTH_MEASURE);
-
-        // hotkey part
-        let mut c_hotkeys = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::LEFT, HeightAuto::FIX, IDWC_HOTKEYS);
+        dlg_instantiate(wnd_instance::<Self>(self.handle), &mut c_root, hfont, &dlg_def_property());
+        let mut c_hotkeys = dlg_cont(c_root.handle(), IDWC_HOTKEYS);
 
         //////////// レジストリを読んで対象の分だけパネルを追加し、初期値をセットする
         let hotkeys = PropertyHolder::enum_hotkey_param();
@@ -188,24 +207,8 @@ impl HotkeyPropWnd {
             self.ctrl_dir_prop.push(hpp);
         }
 
-        // bottom part
-        let mut c_bottom = init_cont_vstack(wnd_instance::<Self>(self.handle), &mut c_root, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H1_2);
-
-        let mut c_b_l = init_cont_hstack(wnd_instance::<Self>(self.handle), &mut c_bottom, 0, 0, WidthAuto::FIX, AlignV::FILL, IDWC_H1_3);
-        init_item_hstack(&mut c_b_l, hfont, 0, h * 2, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut c_b_l, hfont, w * 5, h * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_HK_BT_TEXT_ADD, WINDOW_STYLE::default(), IDC_BT_HOTKEY_ADD);
-        init_item_hstack(&mut c_b_l, hfont, 0, h * 2, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-
-        let _c_b_m = init_cont_hstack(wnd_instance::<Self>(self.handle), &mut c_bottom, 0, 0, WidthAuto::AUTO, AlignV::FILL, IDC_DUMMY);
-
-        let mut c_b_r = init_cont_hstack(wnd_instance::<Self>(self.handle), &mut c_bottom, 0, 0, WidthAuto::FIX, AlignV::FILL, IDWC_H1_4);
-        init_item_hstack(&mut c_b_r, hfont, 0, h * 2, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut c_b_r, hfont, w * 5, h * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_HK_BT_TEXT_APPLY, WINDOW_STYLE::default(), IDC_BT_OK);
-        init_item_hstack(&mut c_b_r, hfont, w * 5, h * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "BUTTON", DLG_HK_BT_TEXT_CANCEL, WINDOW_STYLE::default(), IDC_BT_CANCEL);
-        init_item_hstack(&mut c_b_r, hfont, 0, h * 2, WidthAuto::FIX, AlignV::CENTER, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-
         self.view_adjust_size_root(&mut c_root, false);
-        unsafe { SetFocus(get_ctrl(c_b_r.handle(), IDC_BT_OK)); }
+        unsafe { SetFocus(get_ctrl(c_root.handle(), IDC_BT_OK)); }
         Ok(())
     }
 
@@ -363,51 +366,28 @@ impl HotkeyPropertyPanel {
             ctrl_dir_prop: None,
         });
 
-        let (_, h) = text_size(wc.handle(), hfont, CHAR_FONT_WIDTH_MEASURE); // アルファベット一文字当たりの幅(一番幅をとる文字)
-
-        init_item_place(wc, 0.0, 0.0, 0.0, 0.0, HWND(0), HWND(0), PlaceSet::PIXEL, PlaceSet::PIXEL,
-            "STATIC", "", WINDOW_STYLE(SS_SUNKEN.0), IDC_ST_PANEL);
-
-        let mut cv0  = init_cont_place(Rc::downgrade(&self_rc), wc, 2.0, 2.0, 0.0, 0.0,
-            HWND(0), HWND(0), PlaceSet::PIXEL, PlaceSet::PIXEL, IDWC_H1_1);
-
-        let mut cv1 = init_cont_hstack(Rc::downgrade(&self_rc), &mut cv0, 0, 0, WidthAuto::FIX, AlignV::FILL, IDWC_H2_1);
-
-        let mut cv11 = init_cont_hstack(Rc::downgrade(&self_rc), &mut cv1, 0, 0, WidthAuto::FIX, AlignV::CENTER, IDWC_H21_1);
-
-        // コントロール配置
-        init_item_vstack(&mut cv11, hfont, -1, h * 7 / 5, AlignH::CENTER, HeightAuto::FIX, "COMBOBOX", DLG_HK_DDL_HKKIND_LENGTH, WINDOW_STYLE((CBS_HASSTRINGS | CBS_DROPDOWNLIST) as u32) | WS_TABSTOP, IDC_DDL_HOTKEY_KIND);
-
-        let mut c_hotkey = init_cont_vstack(Rc::downgrade(&self_rc), &mut cv11, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H21_1);
-        init_item_hstack(&mut c_hotkey, hfont, -1, h * 7 / 5, WidthAuto::FIX, AlignV::CENTER, "COMBOBOX", DLG_HK_CB_HKALT_LENGTH, WINDOW_STYLE((CBS_HASSTRINGS | CBS_DROPDOWNLIST) as u32) | WS_TABSTOP, IDC_DDL_ALT_SHIFT);
-        init_item_hstack(&mut c_hotkey, hfont, -1, -1, WidthAuto::FIX, AlignV::CENTER, "EDIT", CHAR_FONT_WIDTH_MEASURE, WINDOW_STYLE::default() | WS_TABSTOP, IDC_ED_HOTKEY_CHR);
-        unsafe { SendMessageW(get_ctrl(c_hotkey.handle(), IDC_ED_HOTKEY_CHR), EM_LIMITTEXT, WPARAM(1), LPARAM(0)); }
-
-        init_item_vstack(&mut cv11, hfont, 0, h / 2, AlignH::CENTER, HeightAuto::FIX, "STATIC", "", WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_vstack(&mut cv11, hfont, -1, h * 7 / 5, AlignH::CENTER, HeightAuto::FIX, "BUTTON", DLG_HK_BT_TEXT_DEL, WINDOW_STYLE::default() | WS_TABSTOP, IDC_BT_HOTKEY_DEL);
+        dlg_instantiate(Rc::downgrade(&self_rc), wc, hfont, &dlg_def_hotkey_panel());
+        unsafe { SendMessageW(get_ctrl(wc.handle(), IDC_ED_HOTKEY_CHR), EM_LIMITTEXT, WPARAM(1), LPARAM(0)); }
 
         // コンボボックス(ドロップダウン)選択肢セット
-        let hwnd_ddl = get_ctrl(cv11.handle(), IDC_DDL_HOTKEY_KIND);
-        unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(DLG_HK_DDL_HKKIND[0].as_ptr() as _)); }
-        unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(DLG_HK_DDL_HKKIND[1].as_ptr() as _)); }
+        let hwnd_ddl = get_ctrl(wc.handle(), IDC_DDL_HOTKEY_KIND);
+        for text in DLG_HK_DDL_HKKIND {
+            unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(text.as_ptr() as _)); }
+        }
 
-        let hwnd_ddl = get_ctrl(c_hotkey.handle(), IDC_DDL_ALT_SHIFT);
+        let hwnd_ddl = get_ctrl(wc.handle(), IDC_DDL_ALT_SHIFT);
         unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(DLG_HK_DDL_MODKEY[0].as_ptr() as _)); }
         unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(DLG_HK_DDL_MODKEY[1].as_ptr() as _)); }
         unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(DLG_HK_DDL_MODKEY[2].as_ptr() as _)); }
 
-        let p = cv1.get_hstack_param(cv11.handle());
-        cv1.set_hstack_param(cv11.handle(), p.0, p.1, 2, p.3, p.4, p.5, p.6, p.7);
-
-        let mut cv2 = init_cont_hstack(Rc::downgrade(&self_rc), &mut cv0, 0, 0, WidthAuto::FIX, AlignV::FILL, IDWC_H2_2);
-
+        let mut cv2 = dlg_cont(wc.handle(), IDWC_H2_2);
         self_rc.get_mut().ctrl_dir_prop = Some(DirPropertyPanel::init(&mut cv2, hfont, true));
         wc.recalc_layout();
         wc.recalc_layout_stop(true);
 
         let hwnd_st = get_ctrl(wc.handle(), IDC_ST_PANEL);
         let p = wc.get_place_param(hwnd_st);
-        wc.set_place_param(hwnd_st, p.0, p.1, 2.0 , 2.0, p.4, cv0.handle(), p.6, PlaceSet::OFFSET);
+        wc.set_place_param(hwnd_st, p.0, p.1, 2.0 , 2.0, p.4, get_ctrl(wc.handle(), IDWC_H1_1), p.6, PlaceSet::OFFSET);
 
         self_rc
     }
@@ -422,11 +402,8 @@ impl HotkeyPropertyPanel {
         }
         set_ctrl_text(self.hparent, IDC_ED_HOTKEY_CHR, &PropertyHolder::conv_vkey2char(vk).unwrap().to_string());
 
-        if param.hotkey_type == HotkeyType::ListLauncher || param.hotkey_type == HotkeyType::IconLauncher {
-            set_ctrl_cursel(self.hparent, IDC_DDL_HOTKEY_KIND, 0);
-        } else if param.hotkey_type == HotkeyType::WinTaskList {
-            set_ctrl_cursel(self.hparent, IDC_DDL_HOTKEY_KIND, 1);
-        }
+        let kind = if param.hotkey_type == HotkeyType::IconLauncher { &HotkeyType::ListLauncher } else { &param.hotkey_type };
+        set_ctrl_cursel(self.hparent, IDC_DDL_HOTKEY_KIND, HOTKEY_KINDS.iter().position(|k| k == kind).unwrap_or(0));
 
         self.ctrl_dir_prop.as_mut().unwrap().get_mut().ctrl_setvalue_dir_property(param);
     }
@@ -444,10 +421,15 @@ impl HotkeyPropertyPanel {
         let vkey = PropertyHolder::conv_char2vkey(c.unwrap());
         if vkey.is_err() { return None }
 
-        let hk_kind_sel = get_ctrl_cursel(self.hparent, IDC_DDL_HOTKEY_KIND);
-        let prop = self.ctrl_dir_prop.as_ref().unwrap().ctrl_getvalue_dir_property(hk_kind_sel == 0 /*LAUNCHER*/);
+        let prop = self.ctrl_dir_prop.as_ref().unwrap().ctrl_getvalue_dir_property(self.hotkey_kind());
+        if prop.hotkey_type == HotkeyType::RunOrRaise && prop.path.is_empty() { return None } // 起動するプログラムの指定なし
         Some((hk_mod, vkey.unwrap(), prop))
     }
+
+    fn hotkey_kind(&self) -> &'static HotkeyType {
+        let sel = usize::try_from(get_ctrl_cursel(self.hparent, IDC_DDL_HOTKEY_KIND)).unwrap_or(0);
+        HOTKEY_KINDS.get(sel).unwrap_or(&HOTKEY_KINDS[0])
+    }
 }
 
 impl WindowContainerMsgProc for HotkeyPropertyPanelWeak {
@@ -471,10 +453,9 @@ impl WindowContainerMsgProc for HotkeyPropertyPanelWeak {
                     }
                     CBN_SELCHANGE => {
                         match (wparam.0 & u16::MAX as usize) as isize {
-                            IDC_DDL_HOTKEY_KIND => { // タスクリストかランチャーか右側のパネル表示を変更する
+                            IDC_DDL_HOTKEY_KIND => { // 種類に合わせて右側のパネル表示を変更する
                                 let rc = self.upgrade().unwrap();
-                                let sel_type_launcher = get_ctrl_cursel(rc.hparent, IDC_DDL_HOTKEY_KIND);
-                                let p = rc.ctrl_dir_prop.as_ref().unwrap().ctrl_getvalue_dir_property(sel_type_launcher == 0);
+                                let p = rc.ctrl_dir_prop.as_ref().unwrap().ctrl_getvalue_dir_property(rc.hotkey_kind());
                                 rc.ctrl_dir_prop.as_ref().unwrap().ctrl_setvalue_dir_property(&p);
                             }
                             IDC_DDL_ALT_SHIFT => {
@@ -561,58 +542,56 @@ impl DirPropertyPanel {
             b_path_edit: b_path_edit,
         });
 
-        let (w, _) = text_size(wc.handle(), hfont, CHAR_FONT_WIDTH_MEASURE); // アルファベット一文字当たりの幅(一番幅をとる文字)
-        let (ew, eh) = text_size(wc.handle(), hfont, DLG_HK_ST_PROP_SIZEEDIT_SIZE);
+        dlg_instantiate(Rc::downgrade(&self_rc), wc, hfont, &dlg_def_dir_property(b_path_edit));
 
-        let mut cv1 = init_cont_hstack(Rc::downgrade(&self_rc), wc, 0, 0, WidthAuto::FIX, AlignV::FILL, IDWC_H11_1);
-        let mut cv2 = init_cont_hstack(Rc::downgrade(&self_rc), wc, 0, 0, WidthAuto::AUTO, AlignV::FILL, IDWC_H11_2);
-
-        for cap in DLG_HK_ST_PROP_CAPTIONS {
-            init_item_vstack(&mut cv1, hfont, -1, -1, AlignH::RIGHT, HeightAuto::FIX, "STATIC", cap, WINDOW_STYLE::default(), IDC_DUMMY);
+        let hwnd_ddl = get_ctrl(wc.handle(), IDC_DDL_SORT_MODE);
+        if b_path_edit { // ホットキーでは先頭に「フォルダの設定に従う」
+            unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(DLG_HK_DDL_SORT_FOLDER.as_ptr() as _)); }
         }
-
-        if b_path_edit {
-            init_item_vstack(&mut cv2, hfont, w * 15, -1, AlignH::FILL, HeightAuto::FIX, "EDIT", "", WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP, IDC_ED_FILENAME);
-        } else {
-            init_item_vstack(&mut cv2, hfont, w * 15, -1, AlignH::FILL, HeightAuto::FIX, "STATIC", "", WINDOW_STYLE(SS_PATHELLIPSIS.0 as u32), IDC_ST_PATH);
+        for text in DLG_HK_DDL_SORT_MODE {
+            unsafe { SendMessageW(hwnd_ddl, CB_ADDSTRING, WPARAM(0), LPARAM(text.as_ptr() as _)); }
         }
-
-        let mut c_radio1 = init_cont_vstack(Rc::downgrade(&self_rc), &mut cv2, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H112_1);
-        init_item_hstack(&mut c_radio1, hfont, w * 5, -1, WidthAuto::FIX, AlignV::FILL, "BUTTON", DLG_HK_RB_LIST_ICON[0], WS_GROUP | WINDOW_STYLE(BS_AUTORADIOBUTTON as u32) | WS_TABSTOP |WS_VISIBLE, IDC_RB_LIST);
-        init_item_hstack(&mut c_radio1, hfont, w * 5, -1, WidthAuto::FIX, AlignV::FILL, "BUTTON", DLG_HK_RB_LIST_ICON[1], WINDOW_STYLE(BS_AUTORADIOBUTTON as u32) | WS_TABSTOP, IDC_RB_ICON);
-
-        let mut c_radio2 = init_cont_vstack(Rc::downgrade(&self_rc), &mut cv2, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H112_2);
-        init_item_hstack(&mut c_radio2, hfont, w * 5, -1, WidthAuto::FIX, AlignV::FILL, "BUTTON", DLG_HK_RB_LARGE_SMALL[0], WS_GROUP | WINDOW_STYLE(BS_AUTORADIOBUTTON as u32) | WS_TABSTOP, IDC_RB_ICON_L);
-        init_item_hstack(&mut c_radio2, hfont, w * 5, -1, WidthAuto::FIX, AlignV::FILL, "BUTTON", DLG_HK_RB_LARGE_SMALL[1], WINDOW_STYLE(BS_AUTORADIOBUTTON as u32) | WS_TABSTOP, IDC_RB_ICON_S);
-
-        let mut c_edit = init_cont_vstack(Rc::downgrade(&self_rc), &mut cv2, 0, 0, AlignH::FILL, HeightAuto::FIX, IDWC_H112_3);
-        init_item_hstack(&mut c_edit, hfont, -1, -1, WidthAuto::FIX, AlignV::FILL, "STATIC", DLG_HK_ST_PROP_SIZEEDIT_CAP[0], WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut c_edit, hfont, ew, eh, WidthAuto::FIX, AlignV::FILL, "EDIT", "", WINDOW_STYLE::default() | WS_TABSTOP, IDC_ED_LAUNCHWIN_W);
-        init_item_hstack(&mut c_edit, hfont, -1, -1, WidthAuto::FIX, AlignV::FILL, "STATIC", DLG_HK_ST_PROP_SIZEEDIT_CAP[1], WINDOW_STYLE::default(), IDC_DUMMY);
-        init_item_hstack(&mut c_edit, hfont, ew, eh, WidthAuto::FIX, AlignV::FILL, "EDIT", "", WINDOW_STYLE::default() | WS_TABSTOP, IDC_ED_LAUNCHWIN_H);
-        init_item_hstack(&mut c_edit, hfont, -1, -1, WidthAuto::FIX, AlignV::FILL, "STATIC", DLG_HK_ST_PROP_SIZEEDIT_CAP[2], WINDOW_STYLE::default(), IDC_DUMMY);
-
-        init_item_vstack(&mut cv2, hfont, 0, -1, AlignH::FILL, HeightAuto::FIX, "BUTTON", DLG_HK_CB_DISP_HIDDEN, WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP, IDC_CB_DISP_HIDDEN);
+        unsafe { SendMessageW(get_ctrl(wc.handle(), IDC_ED_SLOT), EM_LIMITTEXT, WPARAM(1), LPARAM(0)); }
 
         self_rc
     }
 
     pub fn ctrl_setvalue_dir_property(&self, param: &PropertyHolder) {
-        if param.hotkey_type == HotkeyType::WinTaskList {
-            set_ctrl_enable(self.hparent, IDC_ED_FILENAME, false);
+        if param.hotkey_type != HotkeyType::ListLauncher && param.hotkey_type != HotkeyType::IconLauncher {
+            let b_list = param.hotkey_type == HotkeyType::WinTaskList;
+            let b_raise = param.hotkey_type == HotkeyType::RunOrRaise;
+            set_ctrl_enable(self.hparent, IDC_ED_FILENAME, b_raise);
             set_ctrl_enable(self.hparent, IDC_RB_ICON, false);
             set_ctrl_enable(self.hparent, IDC_RB_LIST, false);
             set_ctrl_enable(self.hparent, IDC_RB_ICON_L, false);
             set_ctrl_enable(self.hparent, IDC_RB_ICON_S, false);
             set_ctrl_enable(self.hparent, IDC_CB_DISP_HIDDEN, false);
-
-            set_ctrl_text(self.hparent, IDC_ED_FILENAME, DLG_HK_ST_PROP_WINTASK_LIST);
+            set_ctrl_enable(self.hparent, IDC_CB_ATTENTION_TOP, b_list);
+            set_ctrl_enable(self.hparent, IDC_CB_PROC_LINE, b_list);
+            set_ctrl_enable(self.hparent, IDC_ED_LAUNCHWIN_W, b_list);
+            set_ctrl_enable(self.hparent, IDC_ED_LAUNCHWIN_H, b_list);
+            set_ctrl_enable(self.hparent, IDC_ED_SLOT, param.hotkey_type == HotkeyType::WinSlot);
+            set_ctrl_enable(self.hparent, IDC_DDL_SORT_MODE, false);
+            set_ctrl_enable(self.hparent, IDC_ED_SORT_PROFILE, false);
+            set_ctrl_enable(self.hparent, IDC_CB_INHERIT_VIEW, false);
+
+            let text = match param.hotkey_type {
+                HotkeyType::WinTaskList => DLG_HK_ST_PROP_WINTASK_LIST,
+                HotkeyType::WinSlot => DLG_HK_ST_PROP_WIN_SLOT,
+                HotkeyType::CycleApp => DLG_HK_ST_PROP_CYCLE_APP,
+                HotkeyType::ToggleLast => DLG_HK_ST_PROP_TOGGLE_LAST,
+                _ => &param.path, // 起動か切替：プログラム
+            };
+            set_ctrl_text(self.hparent, IDC_ED_FILENAME, text);
+            set_ctrl_checked(self.hparent, IDC_CB_ATTENTION_TOP, param.b_attention_top);
+            set_ctrl_checked(self.hparent, IDC_CB_PROC_LINE, param.b_proc_line);
 
         } else {
             let pathtext = if !self.b_path_edit && param.path.len() == 0 { DLG_HK_ST_PROP_PATH_DESKTOP } else { &param.path };
             if self.b_path_edit {
                 set_ctrl_enable(self.hparent, IDC_ED_FILENAME, true);
                 set_ctrl_text(self.hparent, IDC_ED_FILENAME, pathtext);
+                self.update_resolved_path();
             } else {
                 set_ctrl_enable(self.hparent, IDC_ST_PATH, true);
                 set_ctrl_text(self.hparent, IDC_ST_PATH, pathtext);
@@ -622,6 +601,15 @@ impl DirPropertyPanel {
             set_ctrl_enable(self.hparent, IDC_RB_ICON_L, true);
             set_ctrl_enable(self.hparent, IDC_RB_ICON_S, true);
             set_ctrl_enable(self.hparent, IDC_CB_DISP_HIDDEN, true);
+            set_ctrl_enable(self.hparent, IDC_CB_ATTENTION_TOP, false);
+            set_ctrl_enable(self.hparent, IDC_CB_PROC_LINE, false);
+            set_ctrl_enable(self.hparent, IDC_ED_LAUNCHWIN_W, true);
+            set_ctrl_enable(self.hparent, IDC_ED_LAUNCHWIN_H, true);
+            set_ctrl_enable(self.hparent, IDC_ED_SLOT, false);
+            set_ctrl_enable(self.hparent, IDC_DDL_SORT_MODE, true);
+            // 並び順の保存名とサブフォルダへの適用はホットキーだけの設定
+            set_ctrl_enable(self.hparent, IDC_ED_SORT_PROFILE, self.b_path_edit);
+            set_ctrl_enable(self.hparent, IDC_CB_INHERIT_VIEW, self.b_path_edit);
 
             set_ctrl_checked(self.hparent, if param.hotkey_type == HotkeyType::IconLauncher { IDC_RB_ICON } else { IDC_RB_LIST }, true); // リスト or アイコン
 
@@ -638,31 +626,76 @@ impl DirPropertyPanel {
             }
         }
 
+        let sort_sel = if self.b_path_edit && !param.b_sort_set { 0 }
+            else { SortMode::ALL.iter().position(|m| *m == param.sort_mode).unwrap_or(0) + self.sort_sel_base() };
+        set_ctrl_cursel(self.hparent, IDC_DDL_SORT_MODE, sort_sel);
+        set_ctrl_checked(self.hparent, IDC_CB_FOLDERS_FIRST, param.b_folders_first);
+        set_ctrl_text(self.hparent, IDC_ED_SORT_PROFILE, &param.sort_profile);
+        set_ctrl_checked(self.hparent, IDC_CB_INHERIT_VIEW, param.b_inherit_view);
+        self.update_folders_first_enable();
+
         set_ctrl_int(self.hparent, IDC_ED_LAUNCHWIN_W, param.w as isize);
         set_ctrl_int(self.hparent, IDC_ED_LAUNCHWIN_H, param.h as isize);
+        if param.hotkey_type == HotkeyType::WinSlot {
+            set_ctrl_int(self.hparent, IDC_ED_SLOT, param.slot.max(1) as isize);
+        } else {
+            set_ctrl_text(self.hparent, IDC_ED_SLOT, "");
+        }
     }
 
-    pub fn ctrl_getvalue_dir_property(&self, b_type_launcher: bool) -> PropertyHolder {
+    // hk_kindはホットキーの種類(ランチャーのアイコン/リストはラジオボタンで決める)
+    pub fn ctrl_getvalue_dir_property(&self, hk_kind: &HotkeyType) -> PropertyHolder {
         let icon_rb_status = get_ctrl_checked(self.hparent, IDC_RB_ICON);
         let b_icon_large = get_ctrl_checked(self.hparent, IDC_RB_ICON_L);
         let path = if self.b_path_edit && is_ctrl_enable(self.hparent, IDC_ED_FILENAME) {
             get_ctrl_text(self.hparent, IDC_ED_FILENAME)
         } else { String::default() };
 
-        let (hk_type, path) = if b_type_launcher {
-            if icon_rb_status {
-                (HotkeyType::IconLauncher, path)
-            } else {
-                (HotkeyType::ListLauncher, path)
-            }
-        } else {
-            (HotkeyType::WinTaskList, String::default())
+        let (hk_type, path) = match hk_kind {
+            HotkeyType::RunOrRaise => (HotkeyType::RunOrRaise, path.trim().to_string()),
+            HotkeyType::WinTaskList | HotkeyType::WinSlot | HotkeyType::CycleApp | HotkeyType::ToggleLast => (hk_kind.clone(), String::default()),
+            _ if icon_rb_status => (HotkeyType::IconLauncher, path),
+            _ => (HotkeyType::ListLauncher, path),
         };
         let w = get_ctrl_int(self.hparent, IDC_ED_LAUNCHWIN_W) as u32;
         let h = get_ctrl_int(self.hparent, IDC_ED_LAUNCHWIN_H) as u32;
         let b_sysfile_hidden = !get_ctrl_checked(self.hparent, IDC_CB_DISP_HIDDEN);
+        let b_attention_top = hk_type == HotkeyType::WinTaskList && get_ctrl_checked(self.hparent, IDC_CB_ATTENTION_TOP);
+        let b_proc_line = hk_type == HotkeyType::WinTaskList && get_ctrl_checked(self.hparent, IDC_CB_PROC_LINE);
+        let slot = if hk_type == HotkeyType::WinSlot { (get_ctrl_int(self.hparent, IDC_ED_SLOT).max(0) as u32).clamp(1, WinSlot::MAX) } else { 0 };
+
+        let sort_sel = get_ctrl_cursel(self.hparent, IDC_DDL_SORT_MODE) as usize;
+        let (sort_mode, b_folders_first, b_sort_set) = if is_ctrl_enable(self.hparent, IDC_DDL_SORT_MODE) && sort_sel >= self.sort_sel_base() {
+            (SortMode::ALL.get(sort_sel - self.sort_sel_base()).copied().unwrap_or_default(),
+                get_ctrl_checked(self.hparent, IDC_CB_FOLDERS_FIRST), self.b_path_edit)
+        } else { (SortMode::default(), true, false) };
+
+        let (sort_profile, b_inherit_view) = if is_ctrl_enable(self.hparent, IDC_ED_SORT_PROFILE) {
+            (get_ctrl_text(self.hparent, IDC_ED_SORT_PROFILE).trim().to_string(), get_ctrl_checked(self.hparent, IDC_CB_INHERIT_VIEW))
+        } else { (String::default(), false) };
+
+        PropertyHolder { b_attention_top, b_proc_line, slot, sort_mode, b_folders_first, b_sort_set, sort_profile, b_inherit_view,
+            ..PropertyHolder::new(hk_type, b_icon_large, w, h, b_sysfile_hidden, path) }
+    }
+
+    // 並び順の選択肢の先頭位置(ホットキーでは先頭が「フォルダの設定」)
+    fn sort_sel_base(&self) -> usize {
+        if self.b_path_edit { 1 } else { 0 }
+    }
+
+    // 「フォルダの設定」の間はフォルダを先に表示するかもフォルダの設定に従う
+    fn update_folders_first_enable(&self) {
+        let b_enable = is_ctrl_enable(self.hparent, IDC_DDL_SORT_MODE)
+            && get_ctrl_cursel(self.hparent, IDC_DDL_SORT_MODE) as usize >= self.sort_sel_base();
+        set_ctrl_enable(self.hparent, IDC_CB_FOLDERS_FIRST, b_enable);
+    }
 
-        PropertyHolder::new(hk_type, b_icon_large, w, h, b_sysfile_hidden, path)
+    // 入力中のパスに環境変数・shell:指定があれば展開結果を表示する(保存するのは入力のまま)
+    fn update_resolved_path(&self) {
+        let path = get_ctrl_text(self.hparent, IDC_ED_FILENAME);
+        let resolved = ObjectHolder::expand_path(&path);
+        let text = if resolved.eq(&path) { String::default() } else { format!("→ {}", resolved) };
+        set_ctrl_text(self.hparent, IDC_ST_PATH_RESOLVED, &text);
     }
 }
 
@@ -697,10 +730,20 @@ impl WindowContainerMsgProc for DirPropertyPanelWeak {
                             if txt.starts_with("\"") { txt.remove(0); }
                             if txt.ends_with("\"") { txt.pop(); }
                             if txt.len() != len { set_ctrl_text(rc.hparent, IDC_ED_FILENAME, &txt); }
+                            rc.update_resolved_path();
+                        }
+                        IDC_ED_SORT_PROFILE => { // 区切り文字(カンマ, 縦棒)は保存名に使えない
+                            let rc = self.upgrade().unwrap();
+                            let txt = get_ctrl_text(rc.hparent, IDC_ED_SORT_PROFILE);
+                            let valid = txt.replace([',', '|'], "");
+                            if valid.len() != txt.len() { set_ctrl_text(rc.hparent, IDC_ED_SORT_PROFILE, &valid); }
                         }
                         _ => {}
                     }
                 }
+                if wparam.0 >> u16::BITS == CBN_SELCHANGE as usize && (wparam.0 & u16::MAX as usize) as isize == IDC_DDL_SORT_MODE {
+                    self.upgrade().unwrap().update_folders_first_enable();
+                }
                 return Some(LRESULT(0))
             }
 
@@ -709,3 +752,123 @@ impl WindowContainerMsgProc for DirPropertyPanelWeak {
         None
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+/// ダイアログ定義
+////////////////////////////////////////////////////////////////////////////////
+// ホットキー設定ダイアログ(ホットキーパネルは実行時にIDWC_HOTKEYSへ追加する)
+fn dlg_def_property() -> DlgItem {
+    let spacer = || DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(2, 1)).hstack(WidthAuto::FIX, AlignV::CENTER);
+    let button = |id, text| DlgItem::ctrl("BUTTON", id).caption(text).size(DlgSize::Char(5, 1), DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER);
+
+    DlgItem::cont(IDWC_ROOT)
+        .child(DlgItem::cont(IDWC_HOTKEYS).vstack(AlignH::LEFT, HeightAuto::FIX))
+        .child(DlgItem::cont(IDWC_H1_2).vstack(AlignH::FILL, HeightAuto::FIX)
+            .child(DlgItem::cont(IDWC_H1_3).hstack(WidthAuto::FIX, AlignV::FILL)
+                .child(spacer())
+                .child(button(IDC_BT_HOTKEY_ADD, DLG_HK_BT_TEXT_ADD))
+                .child(spacer()))
+            .child(DlgItem::cont(IDC_DUMMY).hstack(WidthAuto::AUTO, AlignV::FILL))
+            .child(DlgItem::cont(IDWC_H1_4).hstack(WidthAuto::FIX, AlignV::FILL)
+                .child(spacer())
+                .child(button(IDC_BT_OK, DLG_HK_BT_TEXT_APPLY))
+                .child(button(IDC_BT_CANCEL, DLG_HK_BT_TEXT_CANCEL))
+                .child(spacer())))
+}
+
+// ホットキー1件分のパネル(プロパティ設定パネルはIDWC_H2_2へ追加する)
+fn dlg_def_hotkey_panel() -> DlgItem {
+    let ddl_style = WINDOW_STYLE((CBS_HASSTRINGS | CBS_DROPDOWNLIST) as u32) | WS_TABSTOP;
+
+    DlgItem::cont(IDC_DUMMY)
+        .child(DlgItem::ctrl("STATIC", IDC_ST_PANEL).style(WINDOW_STYLE(SS_SUNKEN.0)).size(DlgSize::Px(0), DlgSize::Px(0)).place(0.0, 0.0))
+        .child(DlgItem::cont(IDWC_H1_1).place(2.0, 2.0)
+            .child(DlgItem::cont(IDWC_H2_1).hstack(WidthAuto::FIX, AlignV::FILL)
+                .child(DlgItem::cont(IDWC_H21_1).hstack(WidthAuto::FIX, AlignV::CENTER).pad(2)
+                    .child(DlgItem::ctrl("COMBOBOX", IDC_DDL_HOTKEY_KIND).style(ddl_style)
+                        .size(DlgSize::TextOf(DLG_HK_DDL_HKKIND_LENGTH), DlgSize::Char(7, 5)).vstack(AlignH::CENTER, HeightAuto::FIX))
+                    .child(DlgItem::cont(IDWC_H211_1).vstack(AlignH::FILL, HeightAuto::FIX)
+                        .child(DlgItem::ctrl("COMBOBOX", IDC_DDL_ALT_SHIFT).style(ddl_style)
+                            .size(DlgSize::TextOf(DLG_HK_CB_HKALT_LENGTH), DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER))
+                        .child(DlgItem::ctrl("EDIT", IDC_ED_HOTKEY_CHR).style(WS_TABSTOP)
+                            .size(DlgSize::Char(1, 1), DlgSize::Char(1, 1)).hstack(WidthAuto::FIX, AlignV::CENTER)))
+                    .child(DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(1, 2)).vstack(AlignH::CENTER, HeightAuto::FIX))
+                    .child(DlgItem::ctrl("BUTTON", IDC_BT_HOTKEY_DEL).caption(DLG_HK_BT_TEXT_DEL).style(WS_TABSTOP)
+                        .size(DlgSize::Text, DlgSize::Char(7, 5)).vstack(AlignH::CENTER, HeightAuto::FIX))))
+            .child(DlgItem::cont(IDWC_H2_2).hstack(WidthAuto::FIX, AlignV::FILL)))
+}
+
+// プロパティ設定パネル(見出し列と設定値列の2列グリッド)
+pub fn dlg_def_dir_property(b_path_edit: bool) -> DlgItem {
+    let radio_style = WINDOW_STYLE(BS_AUTORADIOBUTTON as u32) | WS_TABSTOP;
+    let radio = |id, text, style| DlgItem::ctrl("BUTTON", id).caption(text).style(style)
+        .size(DlgSize::Char(5, 1), DlgSize::Text).hstack(WidthAuto::FIX, AlignV::FILL);
+    let label = |text| DlgItem::ctrl("STATIC", IDC_DUMMY).caption(text).hstack(WidthAuto::FIX, AlignV::FILL);
+    let num_edit = |id| DlgItem::ctrl("EDIT", id).style(WS_TABSTOP)
+        .size(DlgSize::TextOf(DLG_HK_ST_PROP_SIZEEDIT_SIZE), DlgSize::TextOf(DLG_HK_ST_PROP_SIZEEDIT_SIZE)).hstack(WidthAuto::FIX, AlignV::FILL);
+
+    let path_style = WINDOW_STYLE(SS_PATHELLIPSIS.0 as u32);
+    let path = if b_path_edit { // 入力欄の下に展開後のパス
+        DlgItem::cont(IDWC_H112_0).grid(0, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+            .child(DlgItem::ctrl("EDIT", IDC_ED_FILENAME).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP)
+                .size(DlgSize::Char(15, 1), DlgSize::Text).vstack(AlignH::FILL, HeightAuto::FIX))
+            .child(DlgItem::ctrl("STATIC", IDC_ST_PATH_RESOLVED).style(path_style)
+                .size(DlgSize::Char(15, 1), DlgSize::Char(1, 1)).vstack(AlignH::FILL, HeightAuto::FIX))
+    } else {
+        DlgItem::ctrl("STATIC", IDC_ST_PATH).style(path_style)
+            .size(DlgSize::Char(15, 1), DlgSize::Text).grid(0, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+    };
+
+    DlgItem::cont(IDC_DUMMY)
+        .child(DlgItem::cont(IDWC_H11_1).hstack(WidthAuto::AUTO, AlignV::FILL)
+            .grid_define(&vec![GridSize::AUTO; DLG_HK_ST_PROP_CAPTIONS.len()], &[GridSize::AUTO, GridSize::STAR(1)])
+            .children(DLG_HK_ST_PROP_CAPTIONS.iter().enumerate().map(|(i, text)|
+                DlgItem::ctrl("STATIC", IDC_DUMMY).caption(text).grid(i, 0, 1, 1, AlignH::RIGHT, AlignV::CENTER)))
+            .child(path)
+            .child(DlgItem::cont(IDWC_H112_1).grid(1, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+                .child(radio(IDC_RB_LIST, DLG_HK_RB_LIST_ICON[0], WS_GROUP | radio_style))
+                .child(radio(IDC_RB_ICON, DLG_HK_RB_LIST_ICON[1], radio_style)))
+            .child(DlgItem::cont(IDWC_H112_2).grid(2, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+                .child(radio(IDC_RB_ICON_L, DLG_HK_RB_LARGE_SMALL[0], WS_GROUP | radio_style))
+                .child(radio(IDC_RB_ICON_S, DLG_HK_RB_LARGE_SMALL[1], radio_style)))
+            .child(DlgItem::cont(IDWC_H112_3).grid(3, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+                .child(label(DLG_HK_ST_PROP_SIZEEDIT_CAP[0]))
+                .child(num_edit(IDC_ED_LAUNCHWIN_W))
+                .child(label(DLG_HK_ST_PROP_SIZEEDIT_CAP[1]))
+                .child(num_edit(IDC_ED_LAUNCHWIN_H))
+                .child(label(DLG_HK_ST_PROP_SIZEEDIT_CAP[2])))
+            .child(DlgItem::ctrl("BUTTON", IDC_CB_DISP_HIDDEN).caption(DLG_HK_CB_DISP_HIDDEN).style(WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP)
+                .size(DlgSize::Px(0), DlgSize::Text).grid(4, 1, 1, 1, AlignH::FILL, AlignV::CENTER))
+            .child(DlgItem::ctrl("BUTTON", IDC_CB_ATTENTION_TOP).caption(DLG_HK_CB_ATTENTION_TOP).style(WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP)
+                .size(DlgSize::Px(0), DlgSize::Text).grid(5, 1, 1, 1, AlignH::FILL, AlignV::CENTER))
+            .child(DlgItem::ctrl("BUTTON", IDC_CB_PROC_LINE).caption(DLG_HK_CB_PROC_LINE).style(WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP)
+                .size(DlgSize::Px(0), DlgSize::Text).grid(6, 1, 1, 1, AlignH::FILL, AlignV::CENTER))
+            .child(DlgItem::ctrl("EDIT", IDC_ED_SLOT).style(WINDOW_STYLE(ES_NUMBER as u32) | WS_TABSTOP)
+                .size(DlgSize::Char(3, 1), DlgSize::Text).grid(7, 1, 1, 1, AlignH::LEFT, AlignV::CENTER))
+            .child(DlgItem::cont(IDWC_H112_4).grid(8, 1, 1, 1, AlignH::FILL, AlignV::CENTER)
+                .child(DlgItem::ctrl("COMBOBOX", IDC_DDL_SORT_MODE).style(WINDOW_STYLE((CBS_HASSTRINGS | CBS_DROPDOWNLIST) as u32) | WS_TABSTOP)
+                    .size(DlgSize::TextOf(DLG_HK_DDL_SORT_MODE_LENGTH), DlgSize::Char(7, 5)).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::spacer(DlgSize::Char(1, 1), DlgSize::Px(0)).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::ctrl("BUTTON", IDC_CB_FOLDERS_FIRST).caption(DLG_HK_CB_FOLDERS_FIRST).style(WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP)
+                    .size(DlgSize::Px(0), DlgSize::Text).hstack(WidthAuto::AUTO, AlignV::CENTER)))
+            .child(DlgItem::ctrl("EDIT", IDC_ED_SORT_PROFILE).style(WINDOW_STYLE(ES_AUTOHSCROLL as u32) | WS_TABSTOP)
+                .size(DlgSize::TextOf(DLG_HK_DDL_SORT_MODE_LENGTH), DlgSize::Text).grid(9, 1, 1, 1, AlignH::LEFT, AlignV::CENTER))
+            .child(DlgItem::ctrl("BUTTON", IDC_CB_INHERIT_VIEW).caption(DLG_HK_CB_INHERIT_VIEW).style(WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | WS_TABSTOP)
+                .size(DlgSize::Px(0), DlgSize::Text).grid(10, 1, 1, 1, AlignH::FILL, AlignV::CENTER)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn property_dialog_definition_is_valid() {
+        // パネルはダイアログ内に配置され、get_ctrlはダイアログ全体から探すため重複チェックは合算で行う
+        assert_eq!(dlg_validate(&[&dlg_def_property(), &dlg_def_hotkey_panel(), &dlg_def_dir_property(true)]), Ok(()));
+    }
+
+    #[test]
+    fn dir_property_definition_is_valid() {
+        assert_eq!(dlg_validate(&[&dlg_def_dir_property(false)]), Ok(()));
+    }
+}
@@ -3,7 +3,7 @@ use super::*;
 pub const VK_LIST: [VIRTUAL_KEY; 12] = [VK_NONAME, VK_OEM_8, VK_OEM_MINUS, VK_OEM_PLUS, VK_OEM_4, VK_OEM_6, VK_OEM_5, VK_OEM_1, VK_OEM_7, VK_OEM_COMMA, VK_OEM_PERIOD, VK_OEM_2];
 pub const VK_CHARS: [char; 12] = ['!', '`', '-', '=', '[', ']', '\\', ';', '\'', ',', '.', '/'];
 
-pub const TASKTRAY_MENU: [PCWSTR; 3] = [w!("Hotkey Property"), w!("Window List: Sort Edit"), w!("Quit"), ];
+pub const TASKTRAY_MENU: [PCWSTR; 7] = [w!("Hotkey Property"), w!("Window List: Sort Edit"), w!("Window List: Filter Rules"), w!("Window List: Title Rules"), w!("Window List: Slots"), w!("Folder Settings"), w!("Quit"), ];
 pub const TASKTARY_DEFAULT_CAPTION: PCWSTR = w!("Set [None] + [!] hotkey in Hotkey Property dialog box.");
 
 pub const DLG_FV_ST_RENAME: &str = "New Filename(&N):";
@@ -11,6 +11,48 @@ pub const DLG_FV_BT_TEXT_APPLY: &str = "Apply";
 pub const DLG_FV_BT_TEXT_OK: &str = "OK";
 pub const DLG_FV_BT_TEXT_CANCEL: &str = "Cancel";
 
+pub const DLG_FM_BT_SEL_MISSING: &str = "Select Missing(&M)";
+pub const DLG_FM_BT_DELETE: &str = "Delete(&D)";
+pub const DLG_FM_BT_EDIT: &str = "Edit(&E)";
+pub const DLG_FM_BT_EXPORT: &str = "Copy(&C)";
+pub const DLG_FM_BT_CLOSE: &str = "Close";
+pub const DLG_FM_MISSING_MARK: &str = "[missing] ";
+pub const DLG_FM_CONFIRM_DELETE: PCWSTR = w!("Delete the selected folder settings?");
+
+pub const DLG_WF_ST_CAPTIONS: [&str; 5] = [ "Action: ", "Class: ", "Title: ", "Process: ", "Style: " ];
+pub const DLG_WF_DDL_ACTION: [PCWSTR; 2] = [w!("Hide"), w!("Show")];
+pub const DLG_WF_DDL_ACTION_LENGTH: &str = "Showwww";
+pub const DLG_WF_CB_STYLE: [&str; 3] = [ "Tool window", "Owned", "Topmost" ];
+pub const DLG_WF_BT_ADD: &str = "Add(&A)";
+pub const DLG_WF_BT_UPDATE: &str = "Update(&U)";
+pub const DLG_WF_BT_DELETE: &str = "Delete(&D)";
+pub const DLG_WF_BT_UP: &str = "Up";
+pub const DLG_WF_BT_DOWN: &str = "Down";
+
+pub const DLG_TR_ST_CAPTIONS: [&str; 6] = [ "Action: ", "Process: ", "Find: ", "Replace with: ", "Sample title: ", "Result: " ];
+pub const DLG_TR_DDL_ACTION: [PCWSTR; 4] = [w!("Strip suffix"), w!("Strip prefix"), w!("Regex replace"), w!("Swap segments")];
+pub const DLG_TR_DDL_ACTION_LENGTH: &str = "Swap segmentsww";
+
+pub const DLG_SL_ST_CAPTIONS: [&str; 3] = [ "Slot: ", "Process: ", "Title: " ];
+pub const DLG_SL_DDL_SLOT: [PCWSTR; 9] = [w!("Slot 1"), w!("Slot 2"), w!("Slot 3"), w!("Slot 4"), w!("Slot 5"), w!("Slot 6"), w!("Slot 7"), w!("Slot 8"), w!("Slot 9")];
+pub const DLG_SL_DDL_SLOT_LENGTH: &str = "Slot 9www";
+
+pub const DLG_SE_ST_CAPTIONS: [&str; 3] = [ "Rule: ", "Matches: ", "History: " ];
+pub const DLG_SE_BT_ADD: &str = "Add(&A)";
+pub const DLG_SE_BT_UPDATE: &str = "Update(&U)";
+pub const DLG_SE_BT_DELETE: &str = "Delete(&D)";
+pub const DLG_SE_BT_WILDCARD: &str = "To Wildcard(&W)";
+pub const DLG_SE_BT_BROWSE: &str = "Browse(&B)...";
+pub const DLG_SE_OTHER_RULE_MARK: &str = "[other rule] ";
+pub const DLG_SE_INVALID_PATTERN: &str = "(invalid regular expression)";
+pub const DLG_SE_FILTER_EXE: PCWSTR = w!("Programs (*.exe)");
+pub const DLG_SE_CB_HISTORY: &str = "Show processes seen within";
+pub const DLG_SE_ST_DAYS: &str = " days";
+pub const DLG_SE_BT_PRUNE: &str = "Prune(&P)";
+pub const DLG_SE_CONFIRM_PRUNE: PCWSTR = w!("Remove processes not seen within the period, or whose file no longer exists, from the history?");
+pub const DLG_SE_DAYS_AGO: &str = " days ago";
+pub const DLG_SE_ST_FILTER: &str = "Filter candidates: ";
+
 pub const DLG_HK_CAP_INPUT_INVALID: PCWSTR = w!("Invalid input.");
 
 pub const DLG_HK_BT_TEXT_ADD: &str = "Add(&A)";
@@ -18,21 +60,36 @@ pub const DLG_HK_BT_TEXT_APPLY: &str = "Apply(&O)";
 pub const DLG_HK_BT_TEXT_CANCEL: &str = "Cancel";
 pub const DLG_HK_BT_TEXT_DEL: &str = "Delete";
 
-pub const DLG_HK_DDL_HKKIND_LENGTH: &str = "Window Listwww";
+pub const DLG_HK_DDL_HKKIND_LENGTH: &str = "Cycle App Windowsww";
 pub const DLG_HK_CB_HKALT_LENGTH: &str = "SHIFTwww";
 
-pub const DLG_HK_DDL_HKKIND: [PCWSTR; 2] = [w!("Launcher"), w!("Window List")];
+pub const DLG_HK_DDL_HKKIND: [PCWSTR; 6] = [w!("Launcher"), w!("Window List"), w!("Window Slot"), w!("Run or Raise"), w!("Cycle App Windows"), w!("Previous Window")];
 pub const DLG_HK_DDL_MODKEY: [PCWSTR; 3] = [w!("ALT"), w!("SHIFT"), w!("NONE")];
 
-pub const DLG_HK_ST_PROP_CAPTIONS: [&str; 5] = [ "Target folder(&T): ", "View: ", "Icon size: ", "Window size: ", "System hidden file: " ];
+pub const DLG_HK_ST_PROP_CAPTIONS: [&str; 11] = [ "Target folder(&T): ", "View: ", "Icon size: ", "Window size: ", "System hidden file: ", "Attention: ", "Item text: ", "Slot: ", "Sort: ", "Sort profile: ", "Subfolders: " ];
 pub const DLG_HK_RB_LIST_ICON: [&str; 2] = [ "List", "Icon" ];
 pub const DLG_HK_RB_LARGE_SMALL: [&str; 2] = [ "Large", "Small" ];
 pub const DLG_HK_ST_PROP_SIZEEDIT_CAP: [&str; 3] = [ "W", "× H", "px/icons" ];
 pub const DLG_HK_CB_DISP_HIDDEN: &str = "Display enable";
+pub const DLG_HK_CB_ATTENTION_TOP: &str = "Move to top";
+pub const DLG_HK_CB_PROC_LINE: &str = "Show process name on a second line";
+pub const DLG_HK_DDL_SORT_MODE: [PCWSTR; 5] = [w!("Manual"), w!("Name"), w!("Name (numeric)"), w!("Type"), w!("Date modified")];
+pub const DLG_HK_DDL_SORT_MODE_LENGTH: &str = "Name (numeric)www";
+pub const DLG_HK_CB_FOLDERS_FIRST: &str = "Folders first";
+pub const DLG_HK_DDL_SORT_FOLDER: PCWSTR = w!("Folder setting");
+pub const DLG_HK_CB_INHERIT_VIEW: &str = "Open with these view settings";
 pub const DLG_HK_ST_PROP_SIZEEDIT_SIZE: &str = "999999";
 pub const DLG_HK_ST_PROP_WINTASK_LIST: &str = "Window List";
+pub const DLG_HK_ST_PROP_WIN_SLOT: &str = "Switch to the window in the slot";
+pub const DLG_HK_ST_PROP_CYCLE_APP: &str = "Cycle through the windows of the foreground app";
+pub const DLG_HK_ST_PROP_TOGGLE_LAST: &str = "Switch back to the previously active window";
 pub const DLG_HK_ST_PROP_PATH_DESKTOP: &str = "Desktop";
 
 pub const POPUP_MENUITEM_PROP: PCWSTR = w!("Subfolder View Property");
 pub const POPUP_MENUITEM_SORT_RESET: PCWSTR = w!("Reset File Sort");
+pub const WINITEM_MENU_SLOT: &str = "Slot ";
+pub const WINITEM_MENU_SLOT_CLEAR: PCWSTR = w!("Clear Slot");
+pub const WINITEM_HUNG_MARK: &str = " (Not Responding)";
+pub const WINITEM_TIP_LABELS: [&str; 5] = [ "Process: ", "PID: ", "State: ", "Monitor: ", "AppUserModelID: " ];
+pub const WINITEM_TIP_STATES: [&str; 5] = [ "Normal", "Minimized", "Maximized", "Topmost", "Elevated" ];
 pub const OBJECTITEM_EMPTY: &str = "( empty )";
@@ -3,7 +3,7 @@ use super::*;
 pub const VK_LIST: [VIRTUAL_KEY; 13] = [VK_NONAME, VK_OEM_MINUS, VK_OEM_7, VK_OEM_5, VK_OEM_3, VK_OEM_4, VK_OEM_PLUS, VK_OEM_1, VK_OEM_6, VK_OEM_COMMA, VK_OEM_PERIOD, VK_OEM_2, VK_OEM_102];
 pub const VK_CHARS: [char; 13] = ['!', '-', '^', '|', '@', '[', ';', ':', ']', ',', '.', '/', '_'];
 
-pub const TASKTRAY_MENU: [PCWSTR; 3] = [w!("ホットキー設定"), w!("ウィンドウリスト:ソート設定"), w!("終了"), ];
+pub const TASKTRAY_MENU: [PCWSTR; 7] = [w!("ホットキー設定"), w!("ウィンドウリスト:ソート設定"), w!("ウィンドウリスト:表示ルール"), w!("ウィンドウリスト:タイトル書き換え"), w!("ウィンドウリスト:スロット"), w!("フォルダ設定の管理"), w!("終了"), ];
 pub const TASKTARY_DEFAULT_CAPTION: PCWSTR = w!("ホットキー設定画面にて「 ! 」を指定し動作を設定");
 
 pub const DLG_FV_ST_RENAME: &str = "新しいファイル名(&N):";
@@ -11,6 +11,48 @@ pub const DLG_FV_BT_TEXT_APPLY: &str = "設定";
 pub const DLG_FV_BT_TEXT_OK: &str = "ＯＫ";
 pub const DLG_FV_BT_TEXT_CANCEL: &str = "キャンセル";
 
+pub const DLG_FM_BT_SEL_MISSING: &str = "存在しないものを選択(&M)";
+pub const DLG_FM_BT_DELETE: &str = "削除(&D)";
+pub const DLG_FM_BT_EDIT: &str = "編集(&E)";
+pub const DLG_FM_BT_EXPORT: &str = "コピー(&C)";
+pub const DLG_FM_BT_CLOSE: &str = "閉じる";
+pub const DLG_FM_MISSING_MARK: &str = "［なし］";
+pub const DLG_FM_CONFIRM_DELETE: PCWSTR = w!("選択したフォルダ設定を削除しますか？");
+
+pub const DLG_WF_ST_CAPTIONS: [&str; 5] = [ "動作：", "クラス名：", "タイトル：", "プロセス：", "スタイル：" ];
+pub const DLG_WF_DDL_ACTION: [PCWSTR; 2] = [w!("非表示"), w!("表示")];
+pub const DLG_WF_DDL_ACTION_LENGTH: &str = "非表示ああ";
+pub const DLG_WF_CB_STYLE: [&str; 3] = [ "ツールウィンドウ", "オーナーあり", "最前面" ];
+pub const DLG_WF_BT_ADD: &str = "追加(&A)";
+pub const DLG_WF_BT_UPDATE: &str = "更新(&U)";
+pub const DLG_WF_BT_DELETE: &str = "削除(&D)";
+pub const DLG_WF_BT_UP: &str = "上へ";
+pub const DLG_WF_BT_DOWN: &str = "下へ";
+
+pub const DLG_TR_ST_CAPTIONS: [&str; 6] = [ "動作：", "プロセス：", "検索：", "置換後：", "見本のタイトル：", "結果：" ];
+pub const DLG_TR_DDL_ACTION: [PCWSTR; 4] = [w!("末尾を削除"), w!("先頭を削除"), w!("正規表現で置換"), w!("区切りで入れ替え")];
+pub const DLG_TR_DDL_ACTION_LENGTH: &str = "区切りで入れ替えああ";
+
+pub const DLG_SL_ST_CAPTIONS: [&str; 3] = [ "スロット：", "プロセス：", "タイトル：" ];
+pub const DLG_SL_DDL_SLOT: [PCWSTR; 9] = [w!("スロット 1"), w!("スロット 2"), w!("スロット 3"), w!("スロット 4"), w!("スロット 5"), w!("スロット 6"), w!("スロット 7"), w!("スロット 8"), w!("スロット 9")];
+pub const DLG_SL_DDL_SLOT_LENGTH: &str = "スロット 9ああ";
+
+pub const DLG_SE_ST_CAPTIONS: [&str; 3] = [ "ルール：", "一致：", "履歴：" ];
+pub const DLG_SE_BT_ADD: &str = "追加(&A)";
+pub const DLG_SE_BT_UPDATE: &str = "更新(&U)";
+pub const DLG_SE_BT_DELETE: &str = "削除(&D)";
+pub const DLG_SE_BT_WILDCARD: &str = "ワイルドカード化(&W)";
+pub const DLG_SE_BT_BROWSE: &str = "参照(&B)...";
+pub const DLG_SE_OTHER_RULE_MARK: &str = "［他のルール］";
+pub const DLG_SE_INVALID_PATTERN: &str = "（正規表現が不正です）";
+pub const DLG_SE_FILTER_EXE: PCWSTR = w!("プログラム (*.exe)");
+pub const DLG_SE_CB_HISTORY: &str = "候補に表示する期間";
+pub const DLG_SE_ST_DAYS: &str = "日以内";
+pub const DLG_SE_BT_PRUNE: &str = "整理(&P)";
+pub const DLG_SE_CONFIRM_PRUNE: PCWSTR = w!("期間内に見かけていないプロセスと、ファイルが無くなったプロセスを履歴から削除しますか？");
+pub const DLG_SE_DAYS_AGO: &str = "日前";
+pub const DLG_SE_ST_FILTER: &str = "候補の絞り込み：";
+
 pub const DLG_HK_CAP_INPUT_INVALID: PCWSTR = w!("入力不正あり");
 
 pub const DLG_HK_BT_TEXT_ADD: &str = "追加(&A)";
@@ -21,18 +63,33 @@ pub const DLG_HK_BT_TEXT_DEL: &str = "削除";
 pub const DLG_HK_DDL_HKKIND_LENGTH: &str = "ウィンドウリストああ";
 pub const DLG_HK_CB_HKALT_LENGTH: &str = "SHIFTああ";
 
-pub const DLG_HK_DDL_HKKIND: [PCWSTR; 2] = [w!("ランチャー"), w!("ウィンドウリスト")];
+pub const DLG_HK_DDL_HKKIND: [PCWSTR; 6] = [w!("ランチャー"), w!("ウィンドウリスト"), w!("ウィンドウスロット"), w!("起動か切替"), w!("アプリ内で切替"), w!("直前のウィンドウ")];
 pub const DLG_HK_DDL_MODKEY: [PCWSTR; 3] = [w!("ALT"), w!("SHIFT"), w!("NONE")];
 
-pub const DLG_HK_ST_PROP_CAPTIONS: [&str; 5] = [ "ターゲット(&T)：", "表示：", "アイコンサイズ：", "ウィンドウサイズ：", "システムファイル：" ];
+pub const DLG_HK_ST_PROP_CAPTIONS: [&str; 11] = [ "ターゲット(&T)：", "表示：", "アイコンサイズ：", "ウィンドウサイズ：", "システムファイル：", "通知ウィンドウ：", "項目の表示：", "スロット：", "並び順：", "並び順の保存名：", "サブフォルダ：" ];
 pub const DLG_HK_RB_LIST_ICON: [&str; 2] = [ "リスト", "アイコン" ];
 pub const DLG_HK_RB_LARGE_SMALL: [&str; 2] = [ "大", "小" ];
 pub const DLG_HK_ST_PROP_SIZEEDIT_CAP: [&str; 3] = [ "W", "× H", "px/icons" ];
 pub const DLG_HK_CB_DISP_HIDDEN: &str = "表示";
+pub const DLG_HK_CB_ATTENTION_TOP: &str = "先頭に表示";
+pub const DLG_HK_CB_PROC_LINE: &str = "2行目にプロセス名を表示";
+pub const DLG_HK_DDL_SORT_MODE: [PCWSTR; 5] = [w!("手動"), w!("名前"), w!("名前(数値順)"), w!("種類"), w!("更新日時")];
+pub const DLG_HK_DDL_SORT_MODE_LENGTH: &str = "名前(数値順)ああ";
+pub const DLG_HK_CB_FOLDERS_FIRST: &str = "フォルダを先に表示";
+pub const DLG_HK_DDL_SORT_FOLDER: PCWSTR = w!("フォルダの設定");
+pub const DLG_HK_CB_INHERIT_VIEW: &str = "この表示設定で開く";
 pub const DLG_HK_ST_PROP_SIZEEDIT_SIZE: &str = "999999";
 pub const DLG_HK_ST_PROP_WINTASK_LIST: &str = "ウィンドウタスクリスト";
+pub const DLG_HK_ST_PROP_WIN_SLOT: &str = "スロットのウィンドウへ切り替え";
+pub const DLG_HK_ST_PROP_CYCLE_APP: &str = "前面のアプリのウィンドウを順に切り替え";
+pub const DLG_HK_ST_PROP_TOGGLE_LAST: &str = "直前にアクティブだったウィンドウへ戻る";
 pub const DLG_HK_ST_PROP_PATH_DESKTOP: &str = "デスクトップ";
 
 pub const POPUP_MENUITEM_PROP: PCWSTR = w!("子フォルダ表示プロパティ");
 pub const POPUP_MENUITEM_SORT_RESET: PCWSTR = w!("ソート順リセット");
+pub const WINITEM_MENU_SLOT: &str = "スロット ";
+pub const WINITEM_MENU_SLOT_CLEAR: PCWSTR = w!("スロットを解除");
+pub const WINITEM_HUNG_MARK: &str = "（応答なし）";
+pub const WINITEM_TIP_LABELS: [&str; 5] = [ "プロセス：", "PID：", "状態：", "モニタ：", "AppUserModelID：" ];
+pub const WINITEM_TIP_STATES: [&str; 5] = [ "通常", "最小化", "最大化", "最前面", "管理者権限" ];
 pub const OBJECTITEM_EMPTY: &str = "（なし）";
@@ -1,11 +1,13 @@
-use std::rc::{Rc, Weak};
+use std::{cell::Cell, rc::{Rc, Weak}, sync::{Arc, atomic::{AtomicBool, Ordering}}};
+use fxhash::FxHashMap;
 use imp::CoTaskMemFree;
 use windows::{
     core::*,
     Win32::{
         Foundation::*,
         UI::{WindowsAndMessaging::*, Shell::Common::ITEMIDLIST},
-        System::Registry::*, Graphics::Gdi::{HFONT, DeleteObject},
+        System::{Registry::*, Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED}, DataExchange::*, Memory::*, Ole::CF_UNICODETEXT},
+        Graphics::Gdi::{HBITMAP, HFONT, DeleteObject},
     },
 };
 
@@ -57,6 +59,86 @@ pub fn wnd_instance<T>(h: HWND) -> Weak<T> {
     }
 }
 
+// バックグラウンド処理用のSTAスレッド。Dropで中断フラグを立てる(処理側で随時確認する)
+pub struct StaWorker {
+    cancel: Arc<AtomicBool>,
+}
+
+impl Drop for StaWorker {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl StaWorker {
+    pub fn spawn<F>(f: F) -> Self
+    where F: FnOnce(&AtomicBool) + Send + 'static {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let c = cancel.clone();
+        std::thread::spawn(move || {
+            let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+            f(&c);
+            unsafe { CoUninitialize(); }
+        });
+        Self { cancel }
+    }
+}
+
+// 別スレッドからBox化した値をウィンドウへ送る(送れなかった場合はここで破棄)
+pub fn post_boxed<T>(hwnd: HWND, msg: u32, wparam: WPARAM, v: Box<T>) -> bool {
+    let p = Box::into_raw(v);
+    if unsafe { PostMessageW(hwnd, msg, wparam, LPARAM(p as _)) }.is_err() {
+        drop(unsafe { Box::from_raw(p) });
+        return false
+    }
+    true
+}
+
+// post_boxedで送られた値を受け取る
+pub fn take_boxed<T>(lparam: LPARAM) -> Box<T> {
+    unsafe { Box::from_raw(lparam.0 as *mut T) }
+}
+
+// 上限付きのキャッシュ。上限を超えたら最後に使ってから最も時間の経ったものを捨てる
+pub struct LruCache<V> {
+    map: FxHashMap<String, (Cell<u64>, V)>, // K, (最後に使った時の通し番号, V)
+    tick: Cell<u64>,
+    max: usize,
+}
+
+impl<V> LruCache<V> {
+    pub fn new(max: usize) -> Self {
+        Self { map: FxHashMap::default(), tick: Cell::new(0), max }
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.tick.set(self.tick.get() + 1);
+        self.tick.get()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let (used, v) = self.map.get(key)?;
+        used.set(self.next_tick());
+        Some(v)
+    }
+
+    pub fn insert(&mut self, key: String, v: V) {
+        if !self.map.contains_key(&key) && self.map.len() >= self.max {
+            let oldest = self.map.iter().min_by_key(|(_, (used, _))| used.get()).map(|(k, _)| k.clone());
+            if let Some(k) = oldest { self.map.remove(&k); }
+        }
+        let used = Cell::new(self.next_tick());
+        self.map.insert(key, (used, v));
+    }
+
+    // 最近使った順に並べる
+    pub fn iter_recent(&self) -> Vec<(&String, &V)> {
+        let mut v = self.map.iter().map(|(k, (used, v))| (used.get(), k, v)).collect::<Vec<_>>();
+        v.sort_by_key(|(used, _, _)| std::cmp::Reverse(*used));
+        v.into_iter().map(|(_, k, v)| (k, v)).collect()
+    }
+}
+
 pub struct Handle (pub HANDLE);
 impl Drop for Handle {
     fn drop(&mut self) {
@@ -71,6 +153,13 @@ impl Drop for Menu {
     }
 }
 
+pub struct Bitmap(pub HBITMAP);
+impl Drop for Bitmap {
+    fn drop(&mut self) {
+        if self.0.0 != 0 { let _ = unsafe { DeleteObject(self.0) }; }
+    }
+}
+
 pub struct Font(pub HFONT);
 impl Drop for Font {
     fn drop(&mut self) {
@@ -108,6 +197,23 @@ pub fn delete_reg(key: &str, vname: &str) -> Result<()> {
     unsafe { RegDeleteKeyValueW(h.0, None, val_name.PCWSTR()) }
 }
 
+pub fn enum_reg_value_names(key: &str) -> Vec<String> {
+    let mut v = Vec::<String>::default();
+    let mut h = RegKey(HKEY(0)); // auto drop resouce
+    let r = unsafe { RegCreateKeyExW(HKEY_CURRENT_USER, WSTR::from(key).PCWSTR(), 0,
+        None, REG_OPTION_NON_VOLATILE, KEY_READ, None, &mut h.0, None) };
+    if r.is_err() { return v }
+
+    let mut buf = vec![0u16; 16384]; // 値の名前の最大長
+    for idx in 0.. {
+        let mut len = buf.len() as u32;
+        let r = unsafe { RegEnumValueW(h.0, idx, PWSTR::from_raw(buf.as_mut_ptr()), &mut len, None, None, None, None) };
+        if r.is_err() { break }
+        v.push(String::from_utf16_lossy(&buf[..len as usize]));
+    }
+    v
+}
+
 pub fn load_reg_sz(key: &str, vname: &str) -> String {
     let mut h = RegKey(HKEY(0)); // auto drop resouce
     let r = unsafe { RegCreateKeyExW(HKEY_CURRENT_USER, WSTR::from(key).PCWSTR(), 0,
@@ -178,6 +284,24 @@ pub fn store_reg_multi_sz(key: &str, vname: &str, vec: &Vec<String>) -> Result<(
     unsafe { RegSetValueExW(h.0, val_name.PCWSTR(), 0, REG_MULTI_SZ, Some(&buf.to_vec_byte())) }
 }
 
+pub fn set_clipboard_text(hwnd: HWND, text: &str) -> Result<()> {
+    let buf = WSTR::from(text);
+    unsafe { OpenClipboard(hwnd) }?;
+    let r = (|| -> Result<()> { unsafe {
+        EmptyClipboard()?;
+        let hg = GlobalAlloc(GMEM_MOVEABLE, buf.0.len() * std::mem::size_of::<u16>())?;
+        std::ptr::copy_nonoverlapping(buf.0.as_ptr(), GlobalLock(hg) as *mut u16, buf.0.len());
+        let _ = GlobalUnlock(hg);
+        if let Err(e) = SetClipboardData(CF_UNICODETEXT.0 as u32, HANDLE(hg.0 as isize)) {
+            let _ = GlobalFree(hg); // 成功時はクリップボードが所有する
+            return Err(e)
+        }
+        Ok(())
+    }})();
+    let _ = unsafe { CloseClipboard() };
+    r
+}
+
 pub struct WSTR (pub Vec<u16>);
 
 #[allow(dead_code)]
@@ -274,4 +398,22 @@ impl WSTR {
     pub fn PWSTR(&mut self) -> PWSTR {
         PWSTR::from_raw(&mut self.0[0])
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lru_cache_evicts_least_recently_used() {
+        let mut c = LruCache::new(2);
+        c.insert("a".to_string(), 1);
+        c.insert("b".to_string(), 2);
+        assert_eq!(c.get("a"), Some(&1)); // bより後に使った
+        c.insert("c".to_string(), 3);
+        assert_eq!((c.get("a"), c.get("b"), c.get("c")), (Some(&1), None, Some(&3)));
+        c.insert("a".to_string(), 4); // 既にあるキーの更新では捨てない
+        assert_eq!((c.get("a"), c.get("c")), (Some(&4), Some(&3)));
+        assert_eq!(c.iter_recent(), [(&"c".to_string(), &3), (&"a".to_string(), &4)]);
+    }
 }
\ No newline at end of file
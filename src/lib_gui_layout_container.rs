@@ -1,15 +1,16 @@
 use std::{rc::{Rc, Weak}, sync::Once};
-use fxhash::FxHashMap;
 use windows::{
     core::*,
     Win32::{
         Foundation::*,
         Graphics::Gdi::*,
-        UI::{Controls::{SetScrollInfo, BST_CHECKED, BST_UNCHECKED}, HiDpi::{AdjustWindowRectExForDpi, GetDpiForMonitor, GetDpiForWindow, GetSystemMetricsForDpi, SystemParametersInfoForDpi, MDT_EFFECTIVE_DPI}, Input::KeyboardAndMouse::{EnableWindow, IsWindowEnabled}, Shell::{DefSubclassProc, SetWindowSubclass}, WindowsAndMessaging::* },
+        UI::{Controls::{SetScrollInfo, BST_CHECKED, BST_INDETERMINATE, BST_UNCHECKED}, HiDpi::{AdjustWindowRectExForDpi, GetDpiForMonitor, GetDpiForWindow, GetSystemMetricsForDpi, SystemParametersInfoForDpi, MDT_EFFECTIVE_DPI}, Input::KeyboardAndMouse::{EnableWindow, IsWindowEnabled}, Shell::{DefSubclassProc, SetWindowSubclass}, WindowsAndMessaging::* },
     }
 };
 
 use crate::{lib_common::{wnd_instance, wnd_proc, RcValueRef, WndMsgHandler, WSTR}, Font};
+use crate::lib_gui_layout_tree::{ChildHolder, ChildHolderGrid, ChildHolderHStack, ChildHolderPlace, ChildHolderVStack, LayoutTree, StackConstraint};
+pub use crate::lib_gui_layout_tree::{AlignH, AlignV, GridSize, HeightAuto, PlaceSet, WidthAuto};
 
 static ONCE: Once = Once::new();
 
@@ -21,13 +22,11 @@ pub struct WindowContainer {
     handle: HWND,
     cont_w: i32,
     cont_h: i32,
-    m_child: FxHashMap<isize /*HWND*/, ChildHolder>,
-    l_child: Vec<HWND>,
+    tree: LayoutTree, // ノードのキーは子ウィンドウのHWND
     l_child_container: Vec<HWND>,
     b_recalc_layout_stop: bool,
     scr_v: SCROLLINFO,
     scr_h: SCROLLINFO,
-    layout: Layout,
     msg_proc: Option<Box<dyn WindowContainerMsgProc>>,
     sub_proc: Option<Box<dyn WindowContainerSubProc>>,
 }
@@ -35,66 +34,6 @@ pub struct WindowContainer {
 pub type WindowContainerWeak = Weak<WindowContainer>;
 pub type WindowContainerRc = Rc<WindowContainer>;
 
-struct ChildHolderPlace {
-    x: f64,
-    y: f64,
-    w: f64,
-    h: f64,
-    pos_offset_hwnd: HWND,
-    span_offset_hwnd: HWND,
-    pos_kind: PlaceSet,
-    span_kind: PlaceSet,
-}
-
-struct ChildHolderVStack {
-    w: i32,
-    h: i32,
-    pad: i32,
-    filler: i32,
-    split: i32,
-    b_auto: bool,
-    align: AlignH,
-    vert: HeightAuto,
-}
-
-struct ChildHolderHStack {
-    w: i32,
-    h: i32,
-    pad: i32,
-    filler: i32,
-    split: i32,
-    b_auto: bool,
-    align: AlignV,
-    horz: WidthAuto,
-}
-
-enum ChildHolder {
-    Place(ChildHolderPlace), VStack(ChildHolderVStack), HStack(ChildHolderHStack)
-}
-
-#[derive(Default)]
-pub enum Layout { #[default] None, Place, VStack, HStack }
-
-#[allow(unused)]
-#[derive(Default, Clone)]
-pub enum AlignV { #[default] TOP, CENTER, BOTTOM, FILL, EXPAND }
-
-#[allow(unused)]
-#[derive(Default, Clone)]
-pub enum AlignH { #[default] LEFT, CENTER, RIGHT, FILL, EXPAND }
-
-#[allow(unused)]
-#[derive(Default, Clone)]
-pub enum HeightAuto { #[default] AUTO, FIX }
-
-#[allow(unused)]
-#[derive(Default, Clone)]
-pub enum WidthAuto { #[default] AUTO, FIX }
-
-#[allow(unused)]
-#[derive(Default, Clone)]
-pub enum PlaceSet { #[default] PIXEL, REL, OFFSET }
-
 pub trait WindowContainerMsgProc {
     #[allow(unused_variables)]
     fn msgproc(&mut self, hwnd: HWND, umsg: u32, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> { None }
@@ -114,34 +53,30 @@ pub trait WContainerBehavior {
 
     fn create_child(&mut self, style_ex: WINDOW_EX_STYLE, clsname: PCWSTR, wndname: PCWSTR, style: WINDOW_STYLE, cmdid: HMENU, b_subclass: bool) -> HWND;
     fn remove_child(&mut self, hwnd: HWND);
-    fn get_child_rect(&self, htarget: HWND) -> (i32, i32, i32, i32) /* Left, Top, Right, Bottom */;
-    fn get_field_size(&self) -> (i32, i32) /* width, height */;
     fn get_scrollpos(&self) -> (i32, i32) /* nPosH, nPosV */;
 
     fn recalc_layout(&mut self);
     fn recalc_layout_stop(&mut self, b: bool);
     fn check_layout(&mut self) -> (i32, i32);
+    fn pref_layout(&self) -> (i32, i32);
     fn update_layout(&mut self);
 
     fn vstack(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, align: AlignH, size: HeightAuto);
     fn get_vstack_param(&self, htarget: HWND) -> (i32, i32, i32, i32, i32, bool, AlignH, HeightAuto);
     fn set_vstack_param(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, align: AlignH, size: HeightAuto);
-    fn layout_vstack(&mut self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32);
 
     fn hstack(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, size: WidthAuto, align: AlignV);
     fn get_hstack_param(&self, htarget: HWND) -> (i32, i32, i32, i32, i32, bool, WidthAuto, AlignV);
     fn set_hstack_param(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, size: WidthAuto, align: AlignV);
-    fn layout_hstack(&mut self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32);
+
+    fn set_stack_constraint(&mut self, htarget: HWND, min: i32, max: i32, weight: i32);
 
     fn place(&mut self, htarget: HWND, x: f64, y: f64, w: f64, h: f64, pos_offset_hwnd: HWND, span_offset_hwnd: HWND, pos_kind: PlaceSet, span_kind: PlaceSet);
     fn get_place_param(&self, htarget: HWND) -> (f64, f64, f64, f64, HWND, HWND, PlaceSet, PlaceSet);
     fn set_place_param(&mut self, htarget: HWND, x: f64, y: f64, w: f64, h: f64, pos_offset_hwnd: HWND, span_offset_hwnd: HWND, pos_kind: PlaceSet, span_kind: PlaceSet);
-    fn layout_place(&mut self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32);
 
-    // fn grid
-    // fn get_grid_param
-    // fn set_grid_param
-    // fn layout_grid(&mut self, b_min_check: bool, nowScrV: i32, nowScrH: i32) -> (i32, i32);
+    fn grid_define(&mut self, rows: &[GridSize], cols: &[GridSize]);
+    fn grid(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, row: usize, col: usize, row_span: usize, col_span: usize, align_h: AlignH, align_v: AlignV);
 
 }
 
@@ -202,33 +137,13 @@ impl WContainerBehavior for WindowContainerRc {
 
     fn remove_child(&mut self, hwnd: HWND) {
         let s = self.get_mut();
-        for (i, h) in s.l_child.iter().enumerate() {
-            if h.eq(&hwnd) {
-                s.l_child.remove(i);
-                break;
-            }
-        }
         for (i, h) in s.l_child_container.iter().enumerate() {
             if h.eq(&hwnd) {
                 s.l_child_container.remove(i);
                 break;
             }
         }
-        s.m_child.remove(&hwnd.0).and_then(|_| Some(unsafe { DestroyWindow(hwnd) }));
-    }
-
-    // コンテナ左上を0,0にした時の子ウィンドウの位置オフセットを返す(コンテナの大きさを超えることあり。スクロール状態は戻り値に無関係)
-    fn get_child_rect(&self, htarget: HWND) -> (i32, i32, i32, i32) /* Left, Top, Right, Bottom */ {
-        let mut rc = RECT::default();
-		let _ = unsafe { GetWindowRect(self.handle, &mut rc) };
-        let mut rct = RECT::default();
-		let _ = unsafe { GetWindowRect(htarget, &mut rct) };
-        (rct.left - rc.left + self.scr_h.nPos, rct.top - rc.top + self.scr_v.nPos,
-            rct.left - rc.left + self.scr_h.nPos + rct.right - rct.left, rct.top - rc.top + self.scr_v.nPos + rct.bottom - rct.top)
-    }
-
-    fn get_field_size(&self) -> (i32, i32) /* width, height */ {
-        (self.scr_h.nMax + 1, self.scr_v.nMax + 1)
+        if s.tree.remove(hwnd.0) { let _ = unsafe { DestroyWindow(hwnd) }; }
     }
 
     fn get_scrollpos(&self) -> (i32, i32) /* nPosH, nPosV */ {
@@ -239,24 +154,14 @@ impl WContainerBehavior for WindowContainerRc {
     fn recalc_layout(&mut self) {
         if self.b_recalc_layout_stop { return }
         (self.get_mut().scr_h.nPos, self.get_mut().scr_v.nPos) = (0, 0);
-        let v = self.l_child_container.clone(); // 二重借用回避(self.set_xstack_paramと)
+        let v = self.l_child_container.clone(); // 二重借用回避(self.get_mut().treeと)
         for h in v {
             let w = wnd_instance::<WindowContainer>(h);
             if let Some(mut c) = w.upgrade() {
                 c.recalc_layout();
-                if let Layout::VStack = self.layout {
-                    let (width, height) = c.check_layout();
-                    let p = self.get_vstack_param(h);
-                    self.set_vstack_param(h, width, height, p.2, p.3, p.4, p.5, p.6, p.7);
-                } else if let Layout::HStack = self.layout {
-                    let (width, height) = c.check_layout();
-                    let p = self.get_hstack_param(h);
-                    self.set_hstack_param(h, width, height, p.2, p.3, p.4, p.5, p.6, p.7);
-                } else if let Layout::Place = self.layout {
-                    let (width, height) = c.check_layout();
-                    let p = self.get_place_param(h);
-                    self.set_place_param(h, p.0, p.1, width as f64, height as f64, p.4, HWND(0), p.6, PlaceSet::PIXEL);
-                }
+                let (min, pref) = (c.check_layout(), c.pref_layout());
+                self.get_mut().tree.set_size(h.0, pref, min);
+                self.update_layout();
             }
         }
     }
@@ -265,32 +170,24 @@ impl WContainerBehavior for WindowContainerRc {
         self.get_mut().b_recalc_layout_stop = b;
     }
 
+    /** 縮小可能な要素を最小サイズにしたときのビューサイズ */
     fn check_layout(&mut self) -> (i32, i32) {
-        let mut sc = self.clone();
-        let s = self.get_mut();
+        self.apply_layout(true, 0, 0)
+    }
 
-        match s.layout {
-            Layout::None => { (0, 0) }
-            Layout::VStack => { sc.layout_vstack(true, 0, 0) }
-            Layout::HStack => { sc.layout_hstack(true, 0, 0) }
-            Layout::Place => { sc.layout_place(true, 0, 0) }
-        }
+    /** 各要素を基準サイズで並べたときのビューサイズ */
+    fn pref_layout(&self) -> (i32, i32) {
+        self.tree.pref_size()
     }
 
     fn update_layout(&mut self) {
-        let mut sc = self.clone();
         let s = self.get_mut();
 
         unsafe { DefWindowProcW(s.handle,WM_SETREDRAW, WPARAM(FALSE.0 as usize), None); }
 
         let (now_v, now_h) = (s.scr_v.nPos, s.scr_h.nPos);
 
-        let (view_w, view_h) = match s.layout {
-            Layout::None => { (0, 0) }
-            Layout::VStack => { sc.layout_vstack(false, now_v, now_h) }
-            Layout::HStack => { sc.layout_hstack(false, now_v, now_h) }
-            Layout::Place => { sc.layout_place(false, now_v, now_h) }
-        };
+        let (view_w, view_h) = s.apply_layout(false, now_v, now_h);
 
         // スクロール処理
         let (mut b_add_vscr, mut b_add_hscr) = (false, false);
@@ -328,32 +225,22 @@ impl WContainerBehavior for WindowContainerRc {
     }
 
     fn vstack(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, align: AlignH, size: HeightAuto) {
-        let s = self.get_mut();
-
-        if let Layout::None = s.layout {
-            s.layout = Layout::VStack;
-        }
-
-        if let Layout::VStack = s.layout {
-            let ch = ChildHolderVStack {
-                w: w,
-                h: h,
-                pad: pad,
-                filler: filler,
-                split: split,
-                b_auto,
-                align: align,
-                vert: size,
-            };
-            s.m_child.insert(htarget.0, ChildHolder::VStack(ch));
-            s.l_child.push(htarget);
-
-            // self.update_layout();
-        }
+        let ch = ChildHolderVStack {
+            w: w,
+            h: h,
+            pad: pad,
+            filler: filler,
+            split: split,
+            b_auto,
+            align: align,
+            vert: size,
+            cons: StackConstraint::default(),
+        };
+        self.get_mut().tree.add(htarget.0, ChildHolder::VStack(ch));
     }
 
     fn get_vstack_param(&self, htarget: HWND) -> (i32, i32, i32, i32, i32, bool, AlignH, HeightAuto) {
-        let ch = self.m_child.get(&htarget.0);
+        let ch = self.tree.get(htarget.0);
         if let Some(ChildHolder::VStack (v)) = ch {
             (v.w, v.h, v.pad, v.filler, v.split, v.b_auto, v.align.clone(), v.vert.clone())
         } else {
@@ -363,15 +250,17 @@ impl WContainerBehavior for WindowContainerRc {
     }
 
     fn set_vstack_param(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, align: AlignH, size: HeightAuto) {
-        let s = self.get_mut();
-
-        let ch = s.m_child.get(&htarget.0);
-        if ch.is_none() {
+        if let Some(ChildHolder::VStack(v)) = self.get_mut().tree.get_mut(htarget.0) {
+            (v.w, v.h, v.pad, v.filler, v.split, v.b_auto, v.align, v.vert) = (w, h, pad, filler, split, b_auto, align, size); // 制約(min/max/weight)はそのまま
+        } else {
             debug_assert!(false);
             return;
         }
+        self.update_layout();
+    }
 
-        let ch = ChildHolder::VStack(ChildHolderVStack {
+    fn hstack(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, size: WidthAuto, align: AlignV) {
+        let ch = ChildHolderHStack {
             w: w,
             h: h,
             pad: pad,
@@ -379,123 +268,14 @@ impl WContainerBehavior for WindowContainerRc {
             split: split,
             b_auto,
             align: align,
-            vert: size
-        });
-        s.m_child.insert(htarget.0, ch);
-        self.update_layout();
-    }
-
-    fn layout_vstack(&mut self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32) {
-        let s = self.get_mut();
-
-        // 子コントロールのサイズをすべて計算し、ビューサイズを計算(一番右下はどこか)
-        let mut view_w = 0i32;
-        let mut view_h = 0i32;
-
-        let mut size_h_min = 0i32;
-        let mut num_auto_elem = 0i32;
-
-        let mut b_expand = false;
-        for hc in s.l_child.iter() { // サイズ固定分の積み上げ、自動サイズの要素の個数を確認
-            let ch = s.m_child.get(&hc.0).unwrap();
-            if let ChildHolder::VStack(v) = ch {
-                size_h_min += v.h + v.pad * 2 + v.split; // 固定でも自動でもv.hには最低サイズが入っている
-                // if let HeightAuto::FIX = v.vert  { }
-                if let HeightAuto::AUTO = v.vert {
-                    if v.b_auto {
-                        num_auto_elem += 1;
-                    } else {
-                        size_h_min += v.filler;
-                    }
-                }
-                if v.w + v.pad * 2 > view_w { view_w = v.w + v.pad * 2; }
-                if let AlignH::EXPAND = v.align { b_expand = true; }
-            }
-        }
-
-        if b_min_check {
-            return (view_w, size_h_min)
-        }
-
-        // MINサイズの積み上げでビューの方が小さければコンテナのサイズまで広げる
-        if b_expand && s.cont_w > view_w { view_w = s.cont_w; }
-
-        // コンテナの空きサイズ、自動サイズ要素ひとつあたりのFillerサイズ(高さor幅)を計算
-        let mut auto_filler = 0i32;
-        let mut size_free = s.cont_h - size_h_min;
-        if size_free > 0 {
-            if num_auto_elem != 0 {
-                auto_filler = size_free / num_auto_elem;
-            }
-        } else {
-            size_free = 0;
-        }
-
-        let mut now = 0i32;
-        let mut now_auto_count = 0i32;
-
-        for hc in s.l_child.iter() { // 各要素の配置計算、SetWinPos、ビューサイズの積み上げ
-            let ch = s.m_child.get(&hc.0).unwrap();
-            if let ChildHolder::VStack(v) = ch {
-                let (newx, neww) =
-                    match v.align {
-                        AlignH::LEFT => (v.pad, v.w),
-                        AlignH::RIGHT => (view_w - v.pad - v.w, v.w),
-                        AlignH::CENTER => (if view_w / 2 - v.w / 2 > 0 { view_w / 2 - v.w / 2 } else { v.pad }, v.w),
-                        AlignH::FILL | AlignH::EXPAND => (v.pad, view_w - v.pad * 2),
-                    };
-                let newy = now + v.pad;
-                let newh = match v.vert {
-                    HeightAuto::FIX => { v.h }
-                    HeightAuto::AUTO => {
-                        if !v.b_auto {
-                            v.h + v.filler
-                        } else {
-                            now_auto_count += 1;
-                            if now_auto_count == num_auto_elem { // 自動サイズ最後の要素は、auto_size整数除算の剰余をうめないと隙間が出る
-                                v.h + size_free
-                            } else {
-                                size_free -= auto_filler;
-                                v.h + auto_filler
-                            }
-                        }
-                    }
-                };
-                let _ = unsafe { SetWindowPos(*hc, None, newx - now_scr_h, newy - now_scr_v, neww, newh, SWP_NOZORDER) };
-                now += newh + v.pad * 2 + v.split;
-                if view_h < now { view_h = now; }
-            }
-        }
-        (view_w, view_h)
-    }
-
-    fn hstack(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, size: WidthAuto, align: AlignV) {
-        let s = self.get_mut();
-
-        if let Layout::None = s.layout {
-            s.layout = Layout::HStack;
-        }
-
-        if let Layout::HStack = s.layout {
-            let ch = ChildHolderHStack {
-                w: w,
-                h: h,
-                pad: pad,
-                filler: filler,
-                split: split,
-                b_auto,
-                align: align,
-                horz: size,
-            };
-            s.m_child.insert(htarget.0, ChildHolder::HStack(ch));
-            s.l_child.push(htarget);
-
-            // self.update_layout();
-        }
+            horz: size,
+            cons: StackConstraint::default(),
+        };
+        self.get_mut().tree.add(htarget.0, ChildHolder::HStack(ch));
     }
 
     fn get_hstack_param(&self, htarget: HWND) -> (i32, i32, i32, i32, i32, bool, WidthAuto, AlignV) {
-        let ch = self.m_child.get(&htarget.0);
+        let ch = self.tree.get(htarget.0);
         if let Some(ChildHolder::HStack (v)) = ch {
             (v.w, v.h, v.pad, v.filler, v.split, v.b_auto, v.horz.clone(), v.align.clone())
         } else {
@@ -505,204 +285,91 @@ impl WContainerBehavior for WindowContainerRc {
     }
 
     fn set_hstack_param(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, filler: i32, split: i32, b_auto: bool, size: WidthAuto, align: AlignV) {
-        let s = self.get_mut();
-
-        let ch = s.m_child.get(&htarget.0);
-        if ch.is_none() {
+        if let Some(ChildHolder::HStack(v)) = self.get_mut().tree.get_mut(htarget.0) {
+            (v.w, v.h, v.pad, v.filler, v.split, v.b_auto, v.horz, v.align) = (w, h, pad, filler, split, b_auto, size, align); // 制約(min/max/weight)はそのまま
+        } else {
             debug_assert!(false);
             return;
         }
-
-        let ch = ChildHolder::HStack(ChildHolderHStack {
-            w: w,
-            h: h,
-            pad: pad,
-            filler: filler,
-            split: split,
-            b_auto,
-            align: align,
-            horz: size
-        });
-        s.m_child.insert(htarget.0, ch);
         self.update_layout();
     }
 
-    fn layout_hstack(&mut self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32) {
-        let s = self.get_mut();
-
-        // 子コントロールのサイズをすべて計算し、ビューサイズを計算(一番右下はどこか)
-        let mut view_w = 0i32;
-        let mut view_h = 0i32;
-
-        let mut size_w_min = 0i32;
-        let mut num_auto_elem = 0i32;
-
-        let mut b_expand = false;
-        for hc in s.l_child.iter() { // サイズ固定分の積み上げ、自動サイズの要素の個数を確認
-            let ch = s.m_child.get(&hc.0).unwrap();
-            if let ChildHolder::HStack(v) = ch {
-                size_w_min += v.w + v.pad * 2 + v.split; // 固定でも自動でもv.wには最低サイズが入っている
-                // if let WidthAuto::FIX = v.vert  { }
-                if let WidthAuto::AUTO = v.horz {
-                    if v.b_auto {
-                        num_auto_elem += 1;
-                    } else {
-                        size_w_min += v.filler;
-                    }
-                }
-                if v.h + v.pad * 2 > view_h { view_h = v.h + v.pad * 2; }
-                if let AlignV::EXPAND = v.align { b_expand = true; }
-            }
-        }
-
-        if b_min_check {
-            return (size_w_min, view_h)
-        }
-
-        // MINサイズの積み上げでビューの方が小さければコンテナのサイズまで広げる
-        if b_expand && s.cont_h > view_h { view_h = s.cont_h; }
-
-        // コンテナの空きサイズ、自動サイズ要素ひとつあたりのFillerサイズ(高さor幅)を計算
-        let mut auto_filler = 0i32;
-        let mut size_free = s.cont_w - size_w_min;
-        if size_free > 0 {
-            if num_auto_elem != 0 {
-                auto_filler = size_free / num_auto_elem;
-            }
-        } else {
-            size_free = 0;
-        }
-
-        let mut now = 0i32;
-        let mut now_auto_count = 0i32;
-
-        for hc in s.l_child.iter() { // 各要素の配置計算、SetWinPos、ビューサイズの積み上げ
-            let ch = s.m_child.get(&hc.0).unwrap();
-            if let ChildHolder::HStack(v) = ch {
-                let (newy, newh) =
-                    match v.align {
-                        AlignV::TOP => (v.pad, v.h),
-                        AlignV::BOTTOM => (view_h - v.pad - v.h, v.h),
-                        AlignV::CENTER => (if view_h / 2 - v.h / 2 > 0 { view_h / 2 - v.h / 2 } else { v.pad }, v.h),
-                        AlignV::FILL | AlignV::EXPAND => (v.pad, view_h - v.pad * 2),
-                    };
-                let newx = now + v.pad;
-                let neww = match v.horz {
-                    WidthAuto::FIX => { v.w }
-                    WidthAuto::AUTO => {
-                        if !v.b_auto {
-                            v.w + v.filler
-                        } else {
-                            now_auto_count += 1;
-                            if now_auto_count == num_auto_elem { // 自動サイズ最後の要素は、auto_size整数除算の剰余をうめないと隙間が出る
-                                v.w + size_free
-                            } else {
-                                size_free -= auto_filler;
-                                v.w + auto_filler
-                            }
-                        }
-                    }
-                };
-                let _ = unsafe { SetWindowPos(*hc, None, newx - now_scr_h, newy - now_scr_v, neww, newh, SWP_NOZORDER) };
-                now += neww + v.pad * 2 + v.split;
-                if view_w < now { view_w = now; }
-            }
+    /** スタック要素の主軸方向の最小・最大サイズ(-1:指定なし)と、空き・不足サイズを配分する重み */
+    fn set_stack_constraint(&mut self, htarget: HWND, min: i32, max: i32, weight: i32) {
+        if !self.get_mut().tree.set_constraint(htarget.0, min, max, weight) {
+            debug_assert!(false);
+            return;
         }
-        (view_w, view_h)
+        self.update_layout();
     }
 
     fn place(&mut self, htarget: HWND, x: f64, y: f64, w: f64, h: f64, pos_offset_hwnd: HWND, span_offset_hwnd: HWND, pos_kind: PlaceSet, span_kind: PlaceSet) {
-        let s = self.get_mut();
-
-        if let Layout::None = s.layout {
-            s.layout = Layout::Place;
-        }
-
-        if let Layout::Place = s.layout {
-            let ch = ChildHolderPlace {
-                x: x,
-                y: y,
-                w: w,
-                h: h,
-                pos_offset_hwnd: pos_offset_hwnd,
-                span_offset_hwnd: span_offset_hwnd,
-                pos_kind:pos_kind,
-                span_kind: span_kind,
-            };
-            s.m_child.insert(htarget.0, ChildHolder::Place(ch));
-            s.l_child.push(htarget);
-
-            // self.update_layout();
-        }
+        let ch = ChildHolderPlace {
+            x: x,
+            y: y,
+            w: w,
+            h: h,
+            pos_offset: pos_offset_hwnd.0,
+            span_offset: span_offset_hwnd.0,
+            pos_kind:pos_kind,
+            span_kind: span_kind,
+        };
+        self.get_mut().tree.add(htarget.0, ChildHolder::Place(ch));
     }
     fn get_place_param(&self, htarget: HWND) -> (f64, f64, f64, f64, HWND, HWND, PlaceSet, PlaceSet) {
-        let ch = self.m_child.get(&htarget.0);
+        let ch = self.tree.get(htarget.0);
         if let Some(ChildHolder::Place (v)) = ch {
-            (v.x, v.y, v.w, v.h, v.pos_offset_hwnd, v.span_offset_hwnd, v.pos_kind.clone(), v.span_kind.clone())
+            (v.x, v.y, v.w, v.h, HWND(v.pos_offset), HWND(v.span_offset), v.pos_kind.clone(), v.span_kind.clone())
         } else {
             debug_assert!(false);
             (0.0, 0.0, 0.0, 0.0, HWND(0), HWND(0), PlaceSet::PIXEL, PlaceSet::PIXEL)
         }
     }
     fn set_place_param(&mut self, htarget: HWND, x: f64, y: f64, w: f64, h: f64, pos_offset_hwnd: HWND, span_offset_hwnd: HWND, pos_kind: PlaceSet, span_kind: PlaceSet) {
-        let s = self.get_mut();
-
-        let ch = s.m_child.get(&htarget.0);
-        if ch.is_none() {
-            debug_assert!(false);
-            return;
-        }
-
         let ch = ChildHolder::Place(ChildHolderPlace {
             x: x,
             y: y,
             w: w,
             h: h,
-            pos_offset_hwnd: pos_offset_hwnd,
-            span_offset_hwnd: span_offset_hwnd,
+            pos_offset: pos_offset_hwnd.0,
+            span_offset: span_offset_hwnd.0,
             pos_kind:pos_kind,
             span_kind: span_kind,
         });
-        s.m_child.insert(htarget.0, ch);
+        if !self.get_mut().tree.set(htarget.0, ch) {
+            debug_assert!(false);
+            return;
+        }
         self.update_layout();
     }
-    fn layout_place(&mut self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32) {
-        // let s = self.get_mut();
 
-        let place_calc_pos = |v :&ChildHolderPlace, pw :i32, ph :i32| -> (i32, i32, i32, i32) {
-            let (newx, newy) = match v.pos_kind {
-                PlaceSet::PIXEL => (v.x.round() as i32, v.y.round() as i32),
-                PlaceSet::REL => ((pw as f64 * v.x).round() as i32, (ph as f64 * v.y).round() as i32),
-                PlaceSet::OFFSET => {
-                    let (x, y, _, _) = self.get_child_rect(v.pos_offset_hwnd);
-                    (x + v.x.round() as i32, y + v.y.round() as i32)
-                }
-            };
-            let (neww, newh) = match v.span_kind {
-                PlaceSet::PIXEL => (v.w.round() as i32, v.h.round() as i32),
-                PlaceSet::REL => ((pw as f64 * v.w).round() as i32, (ph as f64 * v.h).round() as i32),
-                PlaceSet::OFFSET => {
-                    let (_, _, w, h) = self.get_child_rect(v.span_offset_hwnd);
-                    (w + v.w.round() as i32, h + v.h.round() as i32)
-                }
-            };
-            (newx, newy, neww, newh)
-        };
+    /** 行・列の定義。定義外の行・列にアイテムを配置した場合はAUTOとして扱う */
+    fn grid_define(&mut self, rows: &[GridSize], cols: &[GridSize]) {
+        self.get_mut().tree.grid_define(rows, cols);
+    }
 
-        // 子コントロールのサイズをすべて計算し、ビューサイズを計算(一番右下はどこか)
-        let mut view_w = 0i32;
-        let mut view_h = 0i32;
+    fn grid(&mut self, htarget: HWND, w: i32, h: i32, pad: i32, row: usize, col: usize, row_span: usize, col_span: usize, align_h: AlignH, align_v: AlignV) {
+        let ch = ChildHolderGrid {
+            w: w,
+            h: h,
+            pad: pad,
+            row: row,
+            col: col,
+            row_span: row_span.max(1),
+            col_span: col_span.max(1),
+            align_h: align_h,
+            align_v: align_v,
+        };
+        self.get_mut().tree.add(htarget.0, ChildHolder::Grid(ch));
+    }
+}
 
-        for hc in self.l_child.iter() {
-            let ch = self.m_child.get(&hc.0).unwrap();
-            if let ChildHolder::Place(v) = ch {
-                let (newx, newy, neww, newh) = place_calc_pos(&v, self.cont_w, self.cont_h);
-                if !b_min_check {
-                    let _ = unsafe { SetWindowPos(*hc, None, newx - now_scr_h, newy - now_scr_v, neww, newh, SWP_NOZORDER) };
-                }
-                if view_w < newx + neww { view_w = newx + neww; }
-                if view_h < newy + newh { view_h = newy + newh; }
-            }
+impl WindowContainer {
+    /** レイアウトツリーの計算結果を子ウィンドウに反映する(b_min_checkの場合は計算のみ) */
+    fn apply_layout(&self, b_min_check: bool, now_scr_v: i32, now_scr_h: i32) -> (i32, i32) {
+        let (view_w, view_h, rects) = self.tree.solve(self.cont_w, self.cont_h, b_min_check);
+        for (key, rc) in rects {
+            let _ = unsafe { SetWindowPos(HWND(key), None, rc.x - now_scr_h, rc.y - now_scr_v, rc.w, rc.h, SWP_NOZORDER) };
         }
         (view_w, view_h)
     }
@@ -836,6 +503,12 @@ pub fn get_ctrl_checked(hwnd_root: HWND, id: isize) -> bool {
     BST_CHECKED.0 == unsafe { SendMessageW(get_ctrl(hwnd_root, id), BM_GETCHECK, WPARAM(0), LPARAM(0)) }.0 as u32
 }
 
+// 3ステートのチェックボックス(不定はNone)
+pub fn get_ctrl_tristate(hwnd_root: HWND, id: isize) -> Option<bool> {
+    let state = unsafe { SendMessageW(get_ctrl(hwnd_root, id), BM_GETCHECK, WPARAM(0), LPARAM(0)) }.0 as u32;
+    if state == BST_CHECKED.0 { Some(true) } else if state == BST_UNCHECKED.0 { Some(false) } else { None }
+}
+
 pub fn get_ctrl_text(hwnd_root: HWND, id: isize) -> String {
     let hctrl = get_ctrl(hwnd_root, id);
     let mut buf = [0u16; MAX_PATH as usize];
@@ -864,6 +537,11 @@ pub fn set_ctrl_checked(hwnd_root: HWND, id: isize, bchecked: bool) {
     unsafe { SendMessageW(get_ctrl(hwnd_root, id), BM_SETCHECK, WPARAM(if bchecked { BST_CHECKED.0 } else { BST_UNCHECKED.0 } as usize), LPARAM(0)); }
 }
 
+pub fn set_ctrl_tristate(hwnd_root: HWND, id: isize, state: Option<bool>) {
+    let state = match state { Some(true) => BST_CHECKED, Some(false) => BST_UNCHECKED, None => BST_INDETERMINATE };
+    unsafe { SendMessageW(get_ctrl(hwnd_root, id), BM_SETCHECK, WPARAM(state.0 as usize), LPARAM(0)); }
+}
+
 pub fn set_ctrl_text(hwnd_root: HWND, id: isize, text: &str) {
     let _ = unsafe { SetWindowTextW(get_ctrl(hwnd_root, id), WSTR::from(text).PCWSTR()) };
 }
@@ -900,23 +578,42 @@ where Weak<T>: WindowContainerMsgProc {
     c
 }
 
-pub fn init_item_hstack(wc: &mut WindowContainerRc, hfont: HFONT, w: i32, h: i32, a: WidthAuto, v: AlignV, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) {
+pub fn init_cont_grid<T: 'static>(inst_subproc: Weak<T>, wc: &mut WindowContainerRc, row: usize, col: usize, row_span: usize, col_span: usize, align_h: AlignH, align_v: AlignV, cmdid: isize) -> WindowContainerRc
+where Weak<T>: WindowContainerMsgProc {
+    let mut c = wc.create_child_container(WS_EX_CONTROLPARENT, WS_VISIBLE, HMENU(cmdid)).upgrade().unwrap();
+    c.set_msg_proc(Some(Box::new(inst_subproc)));
+    wc.grid(c.handle(), 0, 0, 0, row, col, row_span, col_span, align_h, align_v);
+    c
+}
+
+pub fn init_item_hstack(wc: &mut WindowContainerRc, hfont: HFONT, w: i32, h: i32, a: WidthAuto, v: AlignV, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) -> HWND {
     let hctrl = wc.create_child(WINDOW_EX_STYLE::default(), WSTR::from(clsname).PCWSTR(), WSTR::from(text).PCWSTR(), WS_VISIBLE | style, HMENU(cmdid), true);
     unsafe { SendMessageW(hctrl,  WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(0/*FALSE*/)); }
     let (wf, hf) = adjust_ctrl_textsize(hctrl, hfont, w, h, text);
     wc.hstack(hctrl, wf, hf, 2, 0, 0, true, a, v);
+    hctrl
 }
 
-pub fn init_item_vstack(wc: &mut WindowContainerRc, hfont: HFONT, w: i32, h: i32, v: AlignH, a: HeightAuto, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) {
+pub fn init_item_vstack(wc: &mut WindowContainerRc, hfont: HFONT, w: i32, h: i32, v: AlignH, a: HeightAuto, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) -> HWND {
     let hctrl = wc.create_child(WINDOW_EX_STYLE::default(), WSTR::from(clsname).PCWSTR(), WSTR::from(text).PCWSTR(), WS_VISIBLE | style, HMENU(cmdid), true);
     unsafe { SendMessageW(hctrl,  WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(0/*FALSE*/)); }
     let (wf, hf) = adjust_ctrl_textsize(hctrl, hfont, w, h, text);
     wc.vstack(hctrl, wf, hf, 2, 0, 0, true, v, a);
+    hctrl
+}
+
+pub fn init_item_grid(wc: &mut WindowContainerRc, hfont: HFONT, w: i32, h: i32, row: usize, col: usize, row_span: usize, col_span: usize, align_h: AlignH, align_v: AlignV, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) -> HWND {
+    let hctrl = wc.create_child(WINDOW_EX_STYLE::default(), WSTR::from(clsname).PCWSTR(), WSTR::from(text).PCWSTR(), WS_VISIBLE | style, HMENU(cmdid), true);
+    unsafe { SendMessageW(hctrl,  WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(0/*FALSE*/)); }
+    let (wf, hf) = adjust_ctrl_textsize(hctrl, hfont, w, h, text);
+    wc.grid(hctrl, wf, hf, 2, row, col, row_span, col_span, align_h, align_v);
+    hctrl
 }
 
-pub fn init_item_place(wc: &mut WindowContainerRc, x: f64, y: f64, w: f64, h: f64, pos_offset_hwnd: HWND, span_offset_hwnd: HWND, pos_kind:  PlaceSet, span_kind: PlaceSet, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) {
+pub fn init_item_place(wc: &mut WindowContainerRc, x: f64, y: f64, w: f64, h: f64, pos_offset_hwnd: HWND, span_offset_hwnd: HWND, pos_kind:  PlaceSet, span_kind: PlaceSet, clsname: &str, text: &str, style: WINDOW_STYLE, cmdid: isize) -> HWND {
     let hctrl = wc.create_child(WINDOW_EX_STYLE::default(), WSTR::from(clsname).PCWSTR(), WSTR::from(text).PCWSTR(), WS_VISIBLE | style, HMENU(cmdid), true);
     wc.place(hctrl, x, y, w, h, pos_offset_hwnd, span_offset_hwnd, pos_kind, span_kind);
+    hctrl
 }
 
 fn adjust_ctrl_textsize(hwnd: HWND, hfont: HFONT, w: i32, h: i32, text: &str) -> (i32, i32) {
@@ -0,0 +1,225 @@
+use std::rc::Weak;
+use fxhash::FxHashSet;
+use windows::Win32::{Foundation::HWND, Graphics::Gdi::HFONT, UI::WindowsAndMessaging::WINDOW_STYLE};
+
+use crate::lib_common::wnd_instance;
+use crate::lib_gui_layout_container::*;
+use crate::*;
+
+pub const IDC_DEF_DUMMY: isize = 0xffff; // 重複チェック対象外のID(スペーサー・見出し等)
+
+/** 寸法指定 */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DlgSize {
+    Text,                 // キャプション文字列から算出
+    Px(i32),              // ピクセル指定
+    Char(i32, i32),       // 文字幅(高さ)の 分子/分母 倍
+    TextOf(&'static str), // 指定した文字列から算出
+}
+
+/** 親コンテナ内での配置 */
+#[derive(Clone, Debug)]
+pub enum DlgSlot {
+    VStack(AlignH, HeightAuto),
+    HStack(WidthAuto, AlignV),
+    Grid(usize /*row*/, usize /*col*/, usize /*row_span*/, usize /*col_span*/, AlignH, AlignV),
+    Place(f64 /*x*/, f64 /*y*/),
+}
+
+/**
+ * ダイアログ定義の1ノード(コンテナまたはコントロール)
+ * DlgItem::cont / DlgItem::ctrl から始めてメソッドチェーンで組み立てる
+ */
+#[derive(Clone, Debug)]
+pub struct DlgItem {
+    pub id: isize,
+    pub class: Option<&'static str>, // Noneはコンテナ
+    pub caption: Option<&'static str>, // lib_caption_*の定数
+    pub style: WINDOW_STYLE,
+    pub w: DlgSize,
+    pub h: DlgSize,
+    pub slot: DlgSlot,
+    pub pad: Option<i32>,
+    pub grid: Option<(Vec<GridSize>, Vec<GridSize>)>,
+    pub children: Vec<DlgItem>,
+}
+
+impl DlgItem {
+    pub fn cont(id: isize) -> Self {
+        Self {
+            id,
+            class: None,
+            caption: None,
+            style: WINDOW_STYLE::default(),
+            w: DlgSize::Px(0),
+            h: DlgSize::Px(0),
+            slot: DlgSlot::VStack(AlignH::FILL, HeightAuto::FIX),
+            pad: None,
+            grid: None,
+            children: Vec::default(),
+        }
+    }
+
+    pub fn ctrl(class: &'static str, id: isize) -> Self {
+        Self { class: Some(class), w: DlgSize::Text, h: DlgSize::Text, ..Self::cont(id) }
+    }
+
+    // 空のSTATIC(余白調整用)
+    pub fn spacer(w: DlgSize, h: DlgSize) -> Self {
+        Self::ctrl("STATIC", IDC_DEF_DUMMY).size(w, h)
+    }
+
+    pub fn caption(mut self, text: &'static str) -> Self { self.caption = Some(text); self }
+    pub fn style(mut self, style: WINDOW_STYLE) -> Self { self.style = style; self }
+    pub fn size(mut self, w: DlgSize, h: DlgSize) -> Self { self.w = w; self.h = h; self }
+    pub fn vstack(mut self, v: AlignH, a: HeightAuto) -> Self { self.slot = DlgSlot::VStack(v, a); self }
+    pub fn hstack(mut self, a: WidthAuto, v: AlignV) -> Self { self.slot = DlgSlot::HStack(a, v); self }
+    pub fn grid(mut self, row: usize, col: usize, row_span: usize, col_span: usize, align_h: AlignH, align_v: AlignV) -> Self {
+        self.slot = DlgSlot::Grid(row, col, row_span, col_span, align_h, align_v);
+        self
+    }
+    pub fn place(mut self, x: f64, y: f64) -> Self { self.slot = DlgSlot::Place(x, y); self }
+    pub fn pad(mut self, pad: i32) -> Self { self.pad = Some(pad); self }
+    pub fn grid_define(mut self, rows: &[GridSize], cols: &[GridSize]) -> Self { self.grid = Some((rows.to_vec(), cols.to_vec())); self }
+    pub fn child(mut self, item: DlgItem) -> Self { self.children.push(item); self }
+    pub fn children(mut self, items: impl IntoIterator<Item = DlgItem>) -> Self { self.children.extend(items); self }
+
+    pub fn is_container(&self) -> bool { self.class.is_none() }
+}
+
+/**
+ * 定義の検証(GUI不要)
+ * 複数の定義を渡すと、同じ親ウィンドウに配置されるものとしてIDの重複を調べる
+ */
+pub fn dlg_validate(defs: &[&DlgItem]) -> std::result::Result<(), Vec<String>> {
+    let mut errs = Vec::<String>::default();
+    let mut ids = FxHashSet::<isize>::default();
+    for def in defs {
+        validate_item(def, true, &mut ids, &mut errs);
+    }
+    if errs.is_empty() { Ok(()) } else { Err(errs) }
+}
+
+fn validate_item(item: &DlgItem, b_root: bool, ids: &mut FxHashSet<isize>, errs: &mut Vec<String>) {
+    if !b_root && item.id != IDC_DEF_DUMMY && !ids.insert(item.id) {
+        errs.push(format!("duplicate id: {}", item.id));
+    }
+    if item.class == Some("BUTTON") && item.caption.is_none() {
+        errs.push(format!("button without caption (id {})", item.id));
+    }
+    if !item.is_container() && !item.children.is_empty() {
+        errs.push(format!("control has children (id {})", item.id));
+    }
+    // 同一コンテナ内の配置種別は揃っていなければならない(WindowContainerは異なる種別を無視する)
+    if let Some(first) = item.children.first() {
+        if item.children.iter().any(|c| std::mem::discriminant(&c.slot) != std::mem::discriminant(&first.slot)) {
+            errs.push(format!("mixed layout kinds in container (id {})", item.id));
+        }
+    }
+    for c in &item.children {
+        validate_item(c, false, ids, errs);
+    }
+}
+
+/** 定義をコンテナ上に実体化する(rootの子以下を生成。root自身はwcに対応する) */
+pub fn dlg_instantiate<T: 'static>(inst: Weak<T>, wc: &mut WindowContainerRc, hfont: HFONT, def: &DlgItem)
+where Weak<T>: WindowContainerMsgProc {
+    debug_assert_eq!(dlg_validate(&[def]), Ok(()));
+    let (cw, ch) = text_size(wc.handle(), hfont, CHAR_FONT_WIDTH_MEASURE);
+    instantiate_children(&inst, wc, hfont, (cw, ch), def);
+}
+
+fn instantiate_children<T: 'static>(inst: &Weak<T>, wc: &mut WindowContainerRc, hfont: HFONT, cs: (i32, i32), def: &DlgItem)
+where Weak<T>: WindowContainerMsgProc {
+    if let Some((rows, cols)) = &def.grid {
+        wc.grid_define(rows, cols);
+    }
+    for item in &def.children {
+        let w = resolve_size(wc.handle(), hfont, item.w, cs.0, |s| s.0);
+        let h = resolve_size(wc.handle(), hfont, item.h, cs.1, |s| s.1);
+        let hwnd = if item.is_container() {
+            let mut c = match item.slot.clone() {
+                DlgSlot::VStack(v, a) => init_cont_vstack(inst.clone(), wc, w, h, v, a, item.id),
+                DlgSlot::HStack(a, v) => init_cont_hstack(inst.clone(), wc, w, h, a, v, item.id),
+                DlgSlot::Grid(row, col, row_span, col_span, ah, av) => init_cont_grid(inst.clone(), wc, row, col, row_span, col_span, ah, av, item.id),
+                DlgSlot::Place(x, y) => init_cont_place(inst.clone(), wc, x, y, w as f64, h as f64, HWND(0), HWND(0), PlaceSet::PIXEL, PlaceSet::PIXEL, item.id),
+            };
+            instantiate_children(inst, &mut c, hfont, cs, item);
+            c.handle()
+        } else {
+            let clsname = item.class.unwrap();
+            let text = item.caption.unwrap_or("");
+            match item.slot.clone() {
+                DlgSlot::VStack(v, a) => init_item_vstack(wc, hfont, w, h, v, a, clsname, text, item.style, item.id),
+                DlgSlot::HStack(a, v) => init_item_hstack(wc, hfont, w, h, a, v, clsname, text, item.style, item.id),
+                DlgSlot::Grid(row, col, row_span, col_span, ah, av) => init_item_grid(wc, hfont, w, h, row, col, row_span, col_span, ah, av, clsname, text, item.style, item.id),
+                DlgSlot::Place(x, y) => init_item_place(wc, x, y, w as f64, h as f64, HWND(0), HWND(0), PlaceSet::PIXEL, PlaceSet::PIXEL, clsname, text, item.style, item.id),
+            }
+        };
+        if let Some(pad) = item.pad {
+            match &item.slot {
+                DlgSlot::VStack(..) => { let p = wc.get_vstack_param(hwnd); wc.set_vstack_param(hwnd, p.0, p.1, pad, p.3, p.4, p.5, p.6, p.7); }
+                DlgSlot::HStack(..) => { let p = wc.get_hstack_param(hwnd); wc.set_hstack_param(hwnd, p.0, p.1, pad, p.3, p.4, p.5, p.6, p.7); }
+                _ => { }
+            }
+        }
+    }
+}
+
+fn resolve_size(hwnd: HWND, hfont: HFONT, s: DlgSize, unit: i32, pick: fn((i32, i32)) -> i32) -> i32 {
+    match s {
+        DlgSize::Text => -1,
+        DlgSize::Px(n) => n,
+        DlgSize::Char(n, d) => unit * n / d,
+        DlgSize::TextOf(text) => pick(text_size(hwnd, hfont, text)),
+    }
+}
+
+/** 定義から生成したコンテナをIDで得る */
+pub fn dlg_cont(hwnd_root: HWND, id: isize) -> WindowContainerRc {
+    wnd_instance::<WindowContainer>(get_ctrl(hwnd_root, id)).upgrade().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DlgItem {
+        DlgItem::cont(1)
+            .child(DlgItem::cont(11).vstack(AlignH::FILL, HeightAuto::FIX)
+                .child(DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(2, 1)).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::ctrl("BUTTON", 1001).caption(DLG_FV_BT_TEXT_OK).hstack(WidthAuto::FIX, AlignV::CENTER))
+                .child(DlgItem::spacer(DlgSize::Px(0), DlgSize::Char(2, 1)).hstack(WidthAuto::FIX, AlignV::CENTER)))
+    }
+
+    #[test]
+    fn valid_definition_passes() {
+        assert_eq!(dlg_validate(&[&sample()]), Ok(()));
+    }
+
+    #[test]
+    fn duplicate_id_is_reported() {
+        let def = sample().child(DlgItem::ctrl("EDIT", 1001).vstack(AlignH::FILL, HeightAuto::FIX));
+        assert_eq!(dlg_validate(&[&def]), Err(vec!["duplicate id: 1001".to_string()]));
+
+        // 別定義でも同じ親に配置するなら重複
+        let other = DlgItem::cont(2).child(DlgItem::cont(11).vstack(AlignH::FILL, HeightAuto::FIX));
+        assert_eq!(dlg_validate(&[&sample(), &other]), Err(vec!["duplicate id: 11".to_string()]));
+    }
+
+    #[test]
+    fn button_without_caption_is_reported() {
+        let def = DlgItem::cont(1)
+            .child(DlgItem::ctrl("STATIC", 2).vstack(AlignH::FILL, HeightAuto::FIX))
+            .child(DlgItem::ctrl("BUTTON", 3).vstack(AlignH::FILL, HeightAuto::FIX));
+        assert_eq!(dlg_validate(&[&def]), Err(vec!["button without caption (id 3)".to_string()]));
+    }
+
+    #[test]
+    fn mixed_layout_kinds_are_reported() {
+        let def = DlgItem::cont(1)
+            .child(DlgItem::spacer(DlgSize::Px(0), DlgSize::Px(0)).vstack(AlignH::FILL, HeightAuto::FIX))
+            .child(DlgItem::spacer(DlgSize::Px(0), DlgSize::Px(0)).hstack(WidthAuto::FIX, AlignV::FILL));
+        assert_eq!(dlg_validate(&[&def]), Err(vec!["mixed layout kinds in container (id 1)".to_string()]));
+    }
+}
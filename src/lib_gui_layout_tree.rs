@@ -0,0 +1,906 @@
+// レイアウト計算本体(ウィンドウハンドルに依存しない)
+// WindowContainerはここで計算した矩形をSetWindowPosで反映するだけのアダプタ
+// キー(isize)はWindowContainerではHWND、カスタム描画のビューでは任意の識別子として使う
+
+#[derive(Default)]
+pub enum Layout { #[default] None, Place, VStack, HStack, Grid }
+
+#[allow(unused)]
+#[derive(Default, Clone, Debug)]
+pub enum AlignV { #[default] TOP, CENTER, BOTTOM, FILL, EXPAND }
+
+#[allow(unused)]
+#[derive(Default, Clone, Debug)]
+pub enum AlignH { #[default] LEFT, CENTER, RIGHT, FILL, EXPAND }
+
+#[allow(unused)]
+#[derive(Default, Clone, Debug)]
+pub enum HeightAuto { #[default] AUTO, FIX }
+
+#[allow(unused)]
+#[derive(Default, Clone, Debug)]
+pub enum WidthAuto { #[default] AUTO, FIX }
+
+#[allow(unused)]
+#[derive(Default, Clone, Debug)]
+pub enum PlaceSet { #[default] PIXEL, REL, OFFSET }
+
+// グリッドの行・列の大きさ指定(AUTO:中身の最大サイズ、PIXEL:固定サイズ、STAR:残りサイズを重みで分配)
+#[allow(unused)]
+#[derive(Default, Clone, Debug)]
+pub enum GridSize { #[default] AUTO, PIXEL(i32), STAR(i32) }
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct LayoutRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+pub struct ChildHolderPlace {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    pub pos_offset: isize,  // PlaceSet::OFFSETの基準となる兄弟ノードのキー
+    pub span_offset: isize,
+    pub pos_kind: PlaceSet,
+    pub span_kind: PlaceSet,
+}
+
+// スタック要素の主軸方向の制約(-1は指定なし)。sizeは基準サイズ(=コンテンツのサイズ)で、
+// 空きがあれば重みに応じて伸長(max以下)、足りなければ重みに応じてminまで縮小する
+#[derive(Clone)]
+pub struct StackConstraint {
+    pub min: i32,
+    pub max: i32,
+    pub weight: i32,
+    pub size_min: i32, // 子コンテナの中身から求めた最小サイズ(recalc_layoutで設定)
+}
+
+impl Default for StackConstraint {
+    fn default() -> Self {
+        Self { min: -1, max: -1, weight: 1, size_min: -1 }
+    }
+}
+
+impl StackConstraint {
+    /** 基準サイズに対する実際の最小サイズ(指定が無ければ縮小しない) */
+    fn min_size(&self, base: i32) -> i32 {
+        if self.min >= 0 {
+            self.min.min(base)
+        } else if self.size_min >= 0 {
+            self.size_min.min(base)
+        } else {
+            base
+        }
+    }
+}
+
+pub struct ChildHolderVStack {
+    pub w: i32,
+    pub h: i32,
+    pub pad: i32,
+    pub filler: i32,
+    pub split: i32,
+    pub b_auto: bool,
+    pub align: AlignH,
+    pub vert: HeightAuto,
+    pub cons: StackConstraint,
+}
+
+pub struct ChildHolderHStack {
+    pub w: i32,
+    pub h: i32,
+    pub pad: i32,
+    pub filler: i32,
+    pub split: i32,
+    pub b_auto: bool,
+    pub align: AlignV,
+    pub horz: WidthAuto,
+    pub cons: StackConstraint,
+}
+
+pub struct ChildHolderGrid {
+    pub w: i32,
+    pub h: i32,
+    pub pad: i32,
+    pub row: usize,
+    pub col: usize,
+    pub row_span: usize,
+    pub col_span: usize,
+    pub align_h: AlignH,
+    pub align_v: AlignV,
+}
+
+pub enum ChildHolder {
+    Place(ChildHolderPlace), VStack(ChildHolderVStack), HStack(ChildHolderHStack), Grid(ChildHolderGrid)
+}
+
+impl ChildHolder {
+    fn is_kind_of(&self, layout: &Layout) -> bool {
+        matches!((self, layout),
+            (ChildHolder::Place(_), Layout::Place) | (ChildHolder::VStack(_), Layout::VStack) |
+            (ChildHolder::HStack(_), Layout::HStack) | (ChildHolder::Grid(_), Layout::Grid))
+    }
+
+    fn layout_kind(&self) -> Layout {
+        match self {
+            ChildHolder::Place(_) => Layout::Place,
+            ChildHolder::VStack(_) => Layout::VStack,
+            ChildHolder::HStack(_) => Layout::HStack,
+            ChildHolder::Grid(_) => Layout::Grid,
+        }
+    }
+
+    /** 子コンテナの最小サイズを反映する(スタックの主軸方向のみ有効) */
+    fn set_size_min(&mut self, width: i32, height: i32) {
+        match self {
+            ChildHolder::VStack(v) => { v.cons.size_min = height; }
+            ChildHolder::HStack(v) => { v.cons.size_min = width; }
+            _ => {}
+        }
+    }
+
+    /** 子コンテナのminサイズを反映する(Placeの場合、大きさの指定はピクセル固定となる) */
+    fn set_size(&mut self, width: i32, height: i32) {
+        match self {
+            ChildHolder::Place(v) => {
+                (v.w, v.h) = (width as f64, height as f64);
+                v.span_offset = 0;
+                v.span_kind = PlaceSet::PIXEL;
+            }
+            ChildHolder::VStack(v) => { (v.w, v.h) = (width, height); }
+            ChildHolder::HStack(v) => { (v.w, v.h) = (width, height); }
+            ChildHolder::Grid(v) => { (v.w, v.h) = (width, height); }
+        }
+    }
+}
+
+pub struct LayoutNode {
+    pub key: isize,
+    pub param: ChildHolder,
+    #[allow(unused)]
+    pub sub: Option<LayoutTree>, // 子コンテナ(入れ子のレイアウト)。WindowContainerは子コンテナもウィンドウとして別に持つため使わない
+}
+
+#[derive(Default)]
+pub struct LayoutTree {
+    pub layout: Layout,
+    pub grid_rows: Vec<GridSize>,
+    pub grid_cols: Vec<GridSize>,
+    pub nodes: Vec<LayoutNode>,
+}
+
+impl LayoutTree {
+    /** 最初に追加したノードの種類でレイアウトが決まる。種類の異なるノードは追加しない */
+    pub fn add(&mut self, key: isize, param: ChildHolder) -> bool {
+        self.add_node(LayoutNode { key: key, param: param, sub: None })
+    }
+
+    #[allow(unused)]
+    pub fn add_sub(&mut self, key: isize, param: ChildHolder, sub: LayoutTree) -> bool {
+        self.add_node(LayoutNode { key: key, param: param, sub: Some(sub) })
+    }
+
+    fn add_node(&mut self, node: LayoutNode) -> bool {
+        if let Layout::None = self.layout {
+            self.layout = node.param.layout_kind();
+        }
+        if !node.param.is_kind_of(&self.layout) { return false }
+        self.nodes.push(node);
+        true
+    }
+
+    /** 行・列の定義。定義外の行・列にアイテムを配置した場合はAUTOとして扱う */
+    pub fn grid_define(&mut self, rows: &[GridSize], cols: &[GridSize]) {
+        if let Layout::None = self.layout {
+            self.layout = Layout::Grid;
+        }
+        self.grid_rows = rows.to_vec();
+        self.grid_cols = cols.to_vec();
+    }
+
+    pub fn get(&self, key: isize) -> Option<&ChildHolder> {
+        self.nodes.iter().find(|n| n.key == key).map(|n| &n.param)
+    }
+
+    pub fn set(&mut self, key: isize, param: ChildHolder) -> bool {
+        if !param.is_kind_of(&self.layout) { return false }
+        match self.nodes.iter_mut().find(|n| n.key == key) {
+            Some(n) => { n.param = param; true }
+            None => false
+        }
+    }
+
+    pub fn get_mut(&mut self, key: isize) -> Option<&mut ChildHolder> {
+        self.nodes.iter_mut().find(|n| n.key == key).map(|n| &mut n.param)
+    }
+
+    /** 子コンテナの基準サイズと最小サイズを反映する */
+    pub fn set_size(&mut self, key: isize, (pref_w, pref_h): (i32, i32), (min_w, min_h): (i32, i32)) -> bool {
+        match self.nodes.iter_mut().find(|n| n.key == key) {
+            Some(n) => {
+                n.param.set_size(pref_w, pref_h);
+                n.param.set_size_min(min_w, min_h);
+                true
+            }
+            None => false
+        }
+    }
+
+    /** スタック要素の最小・最大サイズと重みを設定する(スタック以外はfalse) */
+    pub fn set_constraint(&mut self, key: isize, min: i32, max: i32, weight: i32) -> bool {
+        match self.get_mut(key) {
+            Some(ChildHolder::VStack(ChildHolderVStack { cons, .. })) | Some(ChildHolder::HStack(ChildHolderHStack { cons, .. })) => {
+                (cons.min, cons.max, cons.weight) = (min, max, weight);
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn remove(&mut self, key: isize) -> bool {
+        let len = self.nodes.len();
+        self.nodes.retain(|n| n.key != key);
+        len != self.nodes.len()
+    }
+
+    /** 子コンテナのminサイズを再帰的に再計算してノードに反映し、自身のminサイズを返す */
+    #[allow(unused)]
+    pub fn measure(&mut self) -> (i32, i32) {
+        for n in self.nodes.iter_mut() {
+            if let Some(sub) = n.sub.as_mut() {
+                let (min, pref) = (sub.measure(), sub.pref_size());
+                n.param.set_size(pref.0, pref.1);
+                n.param.set_size_min(min.0, min.1);
+            }
+        }
+        let (w, h, _) = self.solve(0, 0, true);
+        (w, h)
+    }
+
+    /** 入れ子を含めたすべてのノードの矩形を計算する(座標はルート左上基準、スクロールは考慮しない) */
+    #[allow(unused)]
+    pub fn solve_all(&mut self, cont_w: i32, cont_h: i32) -> Vec<(isize, LayoutRect)> {
+        self.measure();
+        let mut v = vec![];
+        self.solve_all_inner(cont_w, cont_h, 0, 0, &mut v);
+        v
+    }
+
+    #[allow(unused)]
+    fn solve_all_inner(&self, cont_w: i32, cont_h: i32, x: i32, y: i32, out: &mut Vec<(isize, LayoutRect)>) {
+        let (_, _, rects) = self.solve(cont_w, cont_h, false);
+        for (key, rc) in rects {
+            let rc = LayoutRect { x: rc.x + x, y: rc.y + y, ..rc };
+            out.push((key, rc));
+            if let Some(sub) = self.nodes.iter().find(|n| n.key == key).and_then(|n| n.sub.as_ref()) {
+                sub.solve_all_inner(rc.w, rc.h, rc.x, rc.y, out);
+            }
+        }
+    }
+
+    /** 1階層分の配置計算。戻り値は(ビュー幅, ビュー高さ, 各ノードの矩形)。b_min_checkの場合は矩形を返さない */
+    pub fn solve(&self, cont_w: i32, cont_h: i32, b_min_check: bool) -> (i32, i32, Vec<(isize, LayoutRect)>) {
+        match self.layout {
+            Layout::None => { (0, 0, vec![]) }
+            Layout::VStack => { self.solve_vstack(cont_w, cont_h, b_min_check) }
+            Layout::HStack => { self.solve_hstack(cont_w, cont_h, b_min_check) }
+            Layout::Place => { self.solve_place(cont_w, cont_h, b_min_check) }
+            Layout::Grid => { self.solve_grid(cont_w, cont_h, b_min_check) }
+        }
+    }
+
+    /** スタックの主軸方向の各要素(基準サイズ, 最小, 最大, 重み, 伸長可否)と、要素以外の固定分(pad, split)の合計 */
+    fn stack_items(&self) -> (Vec<(i32, i32, i32, i32, bool)>, i32) {
+        let mut items = vec![];
+        let mut size_fixed = 0i32;
+        for n in self.nodes.iter() {
+            let (base, b_grow, pad, split, c) = match &n.param {
+                ChildHolder::VStack(v) => {
+                    let b_auto = matches!(v.vert, HeightAuto::AUTO);
+                    (if b_auto && !v.b_auto { v.h + v.filler } else { v.h }, b_auto && v.b_auto, v.pad, v.split, &v.cons)
+                }
+                ChildHolder::HStack(v) => {
+                    let b_auto = matches!(v.horz, WidthAuto::AUTO);
+                    (if b_auto && !v.b_auto { v.w + v.filler } else { v.w }, b_auto && v.b_auto, v.pad, v.split, &v.cons)
+                }
+                _ => { continue }
+            };
+            items.push((base, c.min_size(base), c.max, c.weight, b_grow));
+            size_fixed += pad * 2 + split;
+        }
+        (items, size_fixed)
+    }
+
+    /** 縮小を考慮しない、各要素を基準サイズで並べたときの大きさ */
+    pub fn pref_size(&self) -> (i32, i32) {
+        match self.layout {
+            Layout::VStack | Layout::HStack => {
+                let (items, size_fixed) = self.stack_items();
+                let (w_min, h_min, _) = self.solve(0, 0, true);
+                let size = items.iter().map(|i| i.0).sum::<i32>() + size_fixed;
+                if let Layout::VStack = self.layout { (w_min, size) } else { (size, h_min) }
+            }
+            _ => {
+                let (w, h, _) = self.solve(0, 0, true);
+                (w, h)
+            }
+        }
+    }
+
+    fn solve_vstack(&self, cont_w: i32, cont_h: i32, b_min_check: bool) -> (i32, i32, Vec<(isize, LayoutRect)>) {
+        // 子コントロールのサイズをすべて計算し、ビューサイズを計算(一番右下はどこか)
+        let mut view_w = 0i32;
+        let mut view_h = 0i32;
+
+        let (items, size_fixed) = self.stack_items();
+        let size_h_min = items.iter().map(|i| i.1).sum::<i32>() + size_fixed; // 縮小可能な要素は最小サイズで積み上げ
+
+        let mut b_expand = false;
+        for n in self.nodes.iter() {
+            if let ChildHolder::VStack(v) = &n.param {
+                if v.w + v.pad * 2 > view_w { view_w = v.w + v.pad * 2; }
+                if let AlignH::EXPAND = v.align { b_expand = true; }
+            }
+        }
+
+        if b_min_check {
+            return (view_w, size_h_min, vec![])
+        }
+
+        // MINサイズの積み上げでビューの方が小さければコンテナのサイズまで広げる
+        if b_expand && cont_w > view_w { view_w = cont_w; }
+
+        // コンテナの空きサイズ(または不足サイズ)を重みで配分
+        let sizes = stack_distribute(&items, cont_h - size_fixed);
+
+        let mut now = 0i32;
+        let mut rects = vec![];
+
+        for (n, newh) in self.nodes.iter().filter(|n| matches!(n.param, ChildHolder::VStack(_))).zip(sizes) { // 各要素の配置計算、ビューサイズの積み上げ
+            if let ChildHolder::VStack(v) = &n.param {
+                let (newx, neww) =
+                    match v.align {
+                        AlignH::LEFT => (v.pad, v.w),
+                        AlignH::RIGHT => (view_w - v.pad - v.w, v.w),
+                        AlignH::CENTER => (if view_w / 2 - v.w / 2 > 0 { view_w / 2 - v.w / 2 } else { v.pad }, v.w),
+                        AlignH::FILL | AlignH::EXPAND => (v.pad, view_w - v.pad * 2),
+                    };
+                let newy = now + v.pad;
+                rects.push((n.key, LayoutRect { x: newx, y: newy, w: neww, h: newh }));
+                now += newh + v.pad * 2 + v.split;
+                if view_h < now { view_h = now; }
+            }
+        }
+        (view_w, view_h, rects)
+    }
+
+    fn solve_hstack(&self, cont_w: i32, cont_h: i32, b_min_check: bool) -> (i32, i32, Vec<(isize, LayoutRect)>) {
+        // 子コントロールのサイズをすべて計算し、ビューサイズを計算(一番右下はどこか)
+        let mut view_w = 0i32;
+        let mut view_h = 0i32;
+
+        let (items, size_fixed) = self.stack_items();
+        let size_w_min = items.iter().map(|i| i.1).sum::<i32>() + size_fixed; // 縮小可能な要素は最小サイズで積み上げ
+
+        let mut b_expand = false;
+        for n in self.nodes.iter() {
+            if let ChildHolder::HStack(v) = &n.param {
+                if v.h + v.pad * 2 > view_h { view_h = v.h + v.pad * 2; }
+                if let AlignV::EXPAND = v.align { b_expand = true; }
+            }
+        }
+
+        if b_min_check {
+            return (size_w_min, view_h, vec![])
+        }
+
+        // MINサイズの積み上げでビューの方が小さければコンテナのサイズまで広げる
+        if b_expand && cont_h > view_h { view_h = cont_h; }
+
+        // コンテナの空きサイズ(または不足サイズ)を重みで配分
+        let sizes = stack_distribute(&items, cont_w - size_fixed);
+
+        let mut now = 0i32;
+        let mut rects = vec![];
+
+        for (n, neww) in self.nodes.iter().filter(|n| matches!(n.param, ChildHolder::HStack(_))).zip(sizes) { // 各要素の配置計算、ビューサイズの積み上げ
+            if let ChildHolder::HStack(v) = &n.param {
+                let (newy, newh) =
+                    match v.align {
+                        AlignV::TOP => (v.pad, v.h),
+                        AlignV::BOTTOM => (view_h - v.pad - v.h, v.h),
+                        AlignV::CENTER => (if view_h / 2 - v.h / 2 > 0 { view_h / 2 - v.h / 2 } else { v.pad }, v.h),
+                        AlignV::FILL | AlignV::EXPAND => (v.pad, view_h - v.pad * 2),
+                    };
+                let newx = now + v.pad;
+                rects.push((n.key, LayoutRect { x: newx, y: newy, w: neww, h: newh }));
+                now += neww + v.pad * 2 + v.split;
+                if view_w < now { view_w = now; }
+            }
+        }
+        (view_w, view_h, rects)
+    }
+
+    fn solve_place(&self, cont_w: i32, cont_h: i32, b_min_check: bool) -> (i32, i32, Vec<(isize, LayoutRect)>) {
+        // OFFSET指定は基準ノードの矩形が必要なため、未計算なら先に計算する
+        let mut solved = vec![None; self.nodes.len()];
+        for i in 0..self.nodes.len() {
+            self.place_rect(i, cont_w, cont_h, &mut solved, 0);
+        }
+
+        // 子コントロールのサイズをすべて計算し、ビューサイズを計算(一番右下はどこか)
+        let mut view_w = 0i32;
+        let mut view_h = 0i32;
+        let mut rects = vec![];
+
+        for (n, rc) in self.nodes.iter().zip(solved) {
+            if let (ChildHolder::Place(_), Some(rc)) = (&n.param, rc) {
+                if view_w < rc.x + rc.w { view_w = rc.x + rc.w; }
+                if view_h < rc.y + rc.h { view_h = rc.y + rc.h; }
+                rects.push((n.key, rc));
+            }
+        }
+        (view_w, view_h, if b_min_check { vec![] } else { rects })
+    }
+
+    fn place_rect(&self, idx: usize, pw: i32, ph: i32, solved: &mut Vec<Option<LayoutRect>>, depth: usize) -> LayoutRect {
+        if let Some(rc) = solved[idx] { return rc }
+        let ChildHolder::Place(v) = &self.nodes[idx].param else { return LayoutRect::default() };
+
+        // 基準ノードの矩形(循環参照、存在しないキーは原点・サイズ0として扱う)
+        let refer = |key: isize, solved: &mut Vec<Option<LayoutRect>>| -> LayoutRect {
+            match self.nodes.iter().position(|n| n.key == key) {
+                Some(i) if depth < self.nodes.len() => self.place_rect(i, pw, ph, solved, depth + 1),
+                _ => LayoutRect::default(),
+            }
+        };
+
+        let (newx, newy) = match v.pos_kind {
+            PlaceSet::PIXEL => (v.x.round() as i32, v.y.round() as i32),
+            PlaceSet::REL => ((pw as f64 * v.x).round() as i32, (ph as f64 * v.y).round() as i32),
+            PlaceSet::OFFSET => {
+                let rc = refer(v.pos_offset, solved);
+                (rc.x + v.x.round() as i32, rc.y + v.y.round() as i32)
+            }
+        };
+        let (neww, newh) = match v.span_kind {
+            PlaceSet::PIXEL => (v.w.round() as i32, v.h.round() as i32),
+            PlaceSet::REL => ((pw as f64 * v.w).round() as i32, (ph as f64 * v.h).round() as i32),
+            PlaceSet::OFFSET => { // 基準ノードの右下端からの相対
+                let rc = refer(v.span_offset, solved);
+                (rc.x + rc.w + v.w.round() as i32, rc.y + rc.h + v.h.round() as i32)
+            }
+        };
+        let rc = LayoutRect { x: newx, y: newy, w: neww, h: newh };
+        solved[idx] = Some(rc);
+        rc
+    }
+
+    fn solve_grid(&self, cont_w: i32, cont_h: i32, b_min_check: bool) -> (i32, i32, Vec<(isize, LayoutRect)>) {
+        // 行・列ごとに、(開始位置, スパン数, 必要サイズ)を集める
+        let mut items_row = vec![];
+        let mut items_col = vec![];
+        for n in self.nodes.iter() {
+            if let ChildHolder::Grid(v) = &n.param {
+                items_row.push((v.row, v.row_span, v.h + v.pad * 2));
+                items_col.push((v.col, v.col_span, v.w + v.pad * 2));
+            }
+        }
+
+        let rows = grid_track_size(&self.grid_rows, &items_row, cont_h, b_min_check);
+        let cols = grid_track_size(&self.grid_cols, &items_col, cont_w, b_min_check);
+        let (view_w, view_h) = (cols.iter().sum::<i32>(), rows.iter().sum::<i32>());
+
+        if b_min_check {
+            return (view_w, view_h, vec![])
+        }
+
+        // 各セルの開始位置(先頭からの累積)
+        let offset = |v: &Vec<i32>, idx: usize| -> i32 { v.iter().take(idx).sum() };
+        let span = |v: &Vec<i32>, idx: usize, n: usize| -> i32 { v.iter().skip(idx).take(n).sum() };
+        let mut rects = vec![];
+
+        for n in self.nodes.iter() { // 各要素の配置計算
+            if let ChildHolder::Grid(v) = &n.param {
+                let (cell_x, cell_w) = (offset(&cols, v.col), span(&cols, v.col, v.col_span));
+                let (cell_y, cell_h) = (offset(&rows, v.row), span(&rows, v.row, v.row_span));
+                let (newx, neww) =
+                    match v.align_h {
+                        AlignH::LEFT => (cell_x + v.pad, v.w),
+                        AlignH::RIGHT => (cell_x + cell_w - v.pad - v.w, v.w),
+                        AlignH::CENTER => (cell_x + (cell_w - v.w) / 2, v.w),
+                        AlignH::FILL | AlignH::EXPAND => (cell_x + v.pad, cell_w - v.pad * 2),
+                    };
+                let (newy, newh) =
+                    match v.align_v {
+                        AlignV::TOP => (cell_y + v.pad, v.h),
+                        AlignV::BOTTOM => (cell_y + cell_h - v.pad - v.h, v.h),
+                        AlignV::CENTER => (cell_y + (cell_h - v.h) / 2, v.h),
+                        AlignV::FILL | AlignV::EXPAND => (cell_y + v.pad, cell_h - v.pad * 2),
+                    };
+                rects.push((n.key, LayoutRect { x: newx, y: newy, w: neww, h: newh }));
+            }
+        }
+        (view_w, view_h, rects)
+    }
+}
+
+/** スタック主軸方向のサイズ配分。items は(基準サイズ, 最小, 最大(-1:制限なし), 重み, 伸長可否)
+ * 空きがあれば伸長可能な要素へ重みで分配し(最大サイズで頭打ち)、足りなければ最小サイズまで重みで縮小する
+ * 整数除算の剰余は対象の最後の要素で埋める */
+fn stack_distribute(items: &[(i32, i32, i32, i32, bool)], avail: i32) -> Vec<i32> {
+    let mut size = items.iter().map(|i| i.0).collect::<Vec<_>>();
+    let b_grow = avail > size.iter().sum::<i32>();
+    let mut rest = (avail - size.iter().sum::<i32>()).abs();
+
+    // 対象要素が上限(下限)に達したら外して、残りを再配分する
+    let mut done = vec![false; items.len()];
+    loop {
+        let target = (0..items.len()).filter(|i| {
+            let (base, min, _, weight, b_auto) = items[*i];
+            !done[*i] && weight > 0 && if b_grow { b_auto } else { min < base }
+        }).collect::<Vec<_>>();
+        if rest <= 0 || target.is_empty() { break }
+
+        let weight_total = target.iter().map(|i| items[*i].3).sum::<i32>();
+        let rest_round = rest;
+        let mut b_clamped = false;
+        for (n, i) in target.iter().enumerate() {
+            let (_, min, max, weight, _) = items[*i];
+            let mut d = if n + 1 == target.len() { rest } else { rest_round * weight / weight_total };
+            if b_grow {
+                if max >= 0 && size[*i] + d >= max { d = (max - size[*i]).max(0); done[*i] = true; b_clamped = true; }
+                size[*i] += d;
+            } else {
+                if size[*i] - d <= min { d = size[*i] - min; done[*i] = true; b_clamped = true; }
+                size[*i] -= d;
+            }
+            rest -= d;
+        }
+        if !b_clamped { break }
+    }
+    size
+}
+
+/** グリッドの行(または列)ごとのサイズを計算する。itemsは(開始位置, スパン数, 必要サイズ) */
+fn grid_track_size(defs: &[GridSize], items: &[(usize, usize, i32)], cont: i32, b_min_check: bool) -> Vec<i32> {
+    let num = items.iter().map(|(i, n, _)| i + n).max().unwrap_or(0).max(defs.len());
+    let def = |i: usize| -> GridSize { defs.get(i).cloned().unwrap_or_default() };
+
+    // 固定サイズの確定と、スパン1のアイテムによるAUTO/STARの最小サイズ
+    let mut size = (0..num).map(|i| if let GridSize::PIXEL(n) = def(i) { n } else { 0 }).collect::<Vec<_>>();
+    for (i, _, need) in items.iter().filter(|(_, n, _)| *n == 1) {
+        if let GridSize::PIXEL(_) = def(*i) { continue }
+        if size[*i] < *need { size[*i] = *need; }
+    }
+
+    // 複数スパンのアイテムは、足りない分をスパン内最後の可変行(なければ最後の行)に上乗せ
+    let mut spans = items.iter().filter(|(_, n, _)| *n > 1).collect::<Vec<_>>();
+    spans.sort_by_key(|(_, n, _)| *n);
+    for (i, n, need) in spans {
+        let now = size[*i..*i + *n].iter().sum::<i32>();
+        if now >= *need { continue }
+        let target = (*i..*i + *n).rev().find(|j| !matches!(def(*j), GridSize::PIXEL(_))).unwrap_or(*i + *n - 1);
+        size[target] += *need - now;
+    }
+
+    if b_min_check {
+        return size
+    }
+
+    // コンテナの空きサイズをSTARの重みで分配(整数除算の剰余は最後のSTARで埋める)
+    let weight_total = (0..num).map(|i| if let GridSize::STAR(w) = def(i) { w.max(0) } else { 0 }).sum::<i32>();
+    let size_free = cont - size.iter().sum::<i32>();
+    if size_free > 0 && weight_total > 0 {
+        let mut rest = size_free;
+        let last = (0..num).rev().find(|i| matches!(def(*i), GridSize::STAR(w) if w > 0)).unwrap();
+        for (i, sz) in size.iter_mut().enumerate() {
+            if let GridSize::STAR(w) = def(i) {
+                if w <= 0 { continue }
+                let add = if i == last { rest } else { size_free * w / weight_total };
+                *sz += add;
+                rest -= add;
+            }
+        }
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 計算結果を「キー: x,y wxh」の行に整形してスナップショットと比較する
+    fn snapshot(view: (i32, i32), rects: &[(isize, LayoutRect)]) -> String {
+        let mut s = format!("view: {}x{}\n", view.0, view.1);
+        for (key, rc) in rects {
+            s += &format!("{}: {},{} {}x{}\n", key, rc.x, rc.y, rc.w, rc.h);
+        }
+        s
+    }
+
+    fn solve_snapshot(t: &LayoutTree, cont_w: i32, cont_h: i32) -> String {
+        let (w, h, rects) = t.solve(cont_w, cont_h, false);
+        snapshot((w, h), &rects)
+    }
+
+    fn vs(w: i32, h: i32, pad: i32, b_auto: bool, align: AlignH, vert: HeightAuto) -> ChildHolder {
+        ChildHolder::VStack(ChildHolderVStack { w, h, pad, filler: 0, split: 0, b_auto, align, vert, cons: StackConstraint::default() })
+    }
+
+    fn hs(w: i32, h: i32, pad: i32, b_auto: bool, horz: WidthAuto, align: AlignV) -> ChildHolder {
+        ChildHolder::HStack(ChildHolderHStack { w, h, pad, filler: 0, split: 0, b_auto, align, horz, cons: StackConstraint::default() })
+    }
+
+    fn gr(w: i32, h: i32, row: usize, col: usize, row_span: usize, col_span: usize, align_h: AlignH, align_v: AlignV) -> ChildHolder {
+        ChildHolder::Grid(ChildHolderGrid { w, h, pad: 0, row, col, row_span, col_span, align_h, align_v })
+    }
+
+    fn pl(x: f64, y: f64, w: f64, h: f64, pos_kind: PlaceSet, span_kind: PlaceSet, pos_offset: isize, span_offset: isize) -> ChildHolder {
+        ChildHolder::Place(ChildHolderPlace { x, y, w, h, pos_offset, span_offset, pos_kind, span_kind })
+    }
+
+    #[test]
+    fn vstack_align() {
+        let mut t = LayoutTree::default();
+        t.add(1, vs(40, 10, 2, false, AlignH::LEFT, HeightAuto::FIX));
+        t.add(2, vs(20, 10, 2, false, AlignH::CENTER, HeightAuto::FIX));
+        t.add(3, vs(20, 10, 2, false, AlignH::RIGHT, HeightAuto::FIX));
+        t.add(4, vs(20, 10, 2, false, AlignH::FILL, HeightAuto::FIX));
+        assert_eq!(solve_snapshot(&t, 200, 100), "\
+view: 44x56
+1: 2,2 40x10
+2: 12,16 20x10
+3: 22,30 20x10
+4: 2,44 40x10
+");
+    }
+
+    #[test]
+    fn vstack_expand_widens_to_container() {
+        let mut t = LayoutTree::default();
+        t.add(1, vs(40, 10, 0, false, AlignH::LEFT, HeightAuto::FIX));
+        t.add(2, vs(20, 10, 0, false, AlignH::EXPAND, HeightAuto::FIX));
+        t.add(3, vs(20, 10, 0, false, AlignH::RIGHT, HeightAuto::FIX));
+        assert_eq!(solve_snapshot(&t, 100, 50), "\
+view: 100x30
+1: 0,0 40x10
+2: 0,10 100x10
+3: 80,20 20x10
+");
+        // コンテナが小さい場合は中身の最大幅のまま
+        assert_eq!(solve_snapshot(&t, 30, 50), "\
+view: 40x30
+1: 0,0 40x10
+2: 0,10 40x10
+3: 20,20 20x10
+");
+    }
+
+    #[test]
+    fn vstack_auto_fill() {
+        let mut t = LayoutTree::default();
+        t.add(1, vs(10, 10, 0, true, AlignH::FILL, HeightAuto::AUTO));
+        t.add(2, vs(10, 10, 0, false, AlignH::FILL, HeightAuto::FIX));
+        t.add(3, vs(10, 10, 0, true, AlignH::FILL, HeightAuto::AUTO));
+        t.add(4, vs(10, 10, 0, true, AlignH::FILL, HeightAuto::AUTO));
+        // 空き71pxを3要素で分配し、剰余は最後の要素で埋める
+        assert_eq!(solve_snapshot(&t, 10, 111), "\
+view: 10x111
+1: 0,0 10x33
+2: 0,33 10x10
+3: 0,43 10x33
+4: 0,76 10x35
+");
+        assert_eq!(t.solve(10, 111, true).0, 10);
+        assert_eq!(t.solve(10, 111, true).1, 40);
+    }
+
+    #[test]
+    fn vstack_filler_and_split() {
+        let mut t = LayoutTree::default();
+        t.add(1, ChildHolder::VStack(ChildHolderVStack { w: 10, h: 10, pad: 1, filler: 5, split: 3, b_auto: false, align: AlignH::LEFT, vert: HeightAuto::AUTO, cons: StackConstraint::default() }));
+        t.add(2, vs(10, 10, 1, false, AlignH::LEFT, HeightAuto::FIX));
+        assert_eq!(solve_snapshot(&t, 50, 50), "\
+view: 12x32
+1: 1,1 10x15
+2: 1,21 10x10
+");
+        assert_eq!(t.solve(50, 50, true).1, 32);
+    }
+
+    #[test]
+    fn hstack_align_and_fill() {
+        let mut t = LayoutTree::default();
+        t.add(1, hs(10, 30, 0, false, WidthAuto::FIX, AlignV::TOP));
+        t.add(2, hs(10, 10, 0, false, WidthAuto::FIX, AlignV::CENTER));
+        t.add(3, hs(10, 10, 0, false, WidthAuto::FIX, AlignV::BOTTOM));
+        t.add(4, hs(10, 10, 0, true, WidthAuto::AUTO, AlignV::FILL));
+        t.add(5, hs(10, 10, 0, false, WidthAuto::FIX, AlignV::EXPAND));
+        assert_eq!(solve_snapshot(&t, 100, 60), "\
+view: 100x60
+1: 0,0 10x30
+2: 10,25 10x10
+3: 20,50 10x10
+4: 30,0 60x60
+5: 90,0 10x60
+");
+        assert_eq!(t.solve(100, 60, true), (50, 30, vec![]));
+    }
+
+    #[test]
+    fn place_pixel_rel_offset() {
+        let mut t = LayoutTree::default();
+        // 後に追加したノードを基準とするOFFSET指定(パネル枠とその中身の関係)
+        t.add(1, pl(0.0, 0.0, 2.0, 2.0, PlaceSet::PIXEL, PlaceSet::OFFSET, 0, 2));
+        t.add(2, pl(2.0, 2.0, 50.0, 20.0, PlaceSet::PIXEL, PlaceSet::PIXEL, 0, 0));
+        t.add(3, pl(0.5, 0.25, 0.5, 0.5, PlaceSet::REL, PlaceSet::REL, 0, 0));
+        t.add(4, pl(5.0, 0.0, 10.0, 10.0, PlaceSet::OFFSET, PlaceSet::PIXEL, 2, 0));
+        assert_eq!(solve_snapshot(&t, 200, 100), "\
+view: 200x75
+1: 0,0 54x24
+2: 2,2 50x20
+3: 100,25 100x50
+4: 7,2 10x10
+");
+    }
+
+    #[test]
+    fn place_cyclic_offset_does_not_hang() {
+        let mut t = LayoutTree::default();
+        t.add(1, pl(1.0, 1.0, 1.0, 1.0, PlaceSet::OFFSET, PlaceSet::PIXEL, 2, 0));
+        t.add(2, pl(1.0, 1.0, 1.0, 1.0, PlaceSet::OFFSET, PlaceSet::PIXEL, 1, 0));
+        let (_, _, rects) = t.solve(10, 10, false);
+        assert_eq!(rects.len(), 2);
+    }
+
+    #[test]
+    fn grid_auto_star_span() {
+        let mut t = LayoutTree::default();
+        t.grid_define(&[GridSize::AUTO, GridSize::PIXEL(20), GridSize::STAR(1)], &[GridSize::AUTO, GridSize::STAR(1), GridSize::STAR(2)]);
+        t.add(1, gr(30, 10, 0, 0, 1, 1, AlignH::RIGHT, AlignV::CENTER));
+        t.add(2, gr(10, 14, 0, 1, 1, 2, AlignH::FILL, AlignV::FILL));
+        t.add(3, gr(10, 10, 1, 0, 1, 1, AlignH::LEFT, AlignV::BOTTOM));
+        t.add(4, gr(10, 10, 1, 1, 2, 1, AlignH::CENTER, AlignV::TOP));
+        t.add(5, gr(10, 10, 2, 2, 1, 1, AlignH::EXPAND, AlignV::EXPAND));
+        assert_eq!(solve_snapshot(&t, 130, 64), "\
+view: 130x64
+1: 0,2 30x10
+2: 30,0 100x14
+3: 0,24 10x10
+4: 43,14 10x10
+5: 66,34 64x30
+");
+        assert_eq!(t.solve(130, 64, true), (50, 44, vec![]));
+    }
+
+    #[test]
+    fn grid_span_grows_last_flexible_track() {
+        let mut t = LayoutTree::default();
+        t.grid_define(&[], &[GridSize::AUTO, GridSize::AUTO, GridSize::PIXEL(5)]);
+        t.add(1, gr(10, 10, 0, 0, 1, 1, AlignH::FILL, AlignV::FILL));
+        t.add(2, gr(50, 10, 1, 0, 1, 3, AlignH::FILL, AlignV::FILL));
+        assert_eq!(solve_snapshot(&t, 0, 0), "\
+view: 50x20
+1: 0,0 10x10
+2: 0,10 50x10
+");
+        // 不足分はスパン内最後のAUTO列に乗る(列幅は 10, 35, 5)
+        assert_eq!(grid_track_size(&t.grid_cols, &[(0, 1, 10), (0, 3, 50)], 0, true), vec![10, 35, 5]);
+    }
+
+    #[test]
+    fn nested_tree_propagates_min_size() {
+        let mut inner = LayoutTree::default();
+        inner.add(11, hs(30, 10, 0, false, WidthAuto::FIX, AlignV::EXPAND));
+        inner.add(12, hs(20, 16, 0, true, WidthAuto::AUTO, AlignV::FILL));
+
+        let mut root = LayoutTree::default();
+        root.add(1, vs(0, 10, 0, false, AlignH::EXPAND, HeightAuto::FIX));
+        root.add_sub(2, vs(0, 0, 0, true, AlignH::FILL, HeightAuto::AUTO), inner);
+
+        assert_eq!(root.measure(), (50, 26));
+        let rects = root.solve_all(100, 40);
+        assert_eq!(snapshot((100, 40), &rects), "\
+view: 100x40
+1: 0,0 100x10
+2: 0,10 100x30
+11: 0,10 30x30
+12: 30,10 70x30
+");
+    }
+
+    #[test]
+    fn hstack_weight_and_max() {
+        let mut t = LayoutTree::default();
+        t.add(1, hs(10, 10, 0, false, WidthAuto::FIX, AlignV::FILL));
+        t.add(2, hs(120, 10, 0, true, WidthAuto::AUTO, AlignV::FILL));
+        t.add(3, hs(0, 10, 0, true, WidthAuto::AUTO, AlignV::FILL));
+        t.set_constraint(2, -1, 600, 2);
+        // 空き90pxを2:1で分配
+        assert_eq!(solve_snapshot(&t, 220, 10), "\
+view: 220x10
+1: 0,0 10x10
+2: 10,0 180x10
+3: 190,0 30x10
+");
+        // 最大サイズで頭打ちになった分は残りの要素へ
+        assert_eq!(solve_snapshot(&t, 1000, 10), "\
+view: 1000x10
+1: 0,0 10x10
+2: 10,0 600x10
+3: 610,0 390x10
+");
+    }
+
+    #[test]
+    fn vstack_shrink_to_min() {
+        let mut t = LayoutTree::default();
+        t.add(1, vs(10, 100, 0, true, AlignH::FILL, HeightAuto::FIX));
+        t.add(2, vs(10, 60, 0, true, AlignH::FILL, HeightAuto::FIX));
+        t.add(3, vs(10, 20, 0, true, AlignH::FILL, HeightAuto::FIX));
+        t.set_constraint(1, 30, -1, 1);
+        t.set_constraint(2, 0, -1, 3);
+        assert_eq!(t.solve(10, 0, true), (10, 50, vec![]));
+        assert_eq!(t.pref_size(), (10, 180));
+        // 不足40pxを1:3で縮小
+        assert_eq!(solve_snapshot(&t, 10, 140), "\
+view: 10x140
+1: 0,0 10x90
+2: 0,90 10x30
+3: 0,120 10x20
+");
+        // 最小サイズに達した要素は外し、残りを縮小。それでも足りなければ最小サイズのまま(スクロール)
+        assert_eq!(solve_snapshot(&t, 10, 60), "\
+view: 10x60
+1: 0,0 10x40
+2: 0,40 10x0
+3: 0,40 10x20
+");
+        assert_eq!(solve_snapshot(&t, 10, 10), "\
+view: 10x50
+1: 0,0 10x30
+2: 0,30 10x0
+3: 0,30 10x20
+");
+    }
+
+    #[test]
+    fn nested_min_size_is_propagated() {
+        let mut inner = LayoutTree::default();
+        inner.add(11, vs(10, 200, 0, true, AlignH::FILL, HeightAuto::FIX));
+        inner.add(12, vs(10, 20, 0, true, AlignH::FILL, HeightAuto::FIX));
+        inner.set_constraint(11, 50, -1, 1);
+
+        let mut root = LayoutTree::default();
+        root.add_sub(1, vs(0, 0, 0, true, AlignH::FILL, HeightAuto::FIX), inner);
+        root.add(2, vs(10, 30, 0, false, AlignH::FILL, HeightAuto::FIX));
+
+        assert_eq!(root.measure(), (10, 100));
+        assert_eq!(root.pref_size(), (10, 250));
+        let rects = root.solve_all(10, 150);
+        assert_eq!(snapshot((10, 150), &rects), "\
+view: 10x150
+1: 0,0 10x120
+11: 0,0 10x100
+12: 0,100 10x20
+2: 0,120 10x30
+");
+    }
+
+    #[test]
+    fn add_rejects_mismatched_kind() {
+        let mut t = LayoutTree::default();
+        assert!(t.add(1, vs(1, 1, 0, false, AlignH::LEFT, HeightAuto::FIX)));
+        assert!(!t.add(2, hs(1, 1, 0, false, WidthAuto::FIX, AlignV::TOP)));
+        assert!(!t.set(1, hs(1, 1, 0, false, WidthAuto::FIX, AlignV::TOP)));
+        assert!(t.set_size(1, (5, 6), (5, 6)));
+        assert!(matches!(t.get(1), Some(ChildHolder::VStack(v)) if v.w == 5 && v.h == 6));
+        assert!(t.remove(1));
+        assert!(t.get(1).is_none());
+    }
+}
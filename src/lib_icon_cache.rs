@@ -0,0 +1,127 @@
+// ランチャーのアイコンキャッシュの保存(終了時にファイルへ書き出し、次回起動時に読み込む)
+//   保存先: %LOCALAPPDATA%\myprogram\mytaskbar\iconcache.bin
+//   形式  : 先頭の識別子に続けて、アイテムごとに キー(ObjectHolder::icon_key), 更新日時, 大アイコン, 小アイコン
+use std::path::PathBuf;
+
+use super::*;
+use lib_gui_layout_container::sys_metrics_without_wnd;
+
+const FILE_MAGIC: &[u8; 8] = b"MTBICON1";
+const MAX_ICON_SIZE: i32 = 256;
+
+// アイコンのピクセル列(上から下へ、1ピクセルはBGRAの4バイト)
+pub struct IconPixels {
+    pub w: i32,
+    pub h: i32,
+    pub bgra: Vec<u8>,
+}
+
+// 保存されたアイコン(次に使われた時点でHICONにする)
+pub struct StoredIcon {
+    pub mtime: u64,
+    pub lr: IconPixels,
+    pub sm: IconPixels,
+}
+
+impl StoredIcon {
+    // 現在の表示倍率と大きさが違うものは使わない
+    pub fn restore(&self) -> Option<(Icon, Icon)> {
+        if self.lr.w != sys_metrics_without_wnd(SM_CXICON) || self.sm.w != sys_metrics_without_wnd(SM_CXSMICON) { return None }
+        Some((Icon::from_pixels(&self.lr)?, Icon::from_pixels(&self.sm)?))
+    }
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let dir = if cfg!(debug_assertions) { "mytaskbar_D" } else { "mytaskbar" };
+    Some(PathBuf::from(std::env::var_os("LOCALAPPDATA")?).join("myprogram").join(dir).join("iconcache.bin"))
+}
+
+pub fn load() -> Vec<(String, StoredIcon)> {
+    cache_path().and_then(|p| std::fs::read(p).ok()).map(|data| decode(&data)).unwrap_or_default()
+}
+
+// 一時ファイルに書いてから置き換える(書き込み途中で終了しても前回の保存は残る)
+pub fn store(entries: &[(&str, &StoredIcon)]) {
+    let Some(path) = cache_path() else { return };
+    let tmp = path.with_extension("tmp");
+    let _ = path.parent().map(std::fs::create_dir_all);
+    if std::fs::write(&tmp, encode(entries)).is_ok() {
+        let _ = std::fs::rename(&tmp, &path);
+    }
+}
+
+fn encode(entries: &[(&str, &StoredIcon)]) -> Vec<u8> {
+    let mut buf = FILE_MAGIC.to_vec();
+    for (key, si) in entries {
+        buf.extend((key.len() as u32).to_le_bytes());
+        buf.extend(key.as_bytes());
+        buf.extend(si.mtime.to_le_bytes());
+        for px in [&si.lr, &si.sm] {
+            buf.extend(px.w.to_le_bytes());
+            buf.extend(px.h.to_le_bytes());
+            buf.extend(&px.bgra);
+        }
+    }
+    buf
+}
+
+// 識別子が違えば空、途中で壊れていればそこまでを返す
+fn decode(data: &[u8]) -> Vec<(String, StoredIcon)> {
+    let mut ret = Vec::default();
+    let Some(mut rest) = data.strip_prefix(FILE_MAGIC.as_slice()) else { return ret };
+    let take = |rest: &mut &[u8], n: usize| -> Option<Vec<u8>> {
+        if rest.len() < n { return None }
+        let (head, tail) = rest.split_at(n);
+        *rest = tail;
+        Some(head.to_vec())
+    };
+    let read_u32 = |rest: &mut &[u8]| take(rest, 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+    let read_pixels = |rest: &mut &[u8]| -> Option<IconPixels> {
+        let w = read_u32(rest)? as i32;
+        let h = read_u32(rest)? as i32;
+        if !(1..=MAX_ICON_SIZE).contains(&w) || !(1..=MAX_ICON_SIZE).contains(&h) { return None }
+        Some(IconPixels { w, h, bgra: take(rest, (w * h * 4) as usize)? })
+    };
+    while !rest.is_empty() {
+        let entry = (|| {
+            let len = read_u32(&mut rest)? as usize;
+            let key = String::from_utf8(take(&mut rest, len)?).ok()?;
+            let mtime = u64::from_le_bytes(take(&mut rest, 8)?.try_into().unwrap());
+            let lr = read_pixels(&mut rest)?;
+            let sm = read_pixels(&mut rest)?;
+            Some((key, StoredIcon { mtime, lr, sm }))
+        })();
+        let Some(entry) = entry else { break };
+        ret.push(entry);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(mtime: u64, w: i32) -> StoredIcon {
+        let px = |w: i32| IconPixels { w, h: w, bgra: (0..w * w * 4).map(|i| i as u8).collect() };
+        StoredIcon { mtime, lr: px(w), sm: px(w / 2) }
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let (a, b) = (stored(10, 4), stored(20, 2));
+        let data = encode(&[(r"C:\a.txt", &a), (r"C:\フォルダ", &b)]);
+        let v = decode(&data);
+        assert_eq!(v.len(), 2);
+        assert_eq!((v[0].0.as_str(), v[0].1.mtime, v[0].1.lr.w, v[0].1.sm.w), (r"C:\a.txt", 10, 4, 2));
+        assert_eq!((v[1].0.as_str(), v[1].1.mtime), (r"C:\フォルダ", 20));
+        assert!(v[0].1.lr.bgra == a.lr.bgra && v[1].1.sm.bgra == b.sm.bgra);
+    }
+
+    #[test]
+    fn decode_stops_at_broken_entry() {
+        let data = encode(&[(r"C:\a.txt", &stored(10, 4)), (r"C:\b.txt", &stored(20, 4))]);
+        assert_eq!(decode(&data[..data.len() - 1]).len(), 1);
+        assert!(decode(b"OTHERFMT").is_empty());
+        assert!(decode(&[]).is_empty());
+    }
+}
@@ -0,0 +1,415 @@
+// プロセスイメージ等のパス照合。大文字小文字と'/'・'\'の違いは区別しない
+//   完全一致  : C:\Windows\explorer.exe
+//   ワイルドカード: *(0文字以上) と ?(1文字) を含むもの  例 C:\Program Files\WindowsApps\MSTeams_*\ms-teams.exe
+//   正規表現  : "re:"で始まるもの  例 re:\\(chrome|msedge)\.exe$
+use std::{cell::RefCell, rc::Rc};
+use crate::lib_common::LruCache;
+
+const REGEX_PREFIX: &str = "re:";
+const COMPILED_MAX: usize = 256;
+
+thread_local! {
+    // 設定したルールの文字列 → 解析済みのパターン(照合のたびに解析し直さない。ルールの編集で使われなくなったものは上限で捨てる)
+    static COMPILED_PATTERNS: RefCell<LruCache<Rc<PathPattern>>> = RefCell::new(LruCache::new(COMPILED_MAX));
+    static COMPILED_REGEXES: RefCell<LruCache<Option<Rc<Regex>>>> = RefCell::new(LruCache::new(COMPILED_MAX));
+}
+
+pub enum PathPattern {
+    Exact(String),
+    Glob(String),
+    Regex(Regex),
+    Invalid, // 解釈できない正規表現(何にも一致しない)
+}
+
+impl PathPattern {
+    pub fn parse(s: &str) -> Self {
+        if let Some(re) = s.strip_prefix(REGEX_PREFIX) {
+            Regex::parse(re).map_or(Self::Invalid, Self::Regex)
+        } else if s.contains(['*', '?']) {
+            Self::Glob(s.to_string())
+        } else {
+            Self::Exact(s.to_string())
+        }
+    }
+
+    // ルールの照合用。文字列ごとに一度だけ解析する
+    pub fn compiled(s: &str) -> Rc<Self> {
+        COMPILED_PATTERNS.with(|c| {
+            if let Some(p) = c.borrow().get(s) { return p.clone() }
+            let p = Rc::new(Self::parse(s));
+            c.borrow_mut().insert(s.to_string(), p.clone());
+            p
+        })
+    }
+
+    // 文字列そのものだけに一致する指定(ワイルドカード・正規表現として解釈される文字列は正規表現でエスケープする)
+    pub fn literal(s: &str) -> String {
+        if Self::is_pattern(s) { format!("{}^{}$", REGEX_PREFIX, regex::escape(s)) } else { s.to_string() }
+    }
+
+    // 完全一致以外(ワイルドカード・正規表現)の指定か
+    pub fn is_pattern(s: &str) -> bool {
+        s.starts_with(REGEX_PREFIX) || s.contains(['*', '?'])
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Exact(s) => s.chars().map(fold_path).eq(text.chars().map(fold_path)),
+            Self::Glob(s) => glob_match(text, s),
+            Self::Regex(re) => re.is_match(text),
+            Self::Invalid => false,
+        }
+    }
+
+    // 複数のルールに一致した時の優先度(大きいほど具体的)。完全一致 > ワイルドカード > 正規表現、同種は固定文字が多い方
+    pub fn specificity(&self) -> (u8, usize) {
+        match self {
+            Self::Exact(s) => (3, s.chars().count()),
+            Self::Glob(s) => (2, s.chars().filter(|c| !matches!(c, '*' | '?')).count()),
+            Self::Regex(re) => (1, re.literal_len()),
+            Self::Invalid => (0, 0),
+        }
+    }
+
+    // 一致したパターンのうち最も具体的なもののindex(同じ優先度なら先のもの)
+    pub fn best_match<'a>(patterns: impl IntoIterator<Item = &'a str>, text: &str) -> Option<usize> {
+        let mut best: Option<(usize, (u8, usize))> = None;
+        for (idx, s) in patterns.into_iter().enumerate() {
+            let p = Self::compiled(s);
+            if !p.is_match(text) { continue }
+            let spec = p.specificity();
+            if best.is_none_or(|(_, b)| spec > b) { best = Some((idx, spec)); }
+        }
+        best.map(|(idx, _)| idx)
+    }
+}
+
+// 選択範囲(UTF-16位置)を*に置き換える。範囲が空ならキャレットを含むパスの区切り('\' '/')間を置き換える
+pub fn wildcard_segment(text: &str, start: usize, end: usize) -> String {
+    let t: Vec<u16> = text.encode_utf16().collect();
+    let (mut start, mut end) = (start.min(end).min(t.len()), end.max(start).min(t.len()));
+    if start == end {
+        let is_sep = |c: &u16| *c == '\\' as u16 || *c == '/' as u16;
+        start = t[..start].iter().rposition(is_sep).map_or(0, |i| i + 1);
+        end = t[end..].iter().position(is_sep).map_or(t.len(), |i| end + i);
+    }
+    let mut r = t[..start].to_vec();
+    r.push('*' as u16);
+    r.extend_from_slice(&t[end..]);
+    String::from_utf16_lossy(&r)
+}
+
+fn fold_path(c: char) -> char {
+    if c == '/' { return '\\' }
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// *と?のワイルドカード照合(パターン全体が一致すること)。*の位置を覚えておき、不一致で1文字ずつ伸ばして再試行する
+pub fn glob_match(text: &str, pattern: &str) -> bool {
+    let t: Vec<char> = text.chars().map(fold_path).collect();
+    let p: Vec<char> = pattern.chars().map(fold_path).collect();
+    let (mut ti, mut pi) = (0, 0);
+    let mut star: Option<(usize, usize)> = None; // (*の次のパターン位置, *に対応させたテキストの終端)
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            ti += 1;
+            pi += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi + 1, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// 正規表現(regexクレートで照合する。一致にかかる時間は文字列長に比例し、大文字小文字は区別しない)
+///   . [abc] [^a-z] \d \w \s \D \W \S ^ $ ( ) (?: ) | * + ? {m} {m,} {m,n}
+///   置換文字列では $0(一致全体) $1..$9(キャプチャ) $$($そのもの) を使える
+////////////////////////////////////////////////////////////////////////////////
+pub struct Regex { re: regex::Regex, literal_len: usize }
+
+impl Regex {
+    pub fn parse(s: &str) -> Option<Self> {
+        let re = regex::RegexBuilder::new(s).case_insensitive(true).build().ok()?;
+        // 固定文字数は大文字小文字を区別せずに解析すると文字クラスになってしまうので、そのまま解析し直して数える
+        let hir = regex_syntax::Parser::new().parse(s).ok()?;
+        Some(Self { re, literal_len: literal_len(&hir) })
+    }
+
+    // ルールの照合用。文字列ごとに一度だけ解析する(不正な正規表現はNone)
+    pub fn compiled(s: &str) -> Option<Rc<Self>> {
+        COMPILED_REGEXES.with(|c| {
+            if let Some(re) = c.borrow().get(s) { return re.clone() }
+            let re = Self::parse(s).map(Rc::new);
+            c.borrow_mut().insert(s.to_string(), re.clone());
+            re
+        })
+    }
+
+    // 部分一致(^ $で位置を固定できる)
+    pub fn is_match(&self, text: &str) -> bool {
+        self.re.is_match(text)
+    }
+
+    // 一致した箇所をすべて置換する(大文字小文字は元の文字列のまま残す)
+    pub fn replace_all(&self, text: &str, rep: &str) -> String {
+        let mut r = String::new();
+        let mut last = 0;
+        for caps in self.re.captures_iter(text) {
+            let Some(m) = caps.get(0) else { continue };
+            r.push_str(&text[last..m.start()]);
+            expand_replacement(&mut r, rep, &caps);
+            last = m.end();
+        }
+        r.push_str(&text[last..]);
+        r
+    }
+
+    fn literal_len(&self) -> usize {
+        self.literal_len
+    }
+}
+
+// 優先度の比較用に、選択肢のうち最も短い固定文字数を数える(入れ子の深さは解析時に制限されている)
+fn literal_len(hir: &regex_syntax::hir::Hir) -> usize {
+    use regex_syntax::hir::HirKind;
+    match hir.kind() {
+        HirKind::Literal(lit) => String::from_utf8_lossy(&lit.0).chars().count(),
+        HirKind::Capture(cap) => literal_len(&cap.sub),
+        HirKind::Repetition(rep) => literal_len(&rep.sub).saturating_mul(rep.min as usize),
+        HirKind::Concat(subs) => subs.iter().map(literal_len).fold(0, usize::saturating_add),
+        HirKind::Alternation(subs) => subs.iter().map(literal_len).min().unwrap_or(0),
+        HirKind::Empty | HirKind::Class(_) | HirKind::Look(_) => 0,
+    }
+}
+
+// $n をキャプチャに置き換えて追加する(一致していないキャプチャは空文字)
+fn expand_replacement(r: &mut String, rep: &str, caps: &regex::Captures) {
+    let mut it = rep.chars().peekable();
+    while let Some(ch) = it.next() {
+        if ch != '$' { r.push(ch); continue }
+        match it.peek().copied() {
+            Some('$') => { it.next(); r.push('$'); }
+            Some(d @ '0'..='9') => {
+                it.next();
+                if let Some(m) = caps.get(d as usize - '0' as usize) { r.push_str(m.as_str()); }
+            }
+            _ => r.push('$'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEAMS: &str = r"C:\Program Files\WindowsApps\MSTeams_24335.206.3315.1234_x64__8wekyb3d8bbwe\ms-teams.exe";
+
+    fn glob(text: &str, pattern: &str) -> bool {
+        PathPattern::parse(pattern).is_match(text)
+    }
+
+    fn re(text: &str, pattern: &str) -> bool {
+        PathPattern::parse(&format!("re:{}", pattern)).is_match(text)
+    }
+
+    #[test]
+    fn readme_msteams_example() {
+        assert!(glob(TEAMS, r"C:\Program Files\WindowsApps\MSTeams_*_x64__8wekyb3d8bbwe\ms-teams.exe"));
+        assert!(glob(&TEAMS.to_uppercase(), r"c:\program files\windowsapps\msteams_*_x64__8wekyb3d8bbwe\ms-teams.exe"));
+        assert!(!glob(TEAMS, r"C:\Program Files\WindowsApps\MSTeams_*_arm64__8wekyb3d8bbwe\ms-teams.exe"));
+        assert!(re(TEAMS, r"\\MSTeams_[\d.]+_x64__\w+\\ms-teams\.exe$"));
+    }
+
+    #[test]
+    fn glob_star_and_question() {
+        assert!(glob("abc", "*"));
+        assert!(glob("", "*"));
+        assert!(glob("abc", "a*"));
+        assert!(glob("abc", "*c"));
+        assert!(glob("abc", "a?c"));
+        assert!(glob("abc", "???"));
+        assert!(!glob("abc", "????"));
+        assert!(!glob("ab", "a?c"));
+        assert!(glob("abc", "a**c"));
+        assert!(!glob("", "?"));
+    }
+
+    #[test]
+    fn glob_is_anchored_at_both_ends() {
+        assert!(!glob("ab", "b*")); // 旧実装は先頭を固定していなかった
+        assert!(!glob("abx", "*b"));
+        assert!(!glob("xabc", "abc"));
+    }
+
+    #[test]
+    fn glob_repeated_literal_parts() {
+        // 旧実装は各部分を最後の出現位置で一致させていたため失敗していた
+        assert!(glob("aab", "a*a*b"));
+        assert!(glob(r"C:\app\bin\tool\bin\x.exe", r"C:\*\bin\*.exe"));
+        assert!(glob("mississippi", "*ss*ss*"));
+        assert!(glob("abab", "*ab"));
+        assert!(!glob("abac", "*ab"));
+    }
+
+    #[test]
+    fn windows_path_semantics() {
+        assert!(glob(r"C:\Tools\App.EXE", "c:/tools/app.exe"));
+        assert!(glob(r"C:\Tools\App.EXE", "C:/TOOLS/*.exe"));
+        assert!(!glob(r"C:\Tools\App.EXE", r"C:\Tools\App.exe.bak"));
+        assert!(PathPattern::parse(r"c:\tools\app.exe").is_match(r"C:\Tools\App.exe"));
+    }
+
+    #[test]
+    fn wildcard_from_selection() {
+        let p = r"C:\Program Files\WindowsApps\MSTeams_24335.206_x64__8wekyb3d8bbwe\ms-teams.exe";
+        let (s, e) = (p.find("24335").unwrap(), p.find("_x64").unwrap());
+        assert_eq!(wildcard_segment(p, s, e), r"C:\Program Files\WindowsApps\MSTeams_*_x64__8wekyb3d8bbwe\ms-teams.exe");
+        assert_eq!(wildcard_segment(p, e, s), wildcard_segment(p, s, e));
+        assert_eq!(wildcard_segment(p, s, s), r"C:\Program Files\WindowsApps\*\ms-teams.exe"); // キャレットのみは区切り間
+        assert_eq!(wildcard_segment("C:/a/b.exe", 10, 10), "C:/a/*");
+        assert_eq!(wildcard_segment("日本/語.exe", 3, 4), "日本/*.exe"); // UTF-16位置
+        assert_eq!(wildcard_segment("", 0, 0), "*");
+    }
+
+    #[test]
+    fn pattern_kind_detection() {
+        assert!(matches!(PathPattern::parse(r"C:\a.exe"), PathPattern::Exact(_)));
+        assert!(matches!(PathPattern::parse(r"C:\*.exe"), PathPattern::Glob(_)));
+        assert!(matches!(PathPattern::parse(r"C:\a?.exe"), PathPattern::Glob(_)));
+        assert!(matches!(PathPattern::parse(r"re:a.exe"), PathPattern::Regex(_)));
+        assert!(matches!(PathPattern::parse(r"re:(a"), PathPattern::Invalid));
+        assert!(PathPattern::is_pattern("re:x") && PathPattern::is_pattern("a*") && !PathPattern::is_pattern(r"C:\a.exe"));
+        assert!(!PathPattern::parse("re:(").is_match("("));
+    }
+
+    #[test]
+    fn literal_matches_only_itself() {
+        assert_eq!(PathPattern::literal("Inbox - Mail"), "Inbox - Mail");
+        for s in ["re: Budget (draft)", "*.txt - Notepad", "Why?", "a+b [1]"] {
+            let p = PathPattern::parse(&PathPattern::literal(s));
+            assert!(p.is_match(s), "{}", s);
+            assert!(!p.is_match(&format!("{}x", s)) && !p.is_match(&s[1..]), "{}", s);
+        }
+        assert!(!PathPattern::parse(&PathPattern::literal("*.txt")).is_match("a.txt"));
+    }
+
+    #[test]
+    fn regex_basics() {
+        assert!(re("chrome.exe", "chrome"));
+        assert!(re("CHROME.EXE", r"chrome\.exe"));
+        assert!(!re("chromeXexe", r"chrome\.exe"));
+        assert!(re("chromeXexe", r"chrome.exe"));
+        assert!(re("abc", "^abc$"));
+        assert!(!re("xabc", "^abc"));
+        assert!(!re("abcx", "abc$"));
+        assert!(re(r"C:\x\msedge.exe", r"\\(chrome|msedge)\.exe$"));
+        assert!(!re(r"C:\x\firefox.exe", r"\\(chrome|msedge)\.exe$"));
+        assert!(re("b", "a|b"));
+        assert!(re("abd", "a(?:b|c)d"));
+    }
+
+    #[test]
+    fn regex_quantifiers() {
+        assert!(re("ac", "^ab*c$"));
+        assert!(re("abbbc", "^ab*c$"));
+        assert!(!re("ac", "^ab+c$"));
+        assert!(re("abc", "^ab?c$"));
+        assert!(!re("abbc", "^ab?c$"));
+        assert!(re("aaa", "^a{3}$"));
+        assert!(!re("aa", "^a{3}$"));
+        assert!(re("aaaa", "^a{2,}$"));
+        assert!(!re("aaaa", "^a{1,3}$"));
+        assert!(re("ab", "^(a|ab)(c|bcd)?$")); // 後戻りが必要
+        assert!(re("aaa", "^(a*)*$")); // 空一致の繰り返しで止まらない
+        assert!(re("xyz", "^.*z$"));
+    }
+
+    #[test]
+    fn regex_classes() {
+        assert!(re("v12", r"^v\d+$"));
+        assert!(!re("vx", r"^v\d+$"));
+        assert!(re("A_1", r"^\w+$"));
+        assert!(re("a b", r"a\sb"));
+        assert!(re("x", "^[a-z]$"));
+        assert!(re("X", "^[a-z]$")); // 大文字小文字は区別しない
+        assert!(!re("5", "^[a-z]$"));
+        assert!(re("5", "^[^a-z]$"));
+        assert!(re("-", "^[a-]$"));
+        assert!(re("]", "^[]]$"));
+        assert!(re("3", r"^[\d]$"));
+        assert!(re(".", r"^[.]$"));
+    }
+
+    #[test]
+    fn regex_invalid_syntax() {
+        for p in ["(a", "a)", "*a", "a{2,1}", "[a", "[z-a]", r"a\", "a{x}"] {
+            assert!(Regex::parse(p).is_none(), "{}", p);
+        }
+    }
+
+    #[test]
+    fn regex_pathological_patterns() {
+        // 後戻りで指数時間になる・深く再帰するパターンでも止まる
+        let long = "a".repeat(5000);
+        assert!(!re(&long, "^(a|a)*b$"));
+        assert!(!re(&long, "^(a*)*b$"));
+        assert!(Regex::parse(&"(".repeat(100000)).is_none());
+        assert!(Regex::parse(&format!("{}a{}", "(?:".repeat(100000), ")".repeat(100000))).is_none());
+    }
+
+    #[test]
+    fn regex_compiled_once() {
+        let a = PathPattern::compiled(r"re:\\x\.exe$");
+        assert!(Rc::ptr_eq(&a, &PathPattern::compiled(r"re:\\x\.exe$")));
+        assert!(a.is_match(r"C:\X.EXE"));
+        assert!(Regex::compiled("(a").is_none());
+    }
+
+    #[test]
+    fn compiled_cache_is_bounded() {
+        let a = PathPattern::compiled("re:^bounded$");
+        for i in 0..COMPILED_MAX {
+            PathPattern::compiled(&format!("re:^other{}$", i));
+        }
+        assert!(!Rc::ptr_eq(&a, &PathPattern::compiled("re:^bounded$"))); // 使われなくなったものは捨てて解析し直す
+    }
+
+    #[test]
+    fn regex_replace() {
+        let rep = |text: &str, pattern: &str, r: &str| Regex::parse(pattern).unwrap().replace_all(text, r);
+        assert_eq!(rep("Doc - Google Chrome", r" - Google Chrome$", ""), "Doc");
+        assert_eq!(rep("a1b22c", r"\d+", "#"), "a#b#c");
+        assert_eq!(rep("Main.rs - Proj", r"^(.*) - (.*)$", "$2: $1"), "Proj: Main.rs");
+        assert_eq!(rep("ABC", "b", "[$0]"), "A[B]C"); // 一致箇所は元の大文字のまま
+        assert_eq!(rep("abc", "x*", "-"), "-a-b-c-"); // 空一致
+        assert_eq!(rep("ab", "(x)?b", "<$1>"), "a<>");
+        assert_eq!(rep("a", "a", "$$1"), "$1");
+        assert_eq!(rep("ab", "(?:a)(b)", "$1"), "b");
+        assert_eq!(rep("aXbX", "(a|b)x", "$1"), "ab");
+    }
+
+    #[test]
+    fn specificity_prefers_most_specific_rule() {
+        let rules = [r"re:ms-teams\.exe$", r"C:\Program Files\*", r"C:\Program Files\WindowsApps\MSTeams_*\ms-teams.exe", TEAMS];
+        assert_eq!(PathPattern::best_match(rules, TEAMS), Some(3));
+        assert_eq!(PathPattern::best_match(rules[..3].iter().copied(), TEAMS), Some(2));
+        assert_eq!(PathPattern::best_match([rules[0], rules[1]], TEAMS), Some(1));
+        assert_eq!(PathPattern::best_match([rules[0]], TEAMS), Some(0));
+        assert_eq!(PathPattern::best_match([r"D:\*"], TEAMS), None);
+    }
+
+    #[test]
+    fn specificity_tie_keeps_list_order() {
+        assert_eq!(PathPattern::best_match(["a*c", "*bc"], "abc"), Some(0));
+        assert_eq!(PathPattern::best_match(["re:a", "re:c"], "abc"), Some(0));
+        assert_eq!(PathPattern::best_match(["re:b", "re:abc"], "abc"), Some(1));
+    }
+}
@@ -1,6 +1,8 @@
-use windows::Win32::System::Registry::*;
+use fxhash::FxHashSet;
+use windows::Win32::System::{Registry::*, Com::CoTaskMemFree};
 
 use super::*;
+use lib_pattern::{PathPattern, Regex};
 
 const REGKEY_APP_PARAM: &str = if cfg!(debug_assertions) {
     r"SOFTWARE\myprogram\mytaskbar_D"
@@ -13,30 +15,340 @@ const REGKEY_FILE_LIST_ORDER: &str = if cfg!(debug_assertions) {
     r"SOFTWARE\myprogram\mytaskbar_flo"
 };
 
+// フォルダ設定のキーを既知フォルダ基準にする対象(FOLDERID)
+const FILESORT_KNOWN_FOLDERS: [GUID; 14] = [
+    FOLDERID_Desktop, FOLDERID_Documents, FOLDERID_Downloads, FOLDERID_Music, FOLDERID_Pictures, FOLDERID_Videos,
+    FOLDERID_Favorites, FOLDERID_SkyDrive, FOLDERID_Profile, FOLDERID_RoamingAppData, FOLDERID_LocalAppData,
+    FOLDERID_StartMenu, FOLDERID_ProgramFiles, FOLDERID_Public,
+];
+
+thread_local! {
+    // ("{FOLDERID}", 現在のパス)。長いパスを先に照合する
+    static KNOWN_FOLDERS: Vec<(String, String)> = {
+        let mut v = FILESORT_KNOWN_FOLDERS.iter().filter_map(|id| {
+            let p = unsafe { SHGetKnownFolderPath(id, KNOWN_FOLDER_FLAG(0), None) }.ok()?;
+            let path = unsafe { p.to_string() };
+            unsafe { CoTaskMemFree(Some(p.0 as _)); }
+            Some((format!("{{{:?}}}", id), path.ok()?))
+        }).collect::<Vec<_>>();
+        v.sort_by_key(|(_, path)| std::cmp::Reverse(path.len()));
+        v
+    };
+}
+
 const REG_NOTIFY_ICON: &str = "notify_icon";
 const REG_WIN_SORT: &str = "win_sort";
+const REG_WIN_FILTER: &str = "win_filter";
+const REG_PROC_HISTORY: &str = "proc_history";
+const REG_TITLE_RULE: &str = "title_rule";
+const REG_WIN_SLOT: &str = "win_slot";
+
+const PROC_HISTORY_MAX: usize = 256; // 保存する履歴の上限(古いものから捨てる)
+pub const PROC_HISTORY_DAYS_DEFAULT: u64 = 90;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
 
 const HOTKEY_PARAM_TASK: &str = "TASK";
+const HOTKEY_PARAM_SLOT: &str = "SLOT";
+const HOTKEY_PARAM_RAISE: &str = "RAISE";
+const HOTKEY_PARAM_CYCLE: &str = "CYCLE";
+const HOTKEY_PARAM_LAST: &str = "LAST";
 const HOTKEY_PARAM_FILELIST: &str = "LIST";
 const HOTKEY_PARAM_FILEICON: &str = "ICON";
 const HOTKEY_PARAM_ICON_SM: &str = "SM";
 const HOTKEY_PARAM_ICON_LG: &str = "LG";
 const HOTKEY_PARAM_SYSHIDE_T: &str = "HIDE";
 const HOTKEY_PARAM_SYSHIDE_F: &str = "SHOW";
+const HOTKEY_PARAM_ATTENTION_TOP: &str = "ATTN";
+const HOTKEY_PARAM_PROC_LINE: &str = "PROC";
+const HOTKEY_PARAM_SORT_DIRS_FIRST: &str = "DIR";
+const HOTKEY_PARAM_SORT_DIRS_MIXED: &str = "MIX";
+const HOTKEY_PARAM_SORT_PROFILE: &str = "PRF:";
+const HOTKEY_PARAM_INHERIT_VIEW: &str = "INHT";
+const FILESORT_PROFILE_SEP: char = '|'; // パスに使えない文字でフォルダ設定のキーとプロファイル名を区切る
+const WINFILTER_INCLUDE: &str = "INC";
+const WINFILTER_EXCLUDE: &str = "EXC";
+const WINFILTER_STYLE_TOOL: &str = "TOOL";
+const WINFILTER_STYLE_OWNED: &str = "OWNED";
+const WINFILTER_STYLE_TOPMOST: &str = "TOPMOST";
+const TITLE_ACTION_NAMES: [(TitleAction, &str); 4] = [
+    (TitleAction::StripSuffix, "SUFFIX"), (TitleAction::StripPrefix, "PREFIX"),
+    (TitleAction::Replace, "REPLACE"), (TitleAction::Swap, "SWAP"),
+];
 const HKMOD_CHAR_ALT: char = 'A';
 const HKMOD_CHAR_SHIFT: char = 'S';
 const HKMOD_CHAR_NONE: char = 'N';
 
-#[derive(Default, PartialEq)]
-pub enum HotkeyType { IconLauncher, #[default] ListLauncher, WinTaskList, }
+#[derive(Default, PartialEq, Clone)]
+pub enum HotkeyType { IconLauncher, #[default] ListLauncher, WinTaskList, WinSlot, RunOrRaise, CycleApp, ToggleLast, }
+
+// ランチャーの並び順。Manualは保存済みの並び(ミドルドラッグで変更)、保存がなければシェルの列挙順
+#[derive(Default, PartialEq, Clone, Copy, Debug)]
+pub enum SortMode { #[default] Manual, Name, NaturalName, Type, Modified, }
+
+impl SortMode {
+    pub const ALL: [SortMode; 5] = [SortMode::Manual, SortMode::Name, SortMode::NaturalName, SortMode::Type, SortMode::Modified];
+
+    fn param(&self) -> &'static str {
+        match self {
+            SortMode::Manual => "MANU",
+            SortMode::Name => "NAME",
+            SortMode::NaturalName => "NATU",
+            SortMode::Type => "TYPE",
+            SortMode::Modified => "DATE",
+        }
+    }
+}
+
+// ウィンドウリストの表示対象ルール。空の項目と未指定(None)のスタイルは条件にしない
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct WinFilterRule {
+    pub b_include: bool, // true:既定で除外されるウィンドウも表示する, false:表示しない
+    pub class: String, // ウィンドウクラス名(ワイルドカード)
+    pub title: String, // タイトル(ワイルドカード)
+    pub proc_img: String, // プロセスイメージ(ワイルドカード)
+    pub tool: Option<bool>, // ツールウィンドウ(WS_EX_TOOLWINDOW)
+    pub owned: Option<bool>, // オーナーウィンドウあり
+    pub topmost: Option<bool>, // 最前面(WS_EX_TOPMOST)
+}
+
+// ルールの照合に使うウィンドウの属性
+pub struct WinFilterTarget<'a> {
+    pub class: &'a str,
+    pub title: &'a str,
+    pub proc_img: &'a str,
+    pub b_tool: bool,
+    pub b_owned: bool,
+    pub b_topmost: bool,
+}
+
+impl WinFilterRule {
+    pub fn matches(&self, t: &WinFilterTarget) -> bool {
+        let text = |pattern: &str, s: &str| pattern.is_empty() || PathPattern::compiled(pattern).is_match(s);
+        let style = |cond: Option<bool>, b: bool| cond != Some(!b);
+        text(&self.class, t.class) && text(&self.title, t.title) && text(&self.proc_img, t.proc_img)
+            && style(self.tool, t.b_tool) && style(self.owned, t.b_owned) && style(self.topmost, t.b_topmost)
+    }
+
+    // 先頭から照合して最初に一致したルールに従う(Some(true):表示, Some(false):除外, None:既定の判定)
+    pub fn verdict(rules: &[WinFilterRule], t: &WinFilterTarget) -> Option<bool> {
+        rules.iter().find(|r| r.matches(t)).map(|r| r.b_include)
+    }
+
+    // "INC|EXC<TAB>クラス<TAB>タイトル<TAB>プロセス<TAB>スタイル(例 "TOOL !OWNED")"
+    pub fn parse_string(l: &str) -> Option<Self> {
+        let p: Vec<&str> = l.split('\t').collect();
+        if p.len() != 5 { return None }
+        let b_include =
+            if p[0].eq(WINFILTER_INCLUDE) { true }
+            else if p[0].eq(WINFILTER_EXCLUDE) { false }
+            else { return None };
+        let mut v = Self { b_include, class: p[1].to_string(), title: p[2].to_string(), proc_img: p[3].to_string(), ..Default::default() };
+        for token in p[4].split_whitespace() {
+            let (name, b) = match token.strip_prefix('!') { Some(n) => (n, false), None => (token, true) };
+            if name.eq(WINFILTER_STYLE_TOOL) { v.tool = Some(b); }
+            else if name.eq(WINFILTER_STYLE_OWNED) { v.owned = Some(b); }
+            else if name.eq(WINFILTER_STYLE_TOPMOST) { v.topmost = Some(b); }
+            else { return None }
+        }
+        Some(v)
+    }
+
+    pub fn style_string(&self) -> String {
+        [(self.tool, WINFILTER_STYLE_TOOL), (self.owned, WINFILTER_STYLE_OWNED), (self.topmost, WINFILTER_STYLE_TOPMOST)].iter()
+            .filter_map(|(c, name)| c.map(|b| format!("{}{}", if b { "" } else { "!" }, name)))
+            .collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}", if self.b_include { WINFILTER_INCLUDE } else { WINFILTER_EXCLUDE },
+            self.class, self.title, self.proc_img, self.style_string())
+    }
+}
+
+// ウィンドウリストのタイトル書き換え
+#[derive(Default, PartialEq, Clone, Copy, Debug)]
+pub enum TitleAction {
+    #[default] StripSuffix, // 末尾のfindを除く
+    StripPrefix, // 先頭のfindを除く
+    Replace, // 正規表現findをreplaceに置換($1..$9でキャプチャを参照)
+    Swap, // findで区切った部分を逆順に並べる(replaceが空でなければ区切りをreplaceにする)
+}
+
+// 対象プロセスのタイトル書き換えルール。proc_imgが空なら全プロセスが対象
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct TitleRule {
+    pub action: TitleAction,
+    pub proc_img: String, // プロセスイメージまたはAppUserModelID(ワイルドカード・正規表現)
+    pub find: String,
+    pub replace: String,
+}
+
+impl TitleRule {
+    pub fn matches_proc(&self, proc_img: &str, aumid: &str) -> bool {
+        if self.proc_img.is_empty() { return true }
+        let pattern = PathPattern::compiled(&self.proc_img);
+        pattern.is_match(proc_img) || (!aumid.is_empty() && pattern.is_match(aumid))
+    }
+
+    // 1つのルールを適用する(findが空・正規表現が不正なら何もしない)
+    pub fn apply(&self, title: &str) -> String {
+        if self.find.is_empty() { return title.to_string() }
+        match self.action {
+            TitleAction::StripSuffix => title.strip_suffix(self.find.as_str()).unwrap_or(title).to_string(),
+            TitleAction::StripPrefix => title.strip_prefix(self.find.as_str()).unwrap_or(title).to_string(),
+            TitleAction::Replace => Regex::compiled(&self.find).map_or_else(|| title.to_string(), |re| re.replace_all(title, &self.replace)),
+            TitleAction::Swap => {
+                let sep = if self.replace.is_empty() { &self.find } else { &self.replace };
+                let mut parts = title.split(self.find.as_str()).collect::<Vec<_>>();
+                parts.reverse();
+                parts.join(sep)
+            }
+        }
+    }
+
+    // 対象のルールを先頭から順に適用する。結果が空になる場合は元のタイトルを使う
+    pub fn rewrite(rules: &[TitleRule], proc_img: &str, aumid: &str, title: &str) -> String {
+        let r = rules.iter().filter(|r| r.matches_proc(proc_img, aumid))
+            .fold(title.to_string(), |t, r| r.apply(&t));
+        let r = r.trim();
+        if r.is_empty() { title.to_string() } else { r.to_string() }
+    }
+
+    // "SUFFIX|PREFIX|REPLACE|SWAP<TAB>プロセス<TAB>検索<TAB>置換"
+    pub fn parse_string(l: &str) -> Option<Self> {
+        let p: Vec<&str> = l.split('\t').collect();
+        if p.len() != 4 { return None }
+        let action = TITLE_ACTION_NAMES.iter().find(|(_, name)| p[0].eq(*name))?.0;
+        Some(Self { action, proc_img: p[1].to_string(), find: p[2].to_string(), replace: p[3].to_string() })
+    }
+
+    pub fn action_name(&self) -> &'static str {
+        TITLE_ACTION_NAMES.iter().find(|(a, _)| *a == self.action).map_or("", |(_, name)| name)
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("{}\t{}\t{}\t{}", self.action_name(), self.proc_img, self.find, self.replace)
+    }
+}
+
+// ウィンドウリストで見かけたプロセスの履歴(起動していないプロセスもソート設定の候補にする)
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ProcHistory {
+    pub proc_img: String,
+    pub last_seen: u64, // 最後に見かけた時刻(UNIX時間・秒)
+    pub icon_path: String, // 起動していない時のアイコン取得元(exeのパス、UWPはshell:AppsFolder\AUMID)
+}
+
+impl ProcHistory {
+    pub fn now() -> u64 {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+
+    // 見かけたプロセスの時刻を更新する。新規に追加した場合はtrue
+    pub fn touch(hist: &mut Vec<Self>, proc_img: &str, now: u64) -> bool {
+        if let Some(h) = hist.iter_mut().find(|h| h.proc_img == proc_img) {
+            h.last_seen = h.last_seen.max(now);
+            return false
+        }
+        hist.push(Self { proc_img: proc_img.to_string(), last_seen: now, icon_path: proc_img.to_string() });
+        true
+    }
+
+    pub fn days_ago(&self, now: u64) -> u64 {
+        now.saturating_sub(self.last_seen) / SECS_PER_DAY
+    }
+
+    // max_days日以上見かけていないもの、ファイルが無くなったものを削除する。削除数を返す
+    pub fn prune(hist: &mut Vec<Self>, now: u64, max_days: u64, exists: impl Fn(&str) -> bool) -> usize {
+        let before = hist.len();
+        hist.retain(|h| h.days_ago(now) < max_days && exists(&h.proc_img));
+        before - hist.len()
+    }
+
+    // 新しい順にmax件まで
+    fn newest(hist: &[Self], max: usize) -> Vec<Self> {
+        let mut v = hist.to_vec();
+        v.sort_by_key(|h| std::cmp::Reverse(h.last_seen));
+        v.truncate(max);
+        v
+    }
+
+    // "パス<TAB>最終時刻<TAB>アイコン取得元"
+    pub fn parse_string(l: &str) -> Option<Self> {
+        let p: Vec<&str> = l.split('\t').collect();
+        if p.len() != 3 || p[0].is_empty() { return None }
+        Some(Self { proc_img: p[0].to_string(), last_seen: p[1].parse().ok()?, icon_path: p[2].to_string() })
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("{}\t{}\t{}", self.proc_img, self.last_seen, self.icon_path)
+    }
+}
+
+// ウィンドウのクイック切替スロット(1..=9)。プロセスイメージとタイトルで次回以降も同じウィンドウを割り当てる
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct WinSlot {
+    pub slot: u32,
+    pub proc_img: String,
+    pub title: String, // タイトルの指定(割り当て時はそのままのタイトル。設定画面でワイルドカード・正規表現に書き換えられる)。空なら同じプロセスの最初のウィンドウ
+    pub hwnd: isize, // 実行中に割り当てているウィンドウ(保存しない)
+}
+
+impl WinSlot {
+    pub const MAX: u32 = 9;
+
+    pub fn matches_proc(&self, proc_img: &str) -> bool {
+        PathPattern::compiled(&self.proc_img).is_match(proc_img)
+    }
+
+    pub fn matches_title(&self, title: &str) -> bool {
+        self.title.is_empty() || PathPattern::compiled(&self.title).is_match(title)
+    }
+
+    // スロットにウィンドウを割り当てる。同じスロット・同じウィンドウの以前の割り当ては外す
+    // タイトルは*や?・"re:"を含んでいてもパターンとして扱わないよう、文字どおりに一致する指定にする
+    pub fn bind(slots: &mut Vec<Self>, slot: u32, proc_img: &str, title: &str, hwnd: isize) {
+        slots.retain(|s| s.slot != slot && s.hwnd != hwnd);
+        slots.push(Self { slot, proc_img: proc_img.to_string(), title: PathPattern::literal(title), hwnd });
+        slots.sort_by_key(|s| s.slot);
+    }
+
+    // ウィンドウの割り当てを外す。外した場合はtrue
+    pub fn unbind(slots: &mut Vec<Self>, hwnd: isize) -> bool {
+        let before = slots.len();
+        slots.retain(|s| s.hwnd != hwnd);
+        before != slots.len()
+    }
+
+    // "番号<TAB>プロセス<TAB>タイトル"
+    pub fn parse_string(l: &str) -> Option<Self> {
+        let p: Vec<&str> = l.split('\t').collect();
+        if p.len() != 3 || p[1].is_empty() { return None }
+        let slot = p[0].parse::<u32>().ok().filter(|n| (1..=Self::MAX).contains(n))?;
+        Some(Self { slot, proc_img: p[1].to_string(), title: p[2].to_string(), hwnd: 0 })
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("{}\t{}\t{}", self.slot, self.proc_img, self.title)
+    }
+}
 
+#[derive(Clone)]
 pub struct PropertyHolder {
     pub hotkey_type: HotkeyType,
     pub b_icon_large: bool,
     pub w: u32,
     pub h: u32,
     pub b_sysfile_hidden: bool,
-    pub path: String,
+    pub path: String, // ランチャー：フォルダ、起動か切替：プログラム
+    pub b_attention_top: bool, // ウィンドウリスト：通知中のウィンドウを先頭に表示
+    pub b_proc_line: bool, // ウィンドウリスト：2行目にプロセス名を表示
+    pub slot: u32, // スロット切替：切り替えるスロットの番号
+    pub sort_mode: SortMode, // ランチャー：並び順
+    pub b_folders_first: bool, // ランチャー：フォルダを先に並べる
+    pub b_sort_set: bool, // ランチャー：並び順の項目あり(ホットキーではフォルダの設定より優先する)
+    pub sort_profile: String, // ランチャー：手動の並び順を保存する名前(空ならフォルダ共通)
+    pub b_inherit_view: bool, // ランチャー：開いたサブフォルダにも表示設定を適用する
 }
 
 impl Default for PropertyHolder {
@@ -48,6 +360,14 @@ impl Default for PropertyHolder {
             h: u32::default(),
             b_sysfile_hidden: true,
             path: String::default(),
+            b_attention_top: false,
+            b_proc_line: false,
+            slot: 0,
+            sort_mode: SortMode::default(),
+            b_folders_first: true,
+            b_sort_set: false,
+            sort_profile: String::default(),
+            b_inherit_view: false,
         }
     }
 }
@@ -61,22 +381,51 @@ impl PropertyHolder {
             h: h,
             b_sysfile_hidden: b_sysfile_hidden,
             path: path,
+            ..Default::default()
         }
     }
 
     pub fn parse_string(l: &str) -> Self {
         let p: Vec<&str> = l.split(',').collect();
 
-        // ^T,([0-9]+),([0-9]+),$
+        // ^TASK,([0-9]+),([0-9]+),(ATTN|)(,PROC|)$
         if let Some(v) = (|| {
-            if p.len() != 4 { return None }
+            if p.len() != 4 && p.len() != 5 { return None }
             if !p[0].eq(HOTKEY_PARAM_TASK) { return None }
             let w = p[1].parse::<u32>().ok()?;
             let h = p[2].parse::<u32>().ok()?;
-            Some(Self { hotkey_type: HotkeyType::WinTaskList, w: w, h: h, ..Default::default() })
+            let b_attention_top =
+                if p[3].eq(HOTKEY_PARAM_ATTENTION_TOP) { true }
+                else if p[3].is_empty() { false }
+                else { return None };
+            let b_proc_line =
+                if p.len() == 4 { false }
+                else if p[4].eq(HOTKEY_PARAM_PROC_LINE) { true }
+                else { return None };
+            Some(Self { hotkey_type: HotkeyType::WinTaskList, w: w, h: h, b_attention_top, b_proc_line, ..Default::default() })
         })() { return v };
 
-        // ^L,([0-9]+),([0-9]+),(T|F)(,(.*)|$)
+        // ^SLOT,([1-9])$
+        if let Some(v) = (|| {
+            if p.len() != 2 { return None }
+            if !p[0].eq(HOTKEY_PARAM_SLOT) { return None }
+            let slot = p[1].parse::<u32>().ok().filter(|n| (1..=WinSlot::MAX).contains(n))?;
+            Some(Self { hotkey_type: HotkeyType::WinSlot, slot, ..Default::default() })
+        })() { return v };
+
+        // ^RAISE,(.+)$
+        if let Some(v) = (|| {
+            if p.len() < 2 || !p[0].eq(HOTKEY_PARAM_RAISE) { return None }
+            let path = p[1..].join(","); // pathの中にカンマを含んでいる場合の対処
+            if path.is_empty() { return None }
+            Some(Self { hotkey_type: HotkeyType::RunOrRaise, path, ..Default::default() })
+        })() { return v };
+
+        // ^CYCLE$, ^LAST$
+        if p.len() == 1 && p[0].eq(HOTKEY_PARAM_CYCLE) { return Self { hotkey_type: HotkeyType::CycleApp, ..Default::default() } }
+        if p.len() == 1 && p[0].eq(HOTKEY_PARAM_LAST) { return Self { hotkey_type: HotkeyType::ToggleLast, ..Default::default() } }
+
+        // ^L,([0-9]+),([0-9]+),(T|F)(,SORT|,PRF:name|,INHT)*(,(.*)|$)
         if let Some(v) = (|| {
             if p.len() < 4 { return None }
             if !p[0].eq(HOTKEY_PARAM_FILELIST) { return None }
@@ -86,14 +435,15 @@ impl PropertyHolder {
                 if p[3].eq(HOTKEY_PARAM_SYSHIDE_T) { true }
                 else if p[3].eq(HOTKEY_PARAM_SYSHIDE_F) { false }
                 else { return None };
-            let mut path = String::new();
-            if p.len() > 4 { // pathの中にカンマを含んでいる場合の対処
-                for i in 4..=p.len() - 1 { path += p[i]; }
+            let mut v = Self { hotkey_type: HotkeyType::ListLauncher, w: w, h: h, b_sysfile_hidden: b_syshide, ..Default::default() };
+            let idx_path = 4 + v.parse_launcher_options(&p[4..]);
+            if p.len() > idx_path { // pathの中にカンマを含んでいる場合の対処
+                for i in idx_path..=p.len() - 1 { v.path += p[i]; }
             }
-            Some(Self { hotkey_type: HotkeyType::ListLauncher, w: w, h: h, b_sysfile_hidden: b_syshide, path: path, ..Default::default() })
+            Some(v)
         })() { return v };
 
-        // ^I,(S|L),([0-9]+),([0-9]+),(T|F)(,(.*)|$)
+        // ^I,(S|L),([0-9]+),([0-9]+),(T|F)(,SORT|,PRF:name|,INHT)*(,(.*)|$)
         if let Some(v) = (|| {
             if p.len() < 5 { return None }
             if !p[0].eq(HOTKEY_PARAM_FILEICON) { return None }
@@ -107,17 +457,92 @@ impl PropertyHolder {
                 if p[4].eq(HOTKEY_PARAM_SYSHIDE_T) { true }
                 else if p[4].eq(HOTKEY_PARAM_SYSHIDE_F) { false }
                 else { return None };
-            let mut path = String::new();
-            if p.len() > 5 { // pathの中にカンマを含んでいる場合の対処
-                for i in 5..=p.len() - 1 { path += p[i]; }
+            let mut v = Self { hotkey_type: HotkeyType::IconLauncher, b_icon_large: b_large, w: w, h: h, b_sysfile_hidden: b_syshide, ..Default::default() };
+            let idx_path = 5 + v.parse_launcher_options(&p[5..]);
+            if p.len() > idx_path { // pathの中にカンマを含んでいる場合の対処
+                for i in idx_path..=p.len() - 1 { v.path += p[i]; }
             }
-            Some(Self { hotkey_type: HotkeyType::IconLauncher, b_icon_large: b_large, w: w, h: h, b_sysfile_hidden: b_syshide, path: path })
+            Some(v)
         })() { return v };
 
         Self::default()
     }
 
+    // パスの前に並ぶ省略可能な項目(並び順, プロファイル, サブフォルダへの適用)を読み、読んだ項目数を返す
+    fn parse_launcher_options(&mut self, fields: &[&str]) -> usize {
+        let mut n = 0;
+        for f in fields {
+            if let Some((m, b)) = Self::parse_sort_param(f) {
+                (self.sort_mode, self.b_folders_first, self.b_sort_set) = (m, b, true);
+            } else if let Some(name) = f.strip_prefix(HOTKEY_PARAM_SORT_PROFILE) {
+                self.sort_profile = name.to_string();
+            } else if f.eq(&HOTKEY_PARAM_INHERIT_VIEW) {
+                self.b_inherit_view = true;
+            } else {
+                break
+            }
+            n += 1;
+        }
+        n
+    }
+
+    // 表示設定を ホットキー > フォルダ > 既定値 の順で決める。
+    // 並び順は ホットキーの項目 > プロファイル別の保存 > フォルダ の順、プロファイルとサブフォルダへの適用は常にホットキーに従う
+    pub fn resolve(hotkey: Option<&Self>, folder_param: &str, profile_param: &str) -> Self {
+        let mut v = if folder_param.is_empty() { Self::default() } else { Self::parse_string(folder_param) };
+        let Some(hk) = hotkey else { return v };
+        if !hk.sort_profile.is_empty() && !profile_param.is_empty() { // 手動で並べ替えたプロファイルはフォルダの並び順に戻さない
+            let p = Self::parse_string(profile_param);
+            (v.sort_mode, v.b_folders_first) = (p.sort_mode, p.b_folders_first);
+        }
+        v.hotkey_type = hk.hotkey_type.clone();
+        v.b_icon_large = hk.b_icon_large;
+        (v.w, v.h) = (hk.w, hk.h);
+        v.b_sysfile_hidden = hk.b_sysfile_hidden;
+        if hk.b_sort_set {
+            (v.sort_mode, v.b_folders_first, v.b_sort_set) = (hk.sort_mode, hk.b_folders_first, true);
+        }
+        v.sort_profile = hk.sort_profile.clone();
+        v.b_inherit_view = hk.b_inherit_view;
+        v
+    }
+
+    // ホットキーが手動以外の並び順を指定している(手動で並べ替えても次の列挙で戻るため並べ替えさせない)
+    pub fn sort_fixed_by(hotkey: Option<&Self>) -> bool {
+        hotkey.is_some_and(|h| h.b_sort_set && h.sort_mode != SortMode::Manual)
+    }
+
+    // プロファイル別の並び順を保存する名前(プロファイルがなければフォルダのパスそのもの)
+    pub fn profile_path(path: &str, profile: &str) -> String {
+        if profile.is_empty() { return path.to_string() }
+        format!("{}{}{}", path, FILESORT_PROFILE_SEP, profile)
+    }
+
+    // profile_pathで作った名前を(フォルダのパス, プロファイル名)に分ける
+    pub fn split_profile_path(path: &str) -> (&str, &str) {
+        path.split_once(FILESORT_PROFILE_SEP).unwrap_or((path, ""))
+    }
+
+    // 並び順の項目(例 "NATU/DIR")。一致しなければパスとして扱う
+    fn parse_sort_param(s: &str) -> Option<(SortMode, bool)> {
+        let (mode, dirs) = s.split_once('/')?;
+        let mode = SortMode::ALL.into_iter().find(|m| m.param().eq(mode))?;
+        let b_folders_first =
+            if dirs.eq(HOTKEY_PARAM_SORT_DIRS_FIRST) { true }
+            else if dirs.eq(HOTKEY_PARAM_SORT_DIRS_MIXED) { false }
+            else { return None };
+        Some((mode, b_folders_first))
+    }
+
     pub fn to_string(&self) -> String {
+        match self.hotkey_type {
+            HotkeyType::WinSlot => return format!("{},{}", HOTKEY_PARAM_SLOT, self.slot),
+            HotkeyType::RunOrRaise => return format!("{},{}", HOTKEY_PARAM_RAISE, self.path),
+            HotkeyType::CycleApp => return HOTKEY_PARAM_CYCLE.to_string(),
+            HotkeyType::ToggleLast => return HOTKEY_PARAM_LAST.to_string(),
+            _ => { }
+        }
+
         let mut paramstr = String::default();
         if self.hotkey_type == HotkeyType::IconLauncher {
             paramstr += HOTKEY_PARAM_FILEICON;
@@ -140,6 +565,8 @@ impl PropertyHolder {
         paramstr += &(self.h.to_string() + ",");
 
         if self.hotkey_type == HotkeyType::WinTaskList {
+            if self.b_attention_top { paramstr += HOTKEY_PARAM_ATTENTION_TOP; }
+            if self.b_proc_line { paramstr += ","; paramstr += HOTKEY_PARAM_PROC_LINE; }
             return paramstr
         }
 
@@ -149,6 +576,17 @@ impl PropertyHolder {
             paramstr += HOTKEY_PARAM_SYSHIDE_F;
         }
 
+        if self.b_sort_set || self.sort_mode != SortMode::default() || !self.b_folders_first { // 既定値は従来の形式のまま
+            paramstr += &format!(",{}/{}", self.sort_mode.param(),
+                if self.b_folders_first { HOTKEY_PARAM_SORT_DIRS_FIRST } else { HOTKEY_PARAM_SORT_DIRS_MIXED });
+        }
+        if !self.sort_profile.is_empty() {
+            paramstr += &format!(",{}{}", HOTKEY_PARAM_SORT_PROFILE, self.sort_profile);
+        }
+        if self.b_inherit_view {
+            paramstr += &format!(",{}", HOTKEY_PARAM_INHERIT_VIEW);
+        }
+
         if self.path.len() > 0 {
             paramstr += &(",".to_string() + &self.path);
         }
@@ -170,9 +608,67 @@ impl PropertyHolder {
         }
     }
 
+    // フォルダのパースネームからフォルダ設定のキーを得る。既知フォルダ配下は"{FOLDERID}\以下のパス"とし、
+    // プロファイルの移動やシェル最上位のデスクトップ(空文字)などの別名でも同じ設定を指すようにする
+    pub fn filesort_key(path: &str) -> String {
+        KNOWN_FOLDERS.with(|known| Self::filesort_key_of(path, known))
+    }
+
+    // フォルダのパスの表記をそろえる('/'は'\'に、末尾の'\'は除く。ドライブのルートは"C:\"のまま)
+    pub fn normalize_path(path: &str) -> String {
+        let path = path.replace('/', "\\");
+        let trimmed = path.trim_end_matches('\\');
+        if trimmed.ends_with(':') && !trimmed.contains('\\') { format!("{}\\", trimmed) } else { trimmed.to_string() }
+    }
+
+    // フォルダ設定のキーから現在のパースネームを得る
+    pub fn filesort_path(key: &str) -> String {
+        KNOWN_FOLDERS.with(|known| Self::filesort_path_of(key, known))
+    }
+
+    fn filesort_key_of(path: &str, known: &[(String, String)]) -> String {
+        if let Some((path, profile)) = path.split_once(FILESORT_PROFILE_SEP) {
+            return format!("{}{}{}", Self::filesort_key_of(path, known), FILESORT_PROFILE_SEP, profile)
+        }
+        if path.is_empty() { return format!("{{{:?}}}", FOLDERID_Desktop) }
+        if path.starts_with('{') { return path.to_string() } // キー変換済み
+
+        let path = Self::normalize_path(path);
+        for (id, base) in known {
+            let Some(tail) = strip_prefix_ignore_case(&path, base.trim_end_matches('\\')) else { continue };
+            if tail.is_empty() || tail.starts_with('\\') {
+                return format!("{}{}", id, tail.trim_end_matches('\\'))
+            }
+        }
+        path
+    }
+
+    fn filesort_path_of(key: &str, known: &[(String, String)]) -> String {
+        if let Some((key, profile)) = key.split_once(FILESORT_PROFILE_SEP) {
+            return format!("{}{}{}", Self::filesort_path_of(key, known), FILESORT_PROFILE_SEP, profile)
+        }
+        let Some(end) = key.starts_with('{').then(|| key.find('}')).flatten() else { return key.to_string() };
+        match known.iter().find(|(id, _)| id.eq_ignore_ascii_case(&key[..=end])) {
+            Some((_, base)) => format!("{}{}", base.trim_end_matches('\\'), &key[end + 1..]),
+            None => key.to_string(),
+        }
+    }
+
+    // 旧形式(絶対パス)で保存されたフォルダ設定をキー形式へ移す。既にキー形式の設定があればそちらを優先する
+    pub fn migrate_filesort_keys() {
+        for name in enum_reg_value_names(REGKEY_FILE_LIST_ORDER) {
+            let key = Self::filesort_key(&name);
+            if key.eq(&name) { continue }
+            let mut v = Vec::<String>::default();
+            load_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &name, &mut v);
+            if Self::load_filesort_param(&key).is_empty() && store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &key, &v).is_err() { continue }
+            let _ = delete_reg(REGKEY_FILE_LIST_ORDER, &name);
+        }
+    }
+
     pub fn load_filesort_param(path: &str) -> Vec<String> {
         let mut v = Vec::<String>::default();
-        load_reg_multi_sz(REGKEY_FILE_LIST_ORDER, path, &mut v);
+        load_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &Self::filesort_key(path), &mut v);
         v
     }
 
@@ -186,60 +682,86 @@ impl PropertyHolder {
         };
 
         let mut new = Vec::<String>::default();
-        new.push(firstline);
+        new.push(if b_sorted { Self::with_manual_sort(&firstline) } else { firstline });
         if b_sorted || now.len() > 1 {
             new.append(sort_list);
         }
-        store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, path, &new)
+        store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &Self::filesort_key(path), &new)
+    }
+
+    // 手動で並べ替えたら並び順の設定を手動に切り替える
+    pub fn with_manual_sort(param: &str) -> String {
+        let mut p = Self::parse_string(param);
+        if p.sort_mode == SortMode::Manual { return param.to_string() }
+        p.sort_mode = SortMode::Manual;
+        p.to_string()
+    }
+
+    // 保存済みの並び順を名前変更(旧名, 新名の順に適用)と現在のアイテム一覧に突き合わせる。
+    // 消えたアイテムは除き、並びにないアイテムは現在の順で末尾へ追加する
+    pub fn reconcile_filesort(order: &[String], renames: &[(String, String)], current: &[String]) -> Vec<String> {
+        let mut order = order.to_vec();
+        for (old, new) in renames {
+            order.iter_mut().filter(|n| old.eq(*n)).for_each(|n| *n = new.clone());
+        }
+
+        let set_current = current.iter().collect::<FxHashSet<_>>();
+        let mut set_done = FxHashSet::<&String>::default();
+        let mut ret = Vec::<String>::with_capacity(current.len());
+        for n in order.iter().chain(current.iter()) {
+            if set_current.contains(n) && set_done.insert(n) {
+                ret.push(n.clone());
+            }
+        }
+        ret
+    }
+
+    // 名前変更したアイテムの保存済みの並び順(フォルダ共通と各プロファイル)を新しい名前に付け替える
+    pub fn rename_filesort_param(path: &str, oldname: &str, newname: &str) -> Result<()> {
+        let key = Self::filesort_key(Self::split_profile_path(path).0);
+        let keys = enum_reg_value_names(REGKEY_FILE_LIST_ORDER).into_iter()
+            .filter(|k| Self::split_profile_path(k).0.eq(&key));
+        for k in keys {
+            let mut v = Self::load_filesort_param(&k);
+            if v.len() <= 1 { continue } // 並び順の保存なし
+            v.iter_mut().skip(1).filter(|n| oldname.eq(n.as_str())).for_each(|n| *n = newname.to_string());
+            store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &k, &v)?;
+        }
+        Ok(())
+    }
+
+    // 保存済みの全フォルダ設定(パス, [表示設定, 並び順...])
+    pub fn enum_filesort_param() -> Vec<(String, Vec<String>)> {
+        enum_reg_value_names(REGKEY_FILE_LIST_ORDER).into_iter()
+            .map(|key| { let v = Self::load_filesort_param(&key); (Self::filesort_path(&key), v) }).collect()
+    }
+
+    pub fn delete_filesort_param(path: &str) -> Result<()> {
+        delete_reg(REGKEY_FILE_LIST_ORDER, &Self::filesort_key(path))
     }
 
     pub fn load_winsort_param(v: &mut Vec<WinSortList>) {
         let mut reg = Vec::<String>::default();
         load_reg_multi_sz(REGKEY_APP_PARAM, REG_WIN_SORT, &mut reg);
         for l in reg {
-            if l.contains("*") {
+            if PathPattern::is_pattern(&l) {
                 v.push(WinSortList::WILDCARD(l));
             } else {
                 v.push(WinSortList::IMGFILE(l));
             }
         }
     }
-    // s2 - wildcard
-    pub fn compare_wildcard(s1: &str, s2: &str) -> bool {
-        let mut idx = 0;
-        let splen = s2.split("*").count(); // collect(アロケーション)はしない方が速い
-        for (n, parts) in s2.split("*").enumerate() {
-            if parts.len() == 0 {
-                if n == splen - 1 { idx = s1.len(); } // 末尾の*は全マッチ
-                continue
-            }
-            let before = idx;
-            loop {
-                match s1[idx..].find(parts) {
-                    None => break,
-                    Some(i) => idx += i + parts.len(),
-                }
-            }
-            if idx == before { return false }
-        }
-        idx == s1.len()
-    }
-
+    // 複数のルールに一致する場合は最も具体的なもの(完全一致 > ワイルドカード > 正規表現)を採用する
     pub fn contains_window_sort_list(vec: &Vec<WinSortList>, c: &str) -> Option<usize> {
-        for (idx, v) in vec.iter().enumerate() {
-            if match v {
-                WinSortList::WILDCARD(i) => Self::compare_wildcard(c, i),
-                WinSortList::IMGFILE(i) => i.eq(c),
-            } { return Some(idx) }
-        }
-        None
+        PathPattern::best_match(vec.iter().map(|v| match v {
+            WinSortList::WILDCARD(i) => i.as_str(),
+            WinSortList::IMGFILE(i) => i.as_str(),
+        }), c)
     }
 
-    pub fn contains_procimg_list(vec: &mut dyn Iterator<Item = &&String>, c: &str) -> Option<String> {
-        for v in vec {
-            if Self::compare_wildcard(c, v) { return Some(v.to_string()) }
-        }
-        None
+    pub fn contains_procimg_list(vec: &mut dyn Iterator<Item = &String>, c: &str) -> Option<String> {
+        let list = vec.collect::<Vec<_>>();
+        PathPattern::best_match(list.iter().map(|v| v.as_str()), c).map(|idx| list[idx].to_string())
     }
 
     pub fn store_winsort_param(v: &Vec<WinSortList>) {
@@ -264,17 +786,65 @@ impl PropertyHolder {
                 new.push(i);
             }
         }
-        store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, path, &new)
+        store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &Self::filesort_key(path), &new)
     }
 
     pub fn sort_reset(path: &str) -> Result<()> {
         let mut now = Self::load_filesort_param(path);
+        if !Self::split_profile_path(path).1.is_empty() { // プロファイルは手動の設定ごと消してフォルダの並び順へ戻す
+            if now.is_empty() { return Err(Error::OK) }
+            return Self::delete_filesort_param(path)
+        }
         if now.len() <= 1 { return Err(Error::OK) }
 
         let firstline = now.remove(0);
         let mut val = Vec::<String>::default();
         val.push(firstline);
-        store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, path, &val)
+        store_reg_multi_sz(REGKEY_FILE_LIST_ORDER, &Self::filesort_key(path), &val)
+    }
+
+    pub fn load_winfilter_param() -> Vec<WinFilterRule> {
+        let mut reg = Vec::<String>::default();
+        load_reg_multi_sz(REGKEY_APP_PARAM, REG_WIN_FILTER, &mut reg);
+        reg.iter().filter_map(|l| WinFilterRule::parse_string(l)).collect()
+    }
+
+    pub fn store_winfilter_param(v: &[WinFilterRule]) {
+        let lines = v.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        let _ = store_reg_multi_sz(REGKEY_APP_PARAM, REG_WIN_FILTER, &lines);
+    }
+
+    pub fn load_titlerule_param() -> Vec<TitleRule> {
+        let mut reg = Vec::<String>::default();
+        load_reg_multi_sz(REGKEY_APP_PARAM, REG_TITLE_RULE, &mut reg);
+        reg.iter().filter_map(|l| TitleRule::parse_string(l)).collect()
+    }
+
+    pub fn store_titlerule_param(v: &[TitleRule]) {
+        let lines = v.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        let _ = store_reg_multi_sz(REGKEY_APP_PARAM, REG_TITLE_RULE, &lines);
+    }
+
+    pub fn load_winslot_param() -> Vec<WinSlot> {
+        let mut reg = Vec::<String>::default();
+        load_reg_multi_sz(REGKEY_APP_PARAM, REG_WIN_SLOT, &mut reg);
+        reg.iter().filter_map(|l| WinSlot::parse_string(l)).collect()
+    }
+
+    pub fn store_winslot_param(v: &[WinSlot]) {
+        let lines = v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let _ = store_reg_multi_sz(REGKEY_APP_PARAM, REG_WIN_SLOT, &lines);
+    }
+
+    pub fn load_proc_history() -> Vec<ProcHistory> {
+        let mut reg = Vec::<String>::default();
+        load_reg_multi_sz(REGKEY_APP_PARAM, REG_PROC_HISTORY, &mut reg);
+        reg.iter().filter_map(|l| ProcHistory::parse_string(l)).collect()
+    }
+
+    pub fn store_proc_history(v: &[ProcHistory]) {
+        let lines = ProcHistory::newest(v, PROC_HISTORY_MAX).iter().map(|h| h.to_string()).collect::<Vec<_>>();
+        let _ = store_reg_multi_sz(REGKEY_APP_PARAM, REG_PROC_HISTORY, &lines);
     }
 
     pub fn check_hotkey_char(c: &char) -> bool {
@@ -404,3 +974,373 @@ impl PropertyHolder {
         Ok(())
     }
 }
+
+// 大文字小文字を区別せずに前方一致を除く(パスの比較用)
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut it = s.chars();
+    for pc in prefix.chars() {
+        if !it.next()?.to_lowercase().eq(pc.to_lowercase()) { return None }
+    }
+    Some(it.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(a: &[&str]) -> Vec<String> {
+        a.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn reconcile_keeps_position_of_renamed_item() {
+        let r = PropertyHolder::reconcile_filesort(&v(&["c", "a", "b"]), &[("a".into(), "x".into())], &v(&["b", "c", "x"]));
+        assert_eq!(r, v(&["c", "x", "b"]));
+    }
+
+    #[test]
+    fn reconcile_follows_chained_renames() {
+        let renames = [("a".to_string(), "b".to_string()), ("b".to_string(), "c".to_string())];
+        let r = PropertyHolder::reconcile_filesort(&v(&["z", "a", "y"]), &renames, &v(&["c", "y", "z"]));
+        assert_eq!(r, v(&["z", "c", "y"]));
+    }
+
+    #[test]
+    fn reconcile_drops_deleted_and_appends_new() {
+        let r = PropertyHolder::reconcile_filesort(&v(&["a", "b", "c"]), &[], &v(&["d", "c", "a"]));
+        assert_eq!(r, v(&["a", "c", "d"]));
+    }
+
+    #[test]
+    fn reconcile_readded_item_goes_to_end() {
+        let deleted = PropertyHolder::reconcile_filesort(&v(&["a", "b", "c"]), &[], &v(&["a", "c"]));
+        assert_eq!(deleted, v(&["a", "c"]));
+        let readded = PropertyHolder::reconcile_filesort(&deleted, &[], &v(&["a", "b", "c"]));
+        assert_eq!(readded, v(&["a", "c", "b"]));
+    }
+
+    #[test]
+    fn reconcile_readded_item_keeps_saved_position() {
+        // 保存済みの並びに残っている間に再作成された場合は元の位置に戻る
+        let r = PropertyHolder::reconcile_filesort(&v(&["a", "b", "c"]), &[], &v(&["c", "b", "a"]));
+        assert_eq!(r, v(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn sort_param_round_trip() {
+        let p = PropertyHolder::parse_string("ICON,LG,10,5,HIDE,NATU/MIX");
+        assert_eq!((p.sort_mode, p.b_folders_first, p.path.as_str()), (SortMode::NaturalName, false, ""));
+        assert_eq!(p.to_string(), "ICON,LG,10,5,HIDE,NATU/MIX");
+
+        let p = PropertyHolder::parse_string(r"LIST,0,0,SHOW,DATE/DIR,C:\tools");
+        assert_eq!((p.sort_mode, p.b_folders_first, p.path.as_str()), (SortMode::Modified, true, r"C:\tools"));
+        assert_eq!(p.to_string(), r"LIST,0,0,SHOW,DATE/DIR,C:\tools");
+    }
+
+    #[test]
+    fn task_param_round_trip() {
+        let p = PropertyHolder::parse_string("TASK,300,0,ATTN");
+        assert!(p.hotkey_type == HotkeyType::WinTaskList);
+        assert_eq!((p.b_attention_top, p.b_proc_line), (true, false));
+        assert_eq!(p.to_string(), "TASK,300,0,ATTN");
+
+        let p = PropertyHolder::parse_string("TASK,300,0,,PROC");
+        assert!(p.hotkey_type == HotkeyType::WinTaskList);
+        assert_eq!((p.b_attention_top, p.b_proc_line), (false, true));
+        assert_eq!(p.to_string(), "TASK,300,0,,PROC");
+        assert!(PropertyHolder::parse_string("TASK,300,0,,BOLD").hotkey_type != HotkeyType::WinTaskList);
+    }
+
+    #[test]
+    fn slot_param_round_trip() {
+        let p = PropertyHolder::parse_string("SLOT,3");
+        assert!(p.hotkey_type == HotkeyType::WinSlot);
+        assert_eq!(p.slot, 3);
+        assert_eq!(p.to_string(), "SLOT,3");
+        assert!(PropertyHolder::parse_string("SLOT,0").hotkey_type != HotkeyType::WinSlot);
+        assert!(PropertyHolder::parse_string("SLOT,10").hotkey_type != HotkeyType::WinSlot);
+    }
+
+    #[test]
+    fn direct_action_param_round_trip() {
+        let p = PropertyHolder::parse_string(r"RAISE,C:\Tools\a,b.exe");
+        assert!(p.hotkey_type == HotkeyType::RunOrRaise);
+        assert_eq!(p.path, r"C:\Tools\a,b.exe");
+        assert_eq!(p.to_string(), r"RAISE,C:\Tools\a,b.exe");
+        assert!(PropertyHolder::parse_string("RAISE,").hotkey_type != HotkeyType::RunOrRaise);
+
+        for s in ["CYCLE", "LAST"] {
+            assert_eq!(PropertyHolder::parse_string(s).to_string(), s);
+        }
+        assert!(PropertyHolder::parse_string("CYCLE").hotkey_type == HotkeyType::CycleApp);
+        assert!(PropertyHolder::parse_string("LAST").hotkey_type == HotkeyType::ToggleLast);
+    }
+
+    #[test]
+    fn win_slot_bind_replaces_slot_and_window() {
+        let mut slots = vec![WinSlot::parse_string("2\tC:\\a.exe\tA").unwrap()];
+        assert_eq!(slots[0].to_string(), "2\tC:\\a.exe\tA");
+        slots[0].hwnd = 10;
+        WinSlot::bind(&mut slots, 1, r"C:\b.exe", "B", 20);
+        WinSlot::bind(&mut slots, 3, r"C:\a.exe", "A", 10); // 同じウィンドウは移動
+        assert_eq!(slots.iter().map(|s| (s.slot, s.hwnd)).collect::<Vec<_>>(), vec![(1, 20), (3, 10)]);
+        WinSlot::bind(&mut slots, 1, r"C:\c.exe", "", 30); // 同じスロットは置き換え
+        assert_eq!(slots.iter().map(|s| (s.slot, s.hwnd)).collect::<Vec<_>>(), vec![(1, 30), (3, 10)]);
+        assert!(WinSlot::unbind(&mut slots, 10));
+        assert!(!WinSlot::unbind(&mut slots, 10));
+        assert!(WinSlot::parse_string("0\tC:\\a.exe\t").is_none());
+    }
+
+    #[test]
+    fn win_slot_bind_keeps_title_literal() {
+        let mut slots = Vec::default();
+        WinSlot::bind(&mut slots, 1, r"C:\a.exe", "re: Budget (draft)", 10);
+        WinSlot::bind(&mut slots, 2, r"C:\b.exe", "*.txt - Notepad", 20);
+        assert!(slots[0].matches_title("re: Budget (draft)") && !slots[0].matches_title(" Budget draft"));
+        assert!(slots[1].matches_title("*.txt - Notepad") && !slots[1].matches_title("a.txt - Notepad"));
+        assert_eq!(WinSlot::parse_string(&slots[0].to_string()), Some(WinSlot { hwnd: 0, ..slots[0].clone() }));
+    }
+
+    #[test]
+    fn sort_param_absent_keeps_legacy_format() {
+        let p = PropertyHolder::parse_string(r"LIST,0,0,HIDE,C:\tools");
+        assert_eq!((p.sort_mode, p.b_folders_first, p.path.as_str()), (SortMode::Manual, true, r"C:\tools"));
+        assert_eq!(p.to_string(), r"LIST,0,0,HIDE,C:\tools");
+        assert_eq!(PropertyHolder::with_manual_sort("LIST,0,0,HIDE,TYPE/MIX"), "LIST,0,0,HIDE,MANU/MIX");
+        assert_eq!(PropertyHolder::with_manual_sort("LIST,0,0,HIDE"), "LIST,0,0,HIDE");
+    }
+
+    fn known() -> Vec<(String, String)> {
+        vec![(format!("{{{:?}}}", FOLDERID_Desktop), r"C:\Users\me\Desktop".to_string()),
+            (format!("{{{:?}}}", FOLDERID_Profile), r"C:\Users\me".to_string())]
+    }
+
+    #[test]
+    fn filesort_key_resolves_known_folder_aliases() {
+        let desktop = format!("{{{:?}}}", FOLDERID_Desktop);
+        assert_eq!(PropertyHolder::filesort_key_of("", &known()), desktop);
+        assert_eq!(PropertyHolder::filesort_key_of(r"c:\users\ME\desktop\", &known()), desktop);
+        assert_eq!(PropertyHolder::filesort_key_of(r"C:\Users\me\Desktop\taskbar", &known()), format!(r"{}\taskbar", desktop));
+        assert_eq!(PropertyHolder::filesort_key_of(r"C:\Users\me\Desktop2", &known()), format!(r"{{{:?}}}\Desktop2", FOLDERID_Profile));
+        assert_eq!(PropertyHolder::filesort_key_of(r"D:\tools", &known()), r"D:\tools");
+        assert_eq!(PropertyHolder::filesort_key_of(&desktop, &known()), desktop);
+    }
+
+    #[test]
+    fn normalize_path_unifies_separators() {
+        assert_eq!(PropertyHolder::normalize_path(r"D:/tools/bin/"), r"D:\tools\bin");
+        assert_eq!(PropertyHolder::normalize_path(r"\\server\share\\"), r"\\server\share");
+        assert_eq!(PropertyHolder::normalize_path(r"C:\"), r"C:\");
+        assert_eq!(PropertyHolder::normalize_path("C:"), r"C:\");
+        assert_eq!(PropertyHolder::normalize_path(""), "");
+        assert_eq!(PropertyHolder::filesort_key_of(r"D:/tools/", &known()), PropertyHolder::filesort_key_of(r"D:\tools", &known()));
+    }
+
+    #[test]
+    fn filesort_path_follows_moved_folder() {
+        let key = PropertyHolder::filesort_key_of(r"C:\Users\me\Desktop\taskbar", &known());
+        let moved = vec![(known()[0].0.clone(), r"E:\me\Desktop".to_string())];
+        assert_eq!(PropertyHolder::filesort_path_of(&key, &moved), r"E:\me\Desktop\taskbar");
+        assert_eq!(PropertyHolder::filesort_path_of(r"D:\tools", &moved), r"D:\tools");
+    }
+
+    #[test]
+    fn launcher_options_in_any_order() {
+        let p = PropertyHolder::parse_string(r"LIST,0,0,HIDE,INHT,PRF:work,C:\tools");
+        assert_eq!((p.b_sort_set, p.sort_profile.as_str(), p.b_inherit_view, p.path.as_str()), (false, "work", true, r"C:\tools"));
+        assert_eq!(p.to_string(), r"LIST,0,0,HIDE,PRF:work,INHT,C:\tools");
+
+        let p = PropertyHolder::parse_string("ICON,SM,0,0,SHOW,PRF:a,MANU/DIR");
+        assert_eq!((p.b_sort_set, p.sort_mode, p.sort_profile.as_str()), (true, SortMode::Manual, "a"));
+        assert_eq!(p.to_string(), "ICON,SM,0,0,SHOW,MANU/DIR,PRF:a");
+    }
+
+    #[test]
+    fn resolve_prefers_hotkey_then_folder() {
+        let hk = PropertyHolder::parse_string(r"ICON,LG,3,4,SHOW,PRF:work,C:\tools");
+        let r = PropertyHolder::resolve(Some(&hk), "LIST,0,0,HIDE,TYPE/MIX", "");
+        assert!(r.hotkey_type == HotkeyType::IconLauncher && r.b_icon_large && !r.b_sysfile_hidden);
+        assert_eq!((r.w, r.h, r.sort_mode, r.b_folders_first, r.sort_profile.as_str()), (3, 4, SortMode::Type, false, "work"));
+
+        let hk = PropertyHolder::parse_string(r"LIST,0,0,HIDE,NAME/DIR,C:\tools");
+        let r = PropertyHolder::resolve(Some(&hk), "LIST,0,0,HIDE,TYPE/MIX", "");
+        assert_eq!((r.sort_mode, r.b_folders_first), (SortMode::Name, true));
+
+        let r = PropertyHolder::resolve(None, "", "");
+        assert_eq!((r.sort_mode, r.b_folders_first, r.b_sysfile_hidden), (SortMode::Manual, true, true));
+    }
+
+    #[test]
+    fn resolve_keeps_manual_sort_of_profile() {
+        // フォルダが名前順でも、プロファイルで手動に並べ替えた後はプロファイルの手動を使う
+        let folder = "LIST,0,0,HIDE,NAME/DIR";
+        let profile = PropertyHolder::with_manual_sort(folder);
+        let hk = PropertyHolder::parse_string(r"LIST,0,0,HIDE,PRF:work,C:\tools");
+        assert_eq!(PropertyHolder::resolve(Some(&hk), folder, "").sort_mode, SortMode::Name);
+        let r = PropertyHolder::resolve(Some(&hk), folder, &profile);
+        assert_eq!((r.sort_mode, r.b_folders_first, r.sort_profile.as_str()), (SortMode::Manual, true, "work"));
+        assert!(!PropertyHolder::sort_fixed_by(Some(&hk)));
+
+        // プロファイルなしでは保存先はフォルダの行
+        let hk = PropertyHolder::parse_string(r"LIST,0,0,HIDE,C:\tools");
+        let r = PropertyHolder::resolve(Some(&hk), &PropertyHolder::with_manual_sort(folder), &profile);
+        assert_eq!(r.sort_mode, SortMode::Manual);
+        assert_eq!(PropertyHolder::resolve(Some(&hk), folder, &profile).sort_mode, SortMode::Name);
+    }
+
+    #[test]
+    fn sort_fixed_by_hotkey_override() {
+        // ホットキーの並び順はプロファイル・フォルダの手動より優先するため、手動以外なら並べ替えさせない
+        let hk = PropertyHolder::parse_string(r"LIST,0,0,HIDE,NAME/DIR,PRF:work,C:\tools");
+        let r = PropertyHolder::resolve(Some(&hk), "LIST,0,0,HIDE,MANU/DIR", &PropertyHolder::with_manual_sort(""));
+        assert_eq!(r.sort_mode, SortMode::Name);
+        assert!(PropertyHolder::sort_fixed_by(Some(&hk)));
+
+        let hk = PropertyHolder::parse_string(r"LIST,0,0,HIDE,MANU/MIX,C:\tools");
+        assert!(!PropertyHolder::sort_fixed_by(Some(&hk)));
+        assert!(!PropertyHolder::sort_fixed_by(None));
+    }
+
+    #[test]
+    fn filesort_key_keeps_profile_suffix() {
+        let desktop = format!("{{{:?}}}", FOLDERID_Desktop);
+        let path = PropertyHolder::profile_path(r"C:\Users\me\Desktop\taskbar", "work");
+        let key = PropertyHolder::filesort_key_of(&path, &known());
+        assert_eq!(key, format!(r"{}\taskbar|work", desktop));
+        assert_eq!(PropertyHolder::filesort_path_of(&key, &known()), path);
+        assert_eq!(PropertyHolder::split_profile_path(&path), (r"C:\Users\me\Desktop\taskbar", "work"));
+        assert_eq!(PropertyHolder::profile_path(r"D:\tools", ""), r"D:\tools");
+    }
+
+    fn target<'a>(class: &'a str, title: &'a str, proc_img: &'a str) -> WinFilterTarget<'a> {
+        WinFilterTarget { class, title, proc_img, b_tool: false, b_owned: false, b_topmost: false }
+    }
+
+    #[test]
+    fn winfilter_empty_fields_match_anything() {
+        let r = WinFilterRule::default();
+        assert!(r.matches(&target("AnyClass", "title", r"C:\a.exe")));
+        let r = WinFilterRule { class: "Chrome_*".into(), proc_img: r"*\chrome.exe".into(), ..Default::default() };
+        assert!(r.matches(&target("Chrome_WidgetWin_1", "x", r"C:\Program Files\chrome.exe")));
+        assert!(!r.matches(&target("Chrome_WidgetWin_1", "x", r"C:\Program Files\msedge.exe")));
+        assert!(!r.matches(&target("Notepad", "x", r"C:\Program Files\chrome.exe")));
+    }
+
+    #[test]
+    fn winfilter_style_conditions() {
+        let r = WinFilterRule { tool: Some(true), owned: Some(false), ..Default::default() };
+        let mut t = target("c", "t", "p");
+        assert!(!r.matches(&t));
+        t.b_tool = true;
+        assert!(r.matches(&t));
+        t.b_owned = true;
+        assert!(!r.matches(&t));
+        t.b_owned = false;
+        t.b_topmost = true; // 未指定の条件は問わない
+        assert!(r.matches(&t));
+    }
+
+    #[test]
+    fn winfilter_first_matching_rule_wins() {
+        let rules = [
+            WinFilterRule { b_include: true, title: "Keep*".into(), ..Default::default() },
+            WinFilterRule { b_include: false, proc_img: r"*\helper.exe".into(), ..Default::default() },
+        ];
+        assert_eq!(WinFilterRule::verdict(&rules, &target("c", "Keep me", r"C:\helper.exe")), Some(true));
+        assert_eq!(WinFilterRule::verdict(&rules, &target("c", "Other", r"C:\helper.exe")), Some(false));
+        assert_eq!(WinFilterRule::verdict(&rules, &target("c", "Other", r"C:\app.exe")), None);
+        assert_eq!(WinFilterRule::verdict(&[], &target("c", "Other", r"C:\app.exe")), None);
+    }
+
+    #[test]
+    fn winfilter_round_trip() {
+        let r = WinFilterRule { b_include: false, class: "Tray*".into(), title: String::default(), proc_img: r"C:\x.exe".into(),
+            tool: Some(true), owned: None, topmost: Some(false) };
+        assert_eq!(r.to_string(), "EXC\tTray*\t\tC:\\x.exe\tTOOL !TOPMOST");
+        assert_eq!(WinFilterRule::parse_string(&r.to_string()), Some(r));
+        assert_eq!(WinFilterRule::parse_string("INC\t\t\t\t"), Some(WinFilterRule { b_include: true, ..Default::default() }));
+        assert_eq!(WinFilterRule::parse_string("XXX\t\t\t\t"), None);
+        assert_eq!(WinFilterRule::parse_string("INC\t\t\t\tBOLD"), None);
+    }
+
+    fn title_rule(action: TitleAction, proc_img: &str, find: &str, replace: &str) -> TitleRule {
+        TitleRule { action, proc_img: proc_img.into(), find: find.into(), replace: replace.into() }
+    }
+
+    #[test]
+    fn title_rule_actions() {
+        assert_eq!(title_rule(TitleAction::StripSuffix, "", " - Google Chrome", "").apply("News - Google Chrome"), "News");
+        assert_eq!(title_rule(TitleAction::StripSuffix, "", " - Google Chrome", "").apply("Google Chrome"), "Google Chrome");
+        assert_eq!(title_rule(TitleAction::StripPrefix, "", "* ", "").apply("* Untitled"), "Untitled");
+        assert_eq!(title_rule(TitleAction::Replace, "", r"^\((\d+)\) (.*)$", "$2 [$1]").apply("(3) Inbox"), "Inbox [3]");
+        assert_eq!(title_rule(TitleAction::Replace, "", "(", "x").apply("a(b"), "a(b"); // 不正な正規表現は無視
+        assert_eq!(title_rule(TitleAction::Swap, "", " - ", "").apply("main.rs - proj - Code"), "Code - proj - main.rs");
+        assert_eq!(title_rule(TitleAction::Swap, "", " - ", " | ").apply("a - b"), "b | a");
+        assert_eq!(title_rule(TitleAction::Swap, "", "", "").apply("a - b"), "a - b");
+    }
+
+    #[test]
+    fn title_rule_rewrite_chains_matching_rules() {
+        let rules = [
+            title_rule(TitleAction::StripSuffix, r"*\chrome.exe", " - Google Chrome", ""),
+            title_rule(TitleAction::StripPrefix, "", "Re: ", ""),
+            title_rule(TitleAction::StripSuffix, "Microsoft.WindowsTerminal_*", " - Terminal", ""),
+        ];
+        assert_eq!(TitleRule::rewrite(&rules, r"C:\x\chrome.exe", "", "Re: Mail - Google Chrome"), "Mail");
+        assert_eq!(TitleRule::rewrite(&rules, r"C:\x\edge.exe", "", "Re: Mail - Google Chrome"), "Mail - Google Chrome");
+        assert_eq!(TitleRule::rewrite(&rules, r"C:\w\wt.exe", "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App", "pwsh - Terminal"), "pwsh");
+        assert_eq!(TitleRule::rewrite(&rules, r"C:\x\chrome.exe", "", " - Google Chrome"), " - Google Chrome"); // 空になる場合は元のまま
+    }
+
+    #[test]
+    fn title_rule_round_trip() {
+        let r = title_rule(TitleAction::Replace, r"*\code.exe", r"(.*) - (.*)", "$2: $1");
+        assert_eq!(r.to_string(), "REPLACE\t*\\code.exe\t(.*) - (.*)\t$2: $1");
+        assert_eq!(TitleRule::parse_string(&r.to_string()), Some(r));
+        assert_eq!(TitleRule::parse_string("SWAP\t\t - \t"), Some(title_rule(TitleAction::Swap, "", " - ", "")));
+        assert_eq!(TitleRule::parse_string("XXX\t\t\t"), None);
+        assert_eq!(TitleRule::parse_string("SUFFIX\t\t"), None);
+    }
+
+    #[test]
+    fn proc_history_round_trip() {
+        let h = ProcHistory { proc_img: r"C:\a.exe".into(), last_seen: 1700000000, icon_path: r"shell:AppsFolder\App!Main".into() };
+        assert_eq!(h.to_string(), "C:\\a.exe\t1700000000\tshell:AppsFolder\\App!Main");
+        assert_eq!(ProcHistory::parse_string(&h.to_string()), Some(h));
+        assert_eq!(ProcHistory::parse_string("C:\\a.exe\tyesterday\t"), None);
+        assert_eq!(ProcHistory::parse_string("\t1\t"), None);
+        assert_eq!(ProcHistory::parse_string("C:\\a.exe\t1"), None);
+    }
+
+    #[test]
+    fn proc_history_touch_and_prune() {
+        let day = SECS_PER_DAY;
+        let mut hist = Vec::<ProcHistory>::new();
+        assert!(ProcHistory::touch(&mut hist, r"C:\a.exe", 10 * day));
+        assert!(ProcHistory::touch(&mut hist, r"C:\b.exe", 2 * day));
+        assert!(!ProcHistory::touch(&mut hist, r"C:\a.exe", 100 * day));
+        assert!(!ProcHistory::touch(&mut hist, r"C:\a.exe", 50 * day)); // 時刻は戻さない
+        assert!(ProcHistory::touch(&mut hist, r"C:\gone.exe", 100 * day));
+        assert_eq!(hist.len(), 3);
+        assert_eq!(hist[0].last_seen, 100 * day);
+        assert_eq!(hist[0].icon_path, r"C:\a.exe");
+        assert_eq!(hist[1].days_ago(100 * day), 98);
+
+        let removed = ProcHistory::prune(&mut hist, 100 * day, 90, |p| p != r"C:\gone.exe");
+        assert_eq!(removed, 2);
+        assert_eq!(hist.iter().map(|h| h.proc_img.as_str()).collect::<Vec<_>>(), [r"C:\a.exe"]);
+    }
+
+    #[test]
+    fn proc_history_keeps_newest() {
+        let hist = (0..5).map(|i| ProcHistory { proc_img: format!("{}", i), last_seen: i, ..Default::default() }).collect::<Vec<_>>();
+        let v = ProcHistory::newest(&hist, 3);
+        assert_eq!(v.iter().map(|h| h.last_seen).collect::<Vec<_>>(), [4, 3, 2]);
+    }
+
+    #[test]
+    fn reconcile_without_order_uses_current() {
+        let r = PropertyHolder::reconcile_filesort(&[], &[], &v(&["b", "a"]));
+        assert_eq!(r, v(&["b", "a"]));
+    }
+}
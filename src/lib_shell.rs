@@ -1,22 +1,26 @@
-use std::cell::UnsafeCell;
+use std::{cell::UnsafeCell, cmp, sync::atomic::Ordering};
 
 use fxhash::FxHashMap;
 
 use windows::Win32::{
     UI::Shell::Common::ITEMIDLIST,
-    System::{Ole::*, Com::{IDataObject, CLSCTX_INPROC_SERVER, CoCreateInstance, FORMATETC, DVASPECT_CONTENT, TYMED_HGLOBAL}, SystemServices::{SFGAO_FOLDER, MODIFIERKEYS_FLAGS, MK_LBUTTON, MK_RBUTTON}, Memory::{GlobalUnlock, GlobalLock, GlobalSize}, DataExchange::RegisterClipboardFormatW, },
+    Storage::FileSystem::WIN32_FIND_DATAW,
+    System::{Ole::*, Com::{IDataObject, CLSCTX_INPROC_SERVER, CoCreateInstance, CoTaskMemFree, FORMATETC, DVASPECT_CONTENT, TYMED_HGLOBAL}, SystemServices::{SFGAO_FOLDER, MODIFIERKEYS_FLAGS, MK_LBUTTON, MK_RBUTTON}, Memory::{GlobalUnlock, GlobalLock, GlobalSize}, DataExchange::RegisterClipboardFormatW, },
 };
 
 use super::*;
-use crate::lib_property::PropertyHolder;
+use crate::lib_property::{PropertyHolder, SortMode};
 
 #[derive(Default)]
 pub struct ObjectHolder {
     pub parse_name: String,
-    pub str_param: String,
+    pub param: PropertyHolder, // 表示設定(ホットキー > フォルダ > 既定値で決定済み)
     pub ishell_folder: Option<IShellFolder>,
     pub icontext_menu3: Option<IContextMenu3>,
     pub list_items: Vec<ObjectItem>,
+    pub launch: Option<Rc<PropertyHolder>>, // 起点のホットキーの設定
+    pub b_root: bool, // ホットキーで開いたフォルダそのもの
+    pub b_sort_fixed: bool, // ホットキーで手動以外の並び順に固定(中ドラッグで並べ替えない)
 }
 
 pub struct ObjectItem {
@@ -24,12 +28,22 @@ pub struct ObjectItem {
     pub attr: u32,
     pub str_disp_name: WSTR,
     pub str_parse_name: WSTR,
-    pub icon_sm: Icon,
+    pub mtime: u64, // 更新日時(FILETIME)。ファイルシステム外のアイテムは0
+    pub icon_sm: Rc<Icon>, // 読込完了まではプレースホルダを共有
+    pub icon_lr: Rc<Icon>,
+}
+
+// アイコン読込スレッドからの結果
+pub struct IconLoaded {
+    pub key: String,
     pub icon_lr: Icon,
+    pub icon_sm: Icon,
 }
 
 impl ObjectHolder {
-    fn enumerate(isf: IShellFolder, parse_name: String, b_op_hidden: Option<bool>) -> Result<ObjectHolder> {
+    fn enumerate(isf: IShellFolder, parse_name: String, launch: Option<Rc<PropertyHolder>>, b_root: bool) -> Result<ObjectHolder> {
+        // 並び順の保存名・アイコンキャッシュのキー・プロパティ画面へ渡すパスとして使うため表記をそろえる
+        let parse_name = PropertyHolder::normalize_path(&parse_name);
         let mut vec = Vec::<ObjectItem>::default();
         let mut vec_parsename = Vec::<String>::default();
         let mut map = FxHashMap::<String, ObjectItem>::default();
@@ -37,8 +51,20 @@ impl ObjectHolder {
         let mut sortlist = PropertyHolder::load_filesort_param(&parse_name);
         let str_param = if sortlist.len() > 0 { sortlist.remove(0) } else { String::default() };
 
+        // サブフォルダにはホットキーで指定された場合だけホットキーの設定を適用する
+        let hotkey = launch.as_deref().filter(|h| b_root || h.b_inherit_view);
+        let profile = hotkey.map_or("", |h| h.sort_profile.as_str());
+        let mut profile_list = if profile.is_empty() { Vec::default() } else {
+            PropertyHolder::load_filesort_param(&PropertyHolder::profile_path(&parse_name, profile))
+        };
+        let str_profile = if profile_list.len() > 0 { profile_list.remove(0) } else { String::default() };
+        let param = PropertyHolder::resolve(hotkey, &str_param, &str_profile);
+        let b_sort_fixed = PropertyHolder::sort_fixed_by(hotkey);
+        if !param.sort_profile.is_empty() { // 手動の並び順はプロファイル別の保存を使う
+            sortlist = profile_list;
+        }
+        let b_hidden = param.b_sysfile_hidden;
         let ienum_id_list: IEnumIDList = {
-            let b_hidden = b_op_hidden.or_else(||Some(PropertyHolder::parse_string(&str_param).b_sysfile_hidden)).unwrap();
             let enumflag = (SHCONTF_FOLDERS.0 | SHCONTF_NONFOLDERS.0) | if !b_hidden { SHCONTF_INCLUDEHIDDEN.0 |SHCONTF_INCLUDESUPERHIDDEN.0 } else { 0 };
 
             let mut ptr =  0usize;
@@ -46,6 +72,12 @@ impl ObjectHolder {
             unsafe { std::mem::transmute(ptr) } // 実体化しないとRelease呼ばれない
         };
 
+        // アイコンはバックグラウンドで読み込むため、それまではフォルダ/ファイルの汎用アイコンを表示
+        let placeholder_folder = Icon::load_stock_icon(SIID_FOLDER);
+        let placeholder_folder = (Rc::new(placeholder_folder.0), Rc::new(placeholder_folder.1));
+        let placeholder_file = Icon::load_stock_icon(SIID_DOCNOASSOC);
+        let placeholder_file = (Rc::new(placeholder_file.0), Rc::new(placeholder_file.1));
+
         let mut itemlist = [0usize as *mut ITEMIDLIST];
         let mut pceltfetched = 0u32;
         let mut result = unsafe { ienum_id_list.Next(&mut itemlist, Some(&mut pceltfetched)) };
@@ -60,15 +92,16 @@ impl ObjectHolder {
             let mut attr = SFGAO_FOLDER.0;
             let _ = unsafe { isf.GetAttributesOf(&itemlistc, &mut attr) };
 
-            let (icon_l, icon_s) = Icon::load_file_icon(&isf, itemlist[0]); // auto drop resource(ObjectItemへ保存)
+            let (icon_l, icon_s) = if (attr & SFGAO_FOLDER.0) != 0 { &placeholder_folder } else { &placeholder_file };
 
             let i = ObjectItem {
                 idl_rel: pidl,
                 attr: attr,
                 str_disp_name: str_disp_name,
                 str_parse_name: WSTR(str_parse_name.0.clone()), // MOVE回避のため複製
-                icon_sm: icon_s,
-                icon_lr: icon_l,
+                mtime: Self::get_object_mtime(&isf, itemlist[0]),
+                icon_sm: icon_s.clone(),
+                icon_lr: icon_l.clone(),
             };
             map.insert(str_parse_name.to_string_null_search(), i);
             vec_parsename.push(str_parse_name.to_string_null_search());
@@ -76,25 +109,21 @@ impl ObjectHolder {
             result = unsafe { ienum_id_list.Next(&mut itemlist, Some(&mut pceltfetched)) };
         }
 
-        if sortlist.len() > 0 { // ソート保存に従って並べる(ソート保存に値がないアイテムは後ろに回る)
-            for i in sortlist.into_iter() {
-                let v = map.remove(&i);
-                if let Some(v) = v {
-                    vec.push(v);
-                }
-            }
-            for i in vec_parsename.iter() {
-                let v = map.remove(i);
-                if let Some(v) = v {
+        if param.sort_mode != SortMode::Manual { // 並び順の設定に従って並べる
+            vec = vec_parsename.iter().filter_map(|n| map.remove(n)).collect();
+            vec.sort_by(|a, b| SortKey::of(a).cmp_by(&SortKey::of(b), param.sort_mode, param.b_folders_first));
+        } else if sortlist.len() > 0 { // ソート保存に従って並べる(ソート保存に値がないアイテムは後ろに回る)
+            for i in PropertyHolder::reconcile_filesort(&sortlist, &[], &vec_parsename).iter() {
+                if let Some(v) = map.remove(i) {
                     vec.push(v);
                 }
             }
-        } else { // ソート保存がない時はEnum順を維持するが、ディレクトリは先に並べる
+        } else { // ソート保存がない時はEnum順を維持するが、ディレクトリは先に並べる(設定による)
             let mut v_file = Vec::<ObjectItem>::default();
             for i in vec_parsename.iter() {
                 let v = map.remove(i);
                 if let Some(v) = v {
-                    if (v.attr & SFGAO_FOLDER.0 as u32) != 0 {
+                    if (v.attr & SFGAO_FOLDER.0 as u32) != 0 || !param.b_folders_first {
                         vec.push(v);
                     } else {
                         v_file.push(v);
@@ -110,17 +139,21 @@ impl ObjectHolder {
                 attr: 0u32,
                 str_disp_name: WSTR::from(OBJECTITEM_EMPTY),
                 str_parse_name: WSTR::from(""),
-                icon_sm: Icon(HICON(0)),
-                icon_lr: Icon(HICON(0)),
+                mtime: 0,
+                icon_sm: Rc::new(Icon(HICON(0))),
+                icon_lr: Rc::new(Icon(HICON(0))),
             })
         }
 
         Ok(ObjectHolder {
             parse_name,
-            str_param: str_param,
+            param,
             ishell_folder: Some(isf),
             icontext_menu3: None,
             list_items: vec,
+            launch,
+            b_root,
+            b_sort_fixed,
         })
     }
 
@@ -147,9 +180,40 @@ impl ObjectHolder {
         Ok((ishell_folder, str_parse_name))
     }
 
-    pub fn bind(path: &str, b_hidden: bool) -> Result<ObjectHolder> {
-        let (ishell_folder, str_parse_name) = Self::parse_object(path)?;
-        Self::enumerate(ishell_folder, str_parse_name, Some(b_hidden))
+    // パスが現在も解決できるか(空はデスクトップ)
+    pub fn exists(path: &str) -> bool {
+        path.is_empty() || Self::parse_object(path).is_ok()
+    }
+
+    // 起動対象パスの環境変数(%NAME%)とshell:既知フォルダ名を展開する(展開できない部分はそのまま)
+    pub fn expand_path(path: &str) -> String {
+        let s = Self::expand_env(path);
+        let Some((name, tail)) = split_shell_moniker(&s) else { return s };
+        match Self::known_folder_parse_name(name) {
+            Some(base) => format!("{}{}", base.trim_end_matches('\\'), tail),
+            None => s,
+        }
+    }
+
+    // 環境変数(%NAME%)だけを展開する。shell:はそのまま残す
+    pub fn expand_env(path: &str) -> String {
+        expand_env_vars(path, |name| std::env::var(name).ok())
+    }
+
+    // 既知フォルダの正規名(shell:の後ろ)からパースネームを得る。仮想フォルダは"::{CLSID}"形式になる
+    fn known_folder_parse_name(name: &str) -> Option<String> {
+        let kfm: IKnownFolderManager = unsafe { CoCreateInstance(&KnownFolderManager, None, CLSCTX_INPROC_SERVER) }.ok()?;
+        let kf = unsafe { kfm.GetFolderByName(WSTR::from(name).PCWSTR()) }.ok()?;
+        let pidl = ItemIDList(unsafe { kf.GetIDList(0) }.ok()?); // auto drop resource
+        let p = unsafe { SHGetNameFromIDList(pidl.0, SIGDN_DESKTOPABSOLUTEPARSING) }.ok()?;
+        let s = unsafe { p.to_string() }.ok();
+        unsafe { CoTaskMemFree(Some(p.0 as _)); }
+        s
+    }
+
+    pub fn bind(hotkey: Rc<PropertyHolder>) -> Result<ObjectHolder> {
+        let (ishell_folder, str_parse_name) = Self::parse_object(&Self::expand_path(&hotkey.path))?;
+        Self::enumerate(ishell_folder, str_parse_name, Some(hotkey), true)
     }
 
     pub fn child(&mut self, idx: usize) -> Result<ObjectHolder> {
@@ -160,7 +224,12 @@ impl ObjectHolder {
             Self::get_object_name(&self.ishell_folder.as_ref().unwrap(), self.list_items[idx].idl_rel.0, SHGDN_FORPARSING)
             .to_string_null_search();
 
-        Self::enumerate(ishell_child, str_parse_name, None)
+        Self::enumerate(ishell_child, str_parse_name, self.launch.clone(), false)
+    }
+
+    // 手動の並び順を保存する名前(ホットキーの並び順プロファイルがあればプロファイル別)
+    pub fn order_path(&self) -> String {
+        PropertyHolder::profile_path(&self.parse_name, &self.param.sort_profile)
     }
 
     pub fn get_object_name(isf: &IShellFolder, pidl: *const Common::ITEMIDLIST, uflags: SHGDNF) -> WSTR {
@@ -173,7 +242,92 @@ impl ObjectHolder {
         })().or(Some(WSTR::new(1))).unwrap()
     }
 
-    pub fn set_object_name(path: &str, oldname: &str, newname: &str) -> Result<()> {
+    // ファイルシステム上のアイテムはPIDLに保持された更新日時を返す(I/Oは発生しない)
+    fn get_object_mtime(isf: &IShellFolder, pidl: *const ITEMIDLIST) -> u64 {
+        let mut fd = WIN32_FIND_DATAW::default();
+        let r = unsafe { SHGetDataFromIDListW(isf, pidl, SHGDFIL_FINDDATA, &mut fd as *mut _ as _, std::mem::size_of::<WIN32_FIND_DATAW>() as i32) };
+        if r.is_err() { return 0 }
+        (fd.ftLastWriteTime.dwHighDateTime as u64) << u32::BITS | fd.ftLastWriteTime.dwLowDateTime as u64
+    }
+
+    // アイコンキャッシュのキー(フォルダのパース名＋アイテムのパース名)
+    pub fn icon_key(&self, idx: usize) -> String {
+        format!("{}\\{}", self.parse_name, self.list_items[idx].str_parse_name.to_string_null_search())
+    }
+
+    // アイテムの絶対PIDL(別スレッドへ渡す用)
+    pub fn abs_pidl(&self, idx: usize) -> Option<ItemIDList> {
+        if self.list_items[idx].idl_rel.0.is_null() { return None }
+        let pidl_parent = ItemIDList(unsafe { SHGetIDListFromObject(self.ishell_folder.as_ref()?) }.ok()?); // auto drop resource
+        let pidl = unsafe { ILCombine(Some(pidl_parent.0), Some(self.list_items[idx].idl_rel.0)) };
+        if pidl.is_null() { None } else { Some(ItemIDList(pidl)) }
+    }
+
+    // 再列挙して現在の並び(名前変更は新名へ読み替え)を保ったまま差分を取り込む。新規アイテムは末尾
+    pub fn refresh(&mut self, renames: &[(String, String)]) -> Result<()> {
+        let isf = self.ishell_folder.clone().ok_or(Error::OK)?;
+        let fresh = Self::enumerate(isf, self.parse_name.clone(), self.launch.clone(), self.b_root)?;
+
+        let mut map_old = FxHashMap::<String, ObjectItem>::default();
+        let mut order = Vec::<String>::with_capacity(self.list_items.len());
+        for i in self.list_items.drain(..) {
+            let name = i.str_parse_name.to_string_null_search();
+            if name.is_empty() { continue } // 空フォルダの表示用アイテム
+            order.push(name.clone());
+            map_old.insert(name, i);
+        }
+        for (old, new) in renames { // アイコン引継ぎ用
+            if let Some(i) = map_old.remove(old) { map_old.insert(new.clone(), i); }
+        }
+
+        let mut map_new = FxHashMap::<String, ObjectItem>::default();
+        let mut vec_parsename = Vec::<String>::with_capacity(fresh.list_items.len());
+        for i in fresh.list_items.into_iter() {
+            let name = i.str_parse_name.to_string_null_search();
+            vec_parsename.push(name.clone());
+            map_new.insert(name, i);
+        }
+
+        // 手動以外の並び順では再列挙した順(設定に従って並べ替え済み)をそのまま使う
+        if self.param.sort_mode != SortMode::Manual { order.clear(); }
+        for name in PropertyHolder::reconcile_filesort(&order, renames, &vec_parsename).iter() {
+            if let Some(mut i) = map_new.remove(name) {
+                if let Some(o) = map_old.get(name) { // 更新されていなければ読込済みのアイコンを引き継ぐ
+                    if o.mtime == i.mtime { (i.icon_lr, i.icon_sm) = (o.icon_lr.clone(), o.icon_sm.clone()); }
+                }
+                self.list_items.push(i);
+            }
+        }
+        Ok(())
+    }
+
+    // 変更通知(SHChangeNotification_Lock)を読み、このフォルダ内での名前変更なら(旧名, 新名)を返す
+    pub fn read_change_notify(&self, wparam: WPARAM, lparam: LPARAM) -> Option<(String, String)> {
+        let mut ppidl = std::ptr::null_mut::<*mut ITEMIDLIST>();
+        let mut event = 0i32;
+        let hlock = unsafe { SHChangeNotification_Lock(HANDLE(wparam.0 as isize), lparam.0 as u32, Some(&mut ppidl), Some(&mut event)) };
+        if hlock.0 == 0 { return None }
+
+        let r = (|| {
+            if event as u32 & (SHCNE_RENAMEITEM.0 | SHCNE_RENAMEFOLDER.0) == 0 || ppidl.is_null() { return None }
+            let pidls = unsafe { std::slice::from_raw_parts(ppidl, 2) };
+            Some((self.child_name_of(pidls[0])?, self.child_name_of(pidls[1])?))
+        })();
+        unsafe { SHChangeNotification_Unlock(hlock); }
+        r
+    }
+
+    // 絶対PIDLがこのフォルダ直下のアイテムならパース名を返す
+    fn child_name_of(&self, pidl: *const ITEMIDLIST) -> Option<String> {
+        let isf = self.ishell_folder.as_ref()?;
+        let pidl_parent = ItemIDList(unsafe { SHGetIDListFromObject(isf) }.ok()?); // auto drop resource
+        if !unsafe { ILIsParent(pidl_parent.0, pidl, TRUE) }.as_bool() { return None }
+        let name = Self::get_object_name(isf, unsafe { ILFindLastID(pidl) }, SHGDNF(SHGDN_FORPARSING.0 | SHGDN_INFOLDER.0));
+        Some(name.to_string_null_search())
+    }
+
+    // 変更後のパース名を返す(拡張子の扱いなどで入力した名前と異なることがある)
+    pub fn set_object_name(path: &str, oldname: &str, newname: &str) -> Result<String> {
         let (ishell_folder, _) = Self::parse_object(path)?;
 
         let mut eaten = 0u32;
@@ -183,9 +337,11 @@ impl ObjectHolder {
             ishell_folder.ParseDisplayName(None, None, WSTR::from(oldname).PCWSTR(), Some(&mut eaten),
             &mut itemlist[0] as *mut *const ITEMIDLIST as _, &mut attr)?;
             let mut _pidl = ItemIDList(itemlist[0] as _); // auto drop resource
-            ishell_folder.SetNameOf(None, itemlist[0], WSTR::from(newname).PCWSTR(), SHGDN_NORMAL, None)?;
+            let mut pidl_new = std::ptr::null_mut::<ITEMIDLIST>();
+            ishell_folder.SetNameOf(None, itemlist[0], WSTR::from(newname).PCWSTR(), SHGDN_NORMAL, Some(&mut pidl_new))?;
+            let _pidl_new = ItemIDList(pidl_new); // auto drop resource
+            Ok(Self::get_object_name(&ishell_folder, pidl_new, SHGDNF(SHGDN_FORPARSING.0 | SHGDN_INFOLDER.0)).to_string_null_search())
         }
-        Ok(())
     }
 
     pub fn get_ui_object_of<T>(&self, handle: HWND, idx: i32) -> Result<T>
@@ -284,6 +440,60 @@ impl ObjectHolder {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+/// アイコン読込スレッド(STA)。読み込んだ順にWMU_ICON_LOADEDで通知する
+////////////////////////////////////////////////////////////////////////////////
+pub struct IconLoader { _worker: StaWorker } // Dropで読込を中断
+
+impl IconLoader {
+    pub fn start(hwnd: HWND, jobs: Vec<(String, ItemIDList)>) -> Self {
+        let jobs = jobs.into_iter().map(|(k, pidl)| { // 生ポインタはSendでないため数値で渡す
+            let p = pidl.0 as usize;
+            std::mem::forget(pidl);
+            (k, p)
+        }).collect::<Vec<_>>();
+
+        Self { _worker: StaWorker::spawn(move |cancel| {
+            for (key, p) in jobs {
+                let pidl = ItemIDList(p as _); // auto drop resource
+                if cancel.load(Ordering::Relaxed) { continue; } // 残りのPIDLは解放のみ
+
+                let mut pidl_last = std::ptr::null_mut::<ITEMIDLIST>();
+                let r: Result<IShellFolder> = unsafe { SHBindToParent(pidl.0, Some(&mut pidl_last)) };
+                let (icon_lr, icon_sm) = if let Ok(isf) = r {
+                    Icon::load_file_icon(&isf, pidl_last)
+                } else {
+                    Icon::load_stock_icon(SIID_APPLICATION)
+                };
+                post_boxed(hwnd, WMU_ICON_LOADED, WPARAM(0), Box::new(IconLoaded { key, icon_lr, icon_sm }));
+            }
+        }) }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// フォルダの変更通知。WMU_SHELL_CHANGEで通知され、Dropで登録解除する
+////////////////////////////////////////////////////////////////////////////////
+pub struct ShellChangeNotify(u32);
+
+impl Drop for ShellChangeNotify {
+    fn drop(&mut self) {
+        let _ = unsafe { SHChangeNotifyDeregister(self.0) };
+    }
+}
+
+impl ShellChangeNotify {
+    pub fn regist(hwnd: HWND, obj: &ObjectHolder) -> Option<Self> {
+        let pidl = ItemIDList(unsafe { SHGetIDListFromObject(obj.ishell_folder.as_ref()?) }.ok()?); // auto drop resource
+        let entry = SHChangeNotifyEntry { pidl: pidl.0, fRecursive: FALSE };
+        let events = SHCNE_CREATE.0 | SHCNE_DELETE.0 | SHCNE_MKDIR.0 | SHCNE_RMDIR.0 | SHCNE_RENAMEITEM.0 | SHCNE_RENAMEFOLDER.0
+            | SHCNE_UPDATEDIR.0 | SHCNE_UPDATEITEM.0 | SHCNE_ATTRIBUTES.0;
+        let id = unsafe { SHChangeNotifyRegister(hwnd, SHCNRF_ShellLevel | SHCNRF_InterruptLevel | SHCNRF_NewDelivery,
+            events as i32, WMU_SHELL_CHANGE, 1, &entry) };
+        if id == 0 { None } else { Some(Self(id)) }
+    }
+}
+
 pub trait DropTargetWindow {
     fn get_handle(&self) -> HWND;
     fn get_droptarget(&mut self, mx: i32, my: i32, b_enter: bool) -> (Result<()>, Option<IDropTarget>);
@@ -481,6 +691,111 @@ impl IDropSource_Impl for MyDropSource {
     }
 }
 
+// 並べ替えに使うObjectItemのメタデータ
+struct SortKey {
+    b_folder: bool,
+    disp_name: String,
+    parse_name: String,
+    mtime: u64,
+}
+
+impl SortKey {
+    fn of(i: &ObjectItem) -> Self {
+        Self {
+            b_folder: i.attr & SFGAO_FOLDER.0 != 0,
+            disp_name: i.str_disp_name.to_string_null_search(),
+            parse_name: i.str_parse_name.to_string_null_search(),
+            mtime: i.mtime,
+        }
+    }
+
+    // 拡張子(小文字)。フォルダは空
+    fn extension(&self) -> String {
+        if self.b_folder { return String::default() }
+        self.parse_name.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default()
+    }
+
+    // 並び順の設定に従った比較(Manualは順序を変えない)。更新日時は新しい順
+    fn cmp_by(&self, other: &Self, mode: SortMode, b_folders_first: bool) -> cmp::Ordering {
+        if b_folders_first && self.b_folder != other.b_folder {
+            return if self.b_folder { cmp::Ordering::Less } else { cmp::Ordering::Greater }
+        }
+        let by_natural = || natural_cmp(&self.disp_name, &other.disp_name);
+        match mode {
+            SortMode::Manual => cmp::Ordering::Equal,
+            SortMode::Name => self.disp_name.to_lowercase().cmp(&other.disp_name.to_lowercase()),
+            SortMode::NaturalName => by_natural(),
+            SortMode::Type => self.extension().cmp(&other.extension()).then_with(by_natural),
+            SortMode::Modified => other.mtime.cmp(&self.mtime).then_with(by_natural),
+        }
+    }
+}
+
+// 数字の並びを数値として比較する名前順(大文字小文字は区別しない)。"file2" < "file10"
+fn natural_cmp(a: &str, b: &str) -> cmp::Ordering {
+    fn take_digits(it: &mut std::iter::Peekable<std::str::Chars>) -> String {
+        let mut s = String::default();
+        while let Some(c) = it.next_if(|c| c.is_ascii_digit()) { s.push(c); }
+        s
+    }
+
+    let (mut ia, mut ib) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let o = match (ia.peek().copied(), ib.peek().copied()) {
+            (None, None) => return cmp::Ordering::Equal,
+            (None, Some(_)) => return cmp::Ordering::Less,
+            (Some(_), None) => return cmp::Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let (da, db) = (take_digits(&mut ia), take_digits(&mut ib));
+                let (ta, tb) = (da.trim_start_matches('0'), db.trim_start_matches('0'));
+                ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb))
+            }
+            (Some(ca), Some(cb)) => {
+                ia.next();
+                ib.next();
+                ca.to_lowercase().cmp(cb.to_lowercase())
+            }
+        };
+        if o != cmp::Ordering::Equal { return o }
+    }
+}
+
+// %NAME%を置き換える。未定義の変数は%NAME%のまま残し、名前として不正な箇所の%は文字として扱う
+fn expand_env_vars(s: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('%') {
+        ret.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name = after.find('%').map(|end| &after[..end]).filter(|n| !n.is_empty() && !n.contains(['\\', '/', ':']));
+        match name {
+            Some(name) => {
+                match lookup(name) {
+                    Some(v) => ret.push_str(&v),
+                    None => { ret.push('%'); ret.push_str(name); ret.push('%'); }
+                }
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                ret.push('%');
+                rest = after;
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+// "shell:既知フォルダ名\以下のパス"を(既知フォルダ名, "\以下のパス")に分ける。"shell:::{CLSID}"はParseDisplayNameに任せる
+fn split_shell_moniker(s: &str) -> Option<(&str, &str)> {
+    if !s.get(..6)?.eq_ignore_ascii_case("shell:") { return None }
+    let body = &s[6..];
+    if body.starts_with("::") { return None }
+    let end = body.find(['\\', '/']).unwrap_or(body.len());
+    if end == 0 { return None }
+    Some((&body[..end], &body[end..]))
+}
+
 fn get_global_data_dword(dobj: &IDataObject, str_format: &str) -> Result<u32> {
     let mut fe = FORMATETC::default();
     fe.cfFormat = unsafe { RegisterClipboardFormatW(WSTR::from(str_format).PCWSTR()) } as u16;
@@ -497,3 +812,86 @@ fn get_global_data_dword(dobj: &IDataObject, str_format: &str) -> Result<u32> {
     unsafe { ReleaseStgMedium(&mut sm); }
 	Ok(dw)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, b_folder: bool, mtime: u64) -> SortKey {
+        SortKey {
+            b_folder,
+            disp_name: name.rsplit_once('.').map_or(name, |(n, _)| n).to_string(), // 拡張子は非表示
+            parse_name: name.to_string(),
+            mtime,
+        }
+    }
+
+    fn sorted(items: &mut Vec<SortKey>, mode: SortMode, b_folders_first: bool) -> Vec<String> {
+        items.sort_by(|a, b| a.cmp_by(b, mode, b_folders_first));
+        items.iter().map(|i| i.parse_name.clone()).collect()
+    }
+
+    #[test]
+    fn natural_cmp_orders_numbers_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), cmp::Ordering::Less);
+        assert_eq!(natural_cmp("File10", "file9"), cmp::Ordering::Greater);
+        assert_eq!(natural_cmp("a007", "a7"), cmp::Ordering::Equal);
+        assert_eq!(natural_cmp("abc", "ABD"), cmp::Ordering::Less);
+        assert_eq!(natural_cmp("ab", "abc"), cmp::Ordering::Less);
+    }
+
+    #[test]
+    fn cmp_by_sorts_with_folders_first_option() {
+        let mut v = vec![item("b10.txt", false, 3), item("Tools", true, 1), item("b9.exe", false, 2), item("a.txt", false, 5)];
+        assert_eq!(sorted(&mut v, SortMode::Name, true), ["Tools", "a.txt", "b10.txt", "b9.exe"]);
+        assert_eq!(sorted(&mut v, SortMode::NaturalName, true), ["Tools", "a.txt", "b9.exe", "b10.txt"]);
+        assert_eq!(sorted(&mut v, SortMode::NaturalName, false), ["a.txt", "b9.exe", "b10.txt", "Tools"]);
+    }
+
+    #[test]
+    fn cmp_by_type_and_modified() {
+        let mut v = vec![item("b.txt", false, 3), item("Tools", true, 1), item("c.exe", false, 2), item("a.txt", false, 5)];
+        assert_eq!(sorted(&mut v, SortMode::Type, false), ["Tools", "c.exe", "a.txt", "b.txt"]);
+        assert_eq!(sorted(&mut v, SortMode::Modified, false), ["a.txt", "b.txt", "c.exe", "Tools"]);
+        assert_eq!(sorted(&mut v, SortMode::Modified, true), ["Tools", "a.txt", "b.txt", "c.exe"]);
+    }
+
+    #[test]
+    fn cmp_by_manual_keeps_order() {
+        let mut v = vec![item("b.txt", false, 0), item("Tools", true, 0), item("a.txt", false, 0)];
+        assert_eq!(sorted(&mut v, SortMode::Manual, false), ["b.txt", "Tools", "a.txt"]);
+    }
+
+    fn env(name: &str) -> Option<String> {
+        match name.to_uppercase().as_str() {
+            "USERPROFILE" => Some(r"C:\Users\me".to_string()),
+            "ONEDRIVE" => Some(r"C:\Users\me\OneDrive".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_env_vars_replaces_defined_names() {
+        assert_eq!(expand_env_vars(r"%USERPROFILE%\Launcher", env), r"C:\Users\me\Launcher");
+        assert_eq!(expand_env_vars(r"%OneDrive%\Bookmarks", env), r"C:\Users\me\OneDrive\Bookmarks");
+        assert_eq!(expand_env_vars(r"D:\tools", env), r"D:\tools");
+    }
+
+    #[test]
+    fn expand_env_vars_keeps_undefined_and_stray_percent() {
+        assert_eq!(expand_env_vars(r"%UNDEF%%USERPROFILE%", env), r"%UNDEF%C:\Users\me");
+        assert_eq!(expand_env_vars(r"D:\100%\%USERPROFILE%", env), r"D:\100%\C:\Users\me");
+        assert_eq!(expand_env_vars(r"D:\50%", env), r"D:\50%");
+        assert_eq!(expand_env_vars(r"%%", env), r"%%");
+    }
+
+    #[test]
+    fn split_shell_moniker_parses_known_folder_name() {
+        assert_eq!(split_shell_moniker("shell:Start Menu"), Some(("Start Menu", "")));
+        assert_eq!(split_shell_moniker(r"SHELL:Personal\Launcher"), Some(("Personal", r"\Launcher")));
+        assert_eq!(split_shell_moniker("shell:::{20D04FE0-3AEA-1069-A2D8-08002B30309D}"), None);
+        assert_eq!(split_shell_moniker(r"shell:\x"), None);
+        assert_eq!(split_shell_moniker(r"C:\shell:x"), None);
+        assert_eq!(split_shell_moniker("sh"), None);
+    }
+}
@@ -2,30 +2,33 @@ use std::collections::VecDeque;
 use fxhash::{FxHashMap, FxHashSet};
 use windows::Win32::{
     Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED}, Storage::{EnhancedStorage::PKEY_AppUserModel_ID, FileSystem::FILE_FLAGS_AND_ATTRIBUTES},
-    System::{Com::StructuredStorage::PropVariantClear, Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION}},
+    Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY},
+    System::{Com::{CoTaskMemFree, StructuredStorage::PropVariantClear}, Threading::{GetCurrentProcessId, OpenProcess, OpenProcessToken, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION}},
     UI::Shell::{Common::ITEMIDLIST, PropertiesSystem::{IPropertyStore, SHGetPropertyStoreForWindow}}
 };
 
 use super::*;
-use self::{lib_gui_layout_container::sys_metrics_without_wnd, lib_property::PropertyHolder};
+use self::{lib_gui_layout_container::sys_metrics_without_wnd, lib_icon_cache::IconPixels, lib_pattern::PathPattern, lib_shell::ObjectHolder, lib_property::{ProcHistory, PropertyHolder, WinFilterRule, WinFilterTarget, WinSlot}};
 
 const WINLIST_IGNORE: [&str; 2] = ["Progman", "Internet Explorer_Hidden"];
+const WINICON_TIMEOUT: u32 = 200; // ウィンドウ毎のアイコン問い合わせ上限(ms)
+// 1つのプロセスで別々のAppUserModelIDを持つウィンドウを出すもの(UWPのホスト、ブラウザーのPWA・プロファイル)
+const AUMID_PER_WINDOW_HOSTS: [&str; 3] = ["ApplicationFrameHost.exe", "chrome.exe", "msedge.exe"];
+
+thread_local! {
+    // AppUserModelID → 表示名(シェルへの問い合わせはIDごとに一度だけ)
+    static AUMID_NAMES: RefCell<FxHashMap<String, String>> = RefCell::default();
+}
 
 impl Icon {
     fn get_uwp_icon(hwnd: HWND) -> Result<isize> {
-        let i_property_store: IPropertyStore = unsafe { SHGetPropertyStoreForWindow(hwnd) }?;
-        let mut pv = unsafe { i_property_store.GetValue(&PKEY_AppUserModel_ID) }?;
-        let mut str = WSTR::from_slice_null_search(unsafe { pv.Anonymous.Anonymous.Anonymous.bstrVal.as_wide() });
-        unsafe { PropVariantClear(&mut pv) }?;
+        let path = Self::get_uwp_icon_path(hwnd)?;
+        Ok(Self::get_shell_file_icon(WSTR::from(&path).PCWSTR(), -1))
+    }
 
-        if str.0.len() > 1 {
-            let mut buf2 = WSTR::from(r"shell:AppsFolder\");
-            buf2.0.pop(); // NULL終端をとる
-            buf2.0.append(&mut str.0);
-            Ok(Self::get_shell_file_icon(buf2.PCWSTR(), -1))
-        } else {
-            Err(Error::OK)
-        }
+    // AppUserModelIDを持つウィンドウのアイコン取得元("shell:AppsFolder\AUMID")
+    pub fn get_uwp_icon_path(hwnd: HWND) -> Result<String> {
+        WindowInfo::get_aumid(hwnd).map(|v| WindowInfo::aumid_shell_path(&v))
     }
 
     // index:-1 => file icon
@@ -47,16 +50,17 @@ impl Icon {
         let mut h = 0isize;
 
         if hwnd.0 != 0 {
-            unsafe { SendMessageTimeoutW(hwnd, WM_GETICON, WPARAM(2), LPARAM(0), SMTO_ABORTIFHUNG | SMTO_BLOCK, 500, Some(&mut h as *mut _ as _)) };
-            if h == 0 {
-                unsafe { SendMessageTimeoutW(hwnd, WM_GETICON, WPARAM(0), LPARAM(0), SMTO_ABORTIFHUNG | SMTO_BLOCK, 500, Some(&mut h as *mut _ as _)) };
+            unsafe { SendMessageTimeoutW(hwnd, WM_GETICON, WPARAM(2), LPARAM(0), SMTO_ABORTIFHUNG | SMTO_BLOCK, WINICON_TIMEOUT, Some(&mut h as *mut _ as _)) };
+            if h == 0 && !unsafe { IsHungAppWindow(hwnd) }.as_bool() {
+                unsafe { SendMessageTimeoutW(hwnd, WM_GETICON, WPARAM(0), LPARAM(0), SMTO_ABORTIFHUNG | SMTO_BLOCK, WINICON_TIMEOUT, Some(&mut h as *mut _ as _)) };
             }
             if h == 0 {
                 h = Self::get_uwp_icon(hwnd).unwrap_or(0);
             }
         }
         if h == 0 {
-            h = Self::get_shell_file_icon(WSTR::from(proc_img_fname).PCWSTR(), 0);
+            let index = if proc_img_fname.starts_with("shell:") { -1 } else { 0 }; // UWPなどAppUserModelIDのアイコン取得元
+            h = Self::get_shell_file_icon(WSTR::from(proc_img_fname).PCWSTR(), index);
         }
         if h == 0 && hwnd.0 != 0 {
             h = unsafe { GetClassLongPtrW(hwnd, GCLP_HICONSM) } as isize;
@@ -87,18 +91,61 @@ impl Icon {
             unsafe {iext_icon.Extract(PCWSTR::from_raw(&buf as _), index as u32, Some(&mut h_icon_lr), Some(&mut h_icon_sm), (w_sm << u16::BITS | w_lr) as u32)}?;
             if h_icon_lr.0 != 0isize { Ok(()) } else { Err(Error::OK) }
 
-        })().or_else(|_| {
-            let mut sii = SHSTOCKICONINFO::default();
-            sii.cbSize = std::mem::size_of::<SHSTOCKICONINFO>() as u32;
-            let _ = unsafe { SHGetStockIconInfo(SIID_APPLICATION , SHGSI_ICON | SHGSI_SMALLICON, &mut sii) }?;
-            h_icon_sm = sii.hIcon;
-            unsafe { SHGetStockIconInfo(SIID_APPLICATION , SHGSI_ICON | SHGSI_LARGEICON, &mut sii) }?;
-            h_icon_lr = sii.hIcon;
-            Ok(())
-        });
+        })();
 
+        if h_icon_lr.0 == 0 {
+            let _ = Icon(h_icon_sm); // auto drop resource
+            return Self::load_stock_icon(SIID_APPLICATION)
+        }
         (Icon(h_icon_lr), Icon(h_icon_sm))
     }
+
+    pub fn load_stock_icon(siid: SHSTOCKICONID) -> (Icon, Icon) {
+        let mut sii = SHSTOCKICONINFO::default();
+        sii.cbSize = std::mem::size_of::<SHSTOCKICONINFO>() as u32;
+        let _ = unsafe { SHGetStockIconInfo(siid, SHGSI_ICON | SHGSI_SMALLICON, &mut sii) };
+        let h_icon_sm = sii.hIcon;
+        sii.hIcon = HICON(0);
+        let _ = unsafe { SHGetStockIconInfo(siid, SHGSI_ICON | SHGSI_LARGEICON, &mut sii) };
+        (Icon(sii.hIcon), Icon(h_icon_sm))
+    }
+
+    // 保存用にピクセル列にする。アルファを持たないアイコンはマスクから透過を補う
+    pub fn to_pixels(&self) -> Option<IconPixels> {
+        let mut ii = ICONINFO::default();
+        unsafe { GetIconInfo(self.0, &mut ii) }.ok()?;
+        let (hbm_color, hbm_mask) = (Bitmap(ii.hbmColor), Bitmap(ii.hbmMask)); // auto drop resource
+        if hbm_color.0.0 == 0 { return None } // モノクロアイコンは保存しない
+
+        let mut bm = BITMAP::default();
+        if unsafe { GetObjectW(hbm_color.0, std::mem::size_of::<BITMAP>() as i32, Some(&mut bm as *mut _ as _)) } == 0 { return None }
+        let (w, h) = (bm.bmWidth, bm.bmHeight);
+        let hdc = unsafe { GetDC(None) };
+        let dib_bits = |hbm: HBITMAP| {
+            let mut bi = BITMAPINFO { bmiHeader: BITMAPINFOHEADER { biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32, biWidth: w, biHeight: -h, // 上から下
+                biPlanes: 1, biBitCount: 32, biCompression: BI_RGB.0, ..Default::default() }, ..Default::default() };
+            let mut buf = vec![0u8; (w * h * 4) as usize];
+            let n = unsafe { GetDIBits(hdc, hbm, 0, h as u32, Some(buf.as_mut_ptr() as _), &mut bi, DIB_RGB_COLORS) };
+            if n == h { Some(buf) } else { None }
+        };
+        let (color, mask) = (dib_bits(hbm_color.0), dib_bits(hbm_mask.0));
+        unsafe { ReleaseDC(None, hdc); }
+
+        let mut bgra = color?;
+        if bgra.chunks(4).all(|p| p[3] == 0) {
+            for (p, m) in bgra.chunks_mut(4).zip(mask?.chunks(4)) { p[3] = if m[0] == 0 { 0xff } else { 0 }; }
+        }
+        Some(IconPixels { w, h, bgra })
+    }
+
+    pub fn from_pixels(px: &IconPixels) -> Option<Icon> {
+        let color = Bitmap(unsafe { CreateBitmap(px.w, px.h, 1, 32, Some(px.bgra.as_ptr() as _)) }); // auto drop resource
+        let mask_bits = vec![0u8; ((px.w + 15) / 16 * 2 * px.h) as usize]; // 透過はアルファで表す
+        let mask = Bitmap(unsafe { CreateBitmap(px.w, px.h, 1, 1, Some(mask_bits.as_ptr() as _)) });
+        if color.0.0 == 0 || mask.0.0 == 0 { return None }
+        let ii = ICONINFO { fIcon: TRUE, hbmMask: mask.0, hbmColor: color.0, ..Default::default() };
+        unsafe { CreateIconIndirect(&ii) }.ok().map(Icon)
+    }
 }
 
 #[derive(Clone)]
@@ -106,20 +153,171 @@ pub struct WindowInfo {
     pub group_type: u32, // 0-プロセス自動・ソート固定、1-プロセス自動・ソート非固定、2-テンポラリ
     pub handle: HWND,
     pub proc_img_fname: String,
+    pub aumid: String, // AppUserModelID(無ければ空)。あればプロセスイメージの代わりにグループ化の単位とする
     pub title: String,
+    pub disp_title: String, // 表示用のタイトル(タイトル書き換えルールの適用結果)
+    pub pid: u32,
+    pub b_hung: bool, // 応答なし
+}
+
+// ツールチップに表示するウィンドウの状態(表示する時点で取得する)
+#[derive(Default)]
+pub struct WindowState {
+    pub b_minimized: bool,
+    pub b_maximized: bool,
+    pub b_topmost: bool,
+    pub b_elevated: bool, // 管理者権限で動作中
+    pub monitor: String, // 表示先のモニタ(デバイス名と解像度)
+}
+
+pub struct WinIconLoaded {
+    pub handle: isize,
+    pub icon_source: String, // WindowInfo::icon_source(アイコンキャッシュのキー)
+    pub icon: Icon,
+}
+
+// ウィンドウアイコンをバックグラウンドで取得する。取得毎にWMU_WINICON_LOADEDを送る
+pub struct WinIconLoader {
+    _worker: StaWorker, // Dropで読込を中断
+    pending: FxHashSet<isize>, // まだ受け取っていないウィンドウ
+}
+
+impl WinIconLoader {
+    pub fn start(hwnd: HWND, jobs: Vec<(isize, String)>) -> Self {
+        let pending = jobs.iter().map(|(handle, _)| *handle).collect();
+        Self { pending, _worker: StaWorker::spawn(move |cancel| {
+            for (handle, icon_source) in jobs {
+                if cancel.load(std::sync::atomic::Ordering::Relaxed) { break }
+                let icon = Icon::load_win_icon(HWND(handle), &icon_source);
+                // WM_GETICONの結果は相手ウィンドウの所有物のため、キャッシュ用に複製する
+                let icon = match unsafe { CopyIcon(icon.0) } { Ok(h) => Icon(h), Err(_) => icon };
+                post_boxed(hwnd, WMU_WINICON_LOADED, WPARAM(0), Box::new(WinIconLoaded { handle, icon_source, icon }));
+            }
+        }) }
+    }
+
+    // 受け取ったウィンドウを記録する。すべて受け取り終えたらtrue
+    pub fn received(&mut self, handle: isize) -> bool {
+        self.pending.remove(&handle);
+        self.pending.is_empty()
+    }
 }
 
 impl WindowInfo {
+    // グループ化・履歴・ソート設定候補の単位(AppUserModelID、無ければプロセスイメージ)
+    pub fn group_key(&self) -> &str {
+        if self.aumid.is_empty() { &self.proc_img_fname } else { &self.aumid }
+    }
+
+    // ウィンドウのアイコン取得元。AppUserModelIDを持つものはアプリ単位で取得する
+    pub fn icon_source(&self) -> String {
+        if self.aumid.is_empty() { self.proc_img_fname.clone() } else { Self::aumid_shell_path(&self.aumid) }
+    }
+
+    // ソート順の指定を探す。AppUserModelIDに一致する指定を優先し、無ければプロセスイメージで照合する
+    pub fn sort_score(&self, sortlist: &Vec<WinSortList>) -> Option<usize> {
+        (!self.aumid.is_empty()).then(|| PropertyHolder::contains_window_sort_list(sortlist, &self.aumid)).flatten()
+            .or_else(|| PropertyHolder::contains_window_sort_list(sortlist, &self.proc_img_fname))
+    }
+
+    // グループのキーがAppUserModelIDか(パス区切りを含まず、ワイルドカード・正規表現でもない)
+    pub fn is_aumid(key: &str) -> bool {
+        !key.is_empty() && !key.contains(['\\', '/', ':']) && !PathPattern::is_pattern(key)
+    }
+
+    pub fn aumid_shell_path(aumid: &str) -> String {
+        format!(r"shell:AppsFolder\{}", aumid)
+    }
+
+    // ウィンドウのプロパティストアからAppUserModelIDを読む(メッセージは送らない)
+    pub fn get_aumid(hwnd: HWND) -> Result<String> {
+        let i_property_store: IPropertyStore = unsafe { SHGetPropertyStoreForWindow(hwnd) }?;
+        let mut pv = unsafe { i_property_store.GetValue(&PKEY_AppUserModel_ID) }?;
+        let aumid = String::from_utf16_lossy(unsafe { pv.Anonymous.Anonymous.Anonymous.bstrVal.as_wide() });
+        unsafe { PropVariantClear(&mut pv) }?;
+
+        if !aumid.is_empty() { Ok(aumid) } else { Err(Error::OK) }
+    }
+
+    // AppUserModelIDからアプリの表示名を得る。解決できなければIDのまま
+    pub fn app_display_name(aumid: &str) -> String {
+        AUMID_NAMES.with(|m| m.borrow_mut().entry(aumid.to_string()).or_insert_with(|| {
+            let r = (|| -> Result<String> {
+                let item: IShellItem = unsafe { SHCreateItemFromParsingName(WSTR::from(&Self::aumid_shell_path(aumid)).PCWSTR(), None) }?;
+                let p = unsafe { item.GetDisplayName(SIGDN_NORMALDISPLAY) }?;
+                let name = unsafe { p.to_string() }.unwrap_or_default();
+                unsafe { CoTaskMemFree(Some(p.0 as _)); }
+                if !name.is_empty() { Ok(name) } else { Err(Error::OK) }
+            })();
+            r.unwrap_or_else(|_| aumid.to_string())
+        }).clone())
+    }
+
+    // プロセスイメージのファイル名部分
+    pub fn proc_name(&self) -> &str {
+        self.proc_img_fname.rsplit(['\\', '/']).next().unwrap_or(&self.proc_img_fname)
+    }
+
+    // 同じプロセスの他のウィンドウとAppUserModelIDが異なることがあるか
+    fn aumid_per_window(&self) -> bool {
+        AUMID_PER_WINDOW_HOSTS.iter().any(|h| h.eq_ignore_ascii_case(self.proc_name()))
+    }
+
+    pub fn get_state(&self) -> WindowState {
+        let exstyle = WINDOW_EX_STYLE(unsafe { GetWindowLongPtrW(self.handle, GWL_EXSTYLE) } as u32);
+        WindowState {
+            b_minimized: unsafe { IsIconic(self.handle) }.as_bool(),
+            b_maximized: unsafe { IsZoomed(self.handle) }.as_bool(),
+            b_topmost: exstyle.contains(WS_EX_TOPMOST),
+            b_elevated: Self::is_elevated(self.pid).unwrap_or(false),
+            monitor: Self::monitor_name(self.handle).unwrap_or_default(),
+        }
+    }
+
+    fn is_elevated(pid: u32) -> Result<bool> {
+        let hp = Handle(unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) }?); // auto drop resource
+        let mut ht = Handle(HANDLE(0));
+        unsafe { OpenProcessToken(hp.0, TOKEN_QUERY, &mut ht.0) }?;
+        let mut te = TOKEN_ELEVATION::default();
+        let mut len = 0u32;
+        unsafe { GetTokenInformation(ht.0, TokenElevation, Some(&mut te as *mut _ as _), std::mem::size_of::<TOKEN_ELEVATION>() as u32, &mut len) }?;
+        Ok(te.TokenIsElevated != 0)
+    }
+
+    fn monitor_name(hwnd: HWND) -> Result<String> {
+        let h = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        let mut mi = MONITORINFOEXW::default();
+        mi.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if !unsafe { GetMonitorInfoW(h, &mut mi as *mut _ as *mut MONITORINFO) }.as_bool() { return Err(Error::OK) }
+        let rc = mi.monitorInfo.rcMonitor;
+        let len = mi.szDevice.iter().position(|c| *c == 0).unwrap_or(mi.szDevice.len());
+        Ok(format!("{} ({}x{})", WSTR::from_slice_to_string(&mi.szDevice, len), rc.right - rc.left, rc.bottom - rc.top))
+    }
+
+    // ツールチップの文字列(書き換え前のタイトル、プロセス、PID、状態、モニタ、AppUserModelID)
+    pub fn tooltip_text(&self, st: &WindowState) -> String {
+        let states = [(st.b_minimized, WINITEM_TIP_STATES[1]), (st.b_maximized, WINITEM_TIP_STATES[2]),
+            (st.b_topmost, WINITEM_TIP_STATES[3]), (st.b_elevated, WINITEM_TIP_STATES[4])]
+            .iter().filter(|(b, _)| *b).map(|(_, s)| *s).collect::<Vec<_>>();
+        let state = if states.is_empty() { WINITEM_TIP_STATES[0].to_string() } else { states.join(", ") };
+        let mut text = format!("{}{}\r\n{}{}\r\n{}{}\r\n{}{}\r\n{}{}",
+            self.title, if self.b_hung { WINITEM_HUNG_MARK } else { "" },
+            WINITEM_TIP_LABELS[0], self.proc_img_fname, WINITEM_TIP_LABELS[1], self.pid,
+            WINITEM_TIP_LABELS[2], state, WINITEM_TIP_LABELS[3], st.monitor);
+        if !self.aumid.is_empty() { text += &format!("\r\n{}{}", WINITEM_TIP_LABELS[4], self.aumid); }
+        text
+    }
+
     pub fn sort_window_list(sortlist: &Vec<WinSortList>, nowlist: Vec<WindowInfo>, wingrplist: &mut VecDeque<VecDeque<WindowInfo>>) {
 
         let mut map_hwnd = FxHashMap::<isize/*HWND*/, usize/*grpidx*/>::default();
         let mut map_procimg = FxHashMap::<String, usize/*grpidx*/>::default();
 
-        // 元のグループリストに含まれるウィンドウを列挙(HWNDで引き出せるようにmap準備。procimgをキーに既存グループをgrpidxで引き出せるようmap準備)
+        // 元のグループリストに含まれるウィンドウを列挙(HWNDで引き出せるようにmap準備。group_keyをキーに既存グループをgrpidxで引き出せるようmap準備)
         for (grpidx, v) in wingrplist.iter().enumerate() {
             for (itemidx, i) in v.iter().enumerate() {
                 if itemidx == 0 && (i.group_type == 0 || i.group_type == 1) {
-                    map_procimg.insert(i.proc_img_fname.clone(), grpidx);
+                    map_procimg.insert(i.group_key().to_string(), grpidx);
                 }
                 map_hwnd.insert(i.handle.0, grpidx);
             }
@@ -132,10 +330,12 @@ impl WindowInfo {
             if let Some(grpidx) = values { // マッチ
                 for i in wingrplist[grpidx].iter_mut() { // vecをなめてhwnd一致するものを捜索
                     if i.handle == win.handle {
-                        if i.proc_img_fname.ne(&win.proc_img_fname) { // 別プロセスの同じウィンドウハンドルが出現
+                        if i.group_key().ne(win.group_key()) { // 別プロセス(別アプリ)の同じウィンドウハンドルが出現
                             map_hwnd.insert(win.handle.0, grpidx); // 旧は削除対象にマーク。b=falseとしないことでwinには新規追加
                         } else {
                             i.title = win.title.clone(); // タイトル更新
+                            i.disp_title = win.disp_title.clone();
+                            i.b_hung = win.b_hung;
                             b = false;
                         }
                         break;
@@ -143,10 +343,10 @@ impl WindowInfo {
                 }
             }
             if b { // 新規ウィンドウ（Ａ）オンリー
-                let vec_group = map_procimg.get(&win.proc_img_fname);
+                let vec_group = map_procimg.get(win.group_key());
                 if vec_group.is_none() {
                     let mut vec_group = VecDeque::<WindowInfo>::default();
-                    let k = win.proc_img_fname.clone();
+                    let k = win.group_key().to_string();
                     win.group_type = u32::MAX;
                     vec_group.push_front(win);
                     wingrplist.push_back(vec_group); // grpidxを壊さないよう一旦末尾に追加し後でソート★
@@ -186,7 +386,7 @@ impl WindowInfo {
 
             // sortlistを探索してscoreを決定（sortlistパラメータにない場合は-1）
             let score =
-                match PropertyHolder::contains_window_sort_list(&sortlist, &wingrplist[grpidx][maxidx].proc_img_fname) {
+                match wingrplist[grpidx][maxidx].sort_score(sortlist) {
                     None => -1isize,
                     Some(s) => s as _,
                 };
@@ -231,30 +431,114 @@ impl WindowInfo {
         }
     }
 
+    // 通知中のウィンドウを含むグループを先頭へ、グループ内でも通知中のウィンドウを先頭へ移す(それ以外の順序は保つ)
+    pub fn raise_attention(wingrplist: &mut VecDeque<VecDeque<WindowInfo>>, attention: &FxHashSet<isize>) {
+        let is_attn = |wi: &WindowInfo| attention.contains(&wi.handle.0);
+        let (mut front, back): (VecDeque<_>, VecDeque<_>) = wingrplist.drain(..).partition(|v| v.iter().any(is_attn));
+        for v in front.iter_mut() {
+            let (mut a, b): (VecDeque<_>, VecDeque<_>) = v.drain(..).partition(is_attn);
+            a.extend(b);
+            *v = a;
+        }
+        front.extend(back);
+        *wingrplist = front;
+    }
+
+    // 見かけたプロセス(AppUserModelIDを持つものはアプリ)を履歴に記録する。新しいものがあれば保存する(時刻のみの更新は終了時に保存)
+    pub fn record_history(hist: &mut Vec<ProcHistory>, vec_wi: &[WindowInfo]) {
+        let now = ProcHistory::now();
+        let mut b_added = false;
+        for wi in vec_wi {
+            if wi.group_key().is_empty() { continue }
+            if ProcHistory::touch(hist, wi.group_key(), now) {
+                if let Some(h) = hist.last_mut() { h.icon_path = wi.icon_source(); }
+                b_added = true;
+            }
+        }
+        if b_added { PropertyHolder::store_proc_history(hist); }
+    }
+
+    // スロットを今のウィンドウに割り当て直す。割り当て中のウィンドウが無くなったスロットは
+    // 同じプロセスでタイトルが一致するもの、無ければ同じプロセスの先頭のウィンドウにする
+    pub fn resolve_slots(slots: &mut [WinSlot], vec_wi: &[WindowInfo]) {
+        let mut taken = FxHashSet::<isize>::default();
+        for s in slots.iter_mut() {
+            let b_alive = vec_wi.iter().any(|wi| wi.handle.0 == s.hwnd && s.matches_proc(&wi.proc_img_fname));
+            if b_alive && taken.insert(s.hwnd) { continue }
+            s.hwnd = 0;
+        }
+        for b_title in [true, false] { // タイトルの一致を優先して、他のスロットの候補を先に取らないようにする
+            for s in slots.iter_mut().filter(|s| s.hwnd == 0) {
+                let found = vec_wi.iter().find(|wi| !taken.contains(&wi.handle.0)
+                    && s.matches_proc(&wi.proc_img_fname) && (!b_title || s.matches_title(&wi.title)));
+                if let Some(wi) = found {
+                    s.hwnd = wi.handle.0;
+                    taken.insert(s.hwnd);
+                }
+            }
+        }
+    }
+
+    // ウィンドウを前面に出す(最小化されていれば元に戻す)
+    pub fn activate(hwnd: HWND) {
+        unsafe {
+            if IsIconic(hwnd) == TRUE {
+                SendMessageTimeoutW(hwnd, WM_SYSCOMMAND, WPARAM(SC_RESTORE as usize), LPARAM(0), SMTO_ABORTIFHUNG | SMTO_BLOCK, 500, None);
+            }
+            SetForegroundWindow(hwnd);
+        }
+    }
+
+    // 起動か切替の対象ウィンドウを重なり順(最近アクティブだった順)で返す。プロセスイメージかshell:AppsFolder\AUMIDで照合する。
+    // ターゲットは環境変数だけを展開する(shell:AppsFolderを既知フォルダのパスにすると照合できない)
+    pub fn raise_candidates(vec_wi: &[WindowInfo], target: &str) -> Vec<HWND> {
+        let pattern = PathPattern::compiled(&ObjectHolder::expand_env(target));
+        vec_wi.iter().filter(|wi| pattern.is_match(&wi.proc_img_fname) || (!wi.aumid.is_empty() && pattern.is_match(&wi.icon_source())))
+            .map(|wi| wi.handle).collect()
+    }
+
+    // 前面のウィンドウと同じアプリで最も奥にあるウィンドウ(繰り返すと順に巡回する)
+    pub fn cycle_target(vec_wi: &[WindowInfo], fg: HWND) -> Option<HWND> {
+        let key = vec_wi.iter().find(|wi| wi.handle == fg)?.group_key();
+        if key.is_empty() { return None }
+        vec_wi.iter().rev().find(|wi| wi.handle != fg && wi.group_key() == key).map(|wi| wi.handle)
+    }
+
+    pub fn is_own_window(hwnd: HWND) -> bool {
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) != 0 && pid == GetCurrentProcessId() }
+    }
+
     pub fn merge_proc_list(vec_wi: &Vec<WindowInfo>, sort_list: &Vec<String>, candidate_list: &mut Vec<String>) -> FxHashMap<String, HWND> {
-        let mut sl_map = FxHashSet::<&String>::default();
         let mut cl_map = FxHashSet::<String>::default();
         let mut ret  = FxHashMap::<String, HWND>::default();
 
-        for l in sort_list { sl_map.insert(l); }
         for l in &*candidate_list { cl_map.insert(l.clone()); }
 
         for wi in vec_wi {
-            let r = PropertyHolder::contains_procimg_list(&mut sl_map.iter(), &wi.proc_img_fname);
+            let r = PropertyHolder::contains_procimg_list(&mut sort_list.iter(), wi.group_key());
             if r.is_some() {
-                if r.as_ref().unwrap().contains("*") { ret.insert(r.unwrap(), wi.handle); }
-            } else if !cl_map.contains(&wi.proc_img_fname) {
-                cl_map.insert(wi.proc_img_fname.clone());
-                candidate_list.push(wi.proc_img_fname.clone());
+                if PathPattern::is_pattern(r.as_ref().unwrap()) { ret.insert(r.unwrap(), wi.handle); }
+            } else if !cl_map.contains(wi.group_key()) {
+                cl_map.insert(wi.group_key().to_string());
+                candidate_list.push(wi.group_key().to_string());
             }
         }
         ret
     }
 
-    fn enum_window_base(hwnd: HWND) -> Option<WindowInfo> {
+    // 表示対象のウィンドウを列挙する。ルールは先頭から照合し、一致しなければ既定の判定に従う
+    pub fn enum_windows(rules: &[WinFilterRule]) -> Vec<WindowInfo> {
+        let mut param = (Vec::<WindowInfo>::default(), rules);
+        let _ = unsafe { EnumWindows(Some(Self::enum_window), LPARAM(&mut param as *mut _ as _)) };
+        param.0
+    }
+
+    // 応答なしのウィンドウで待たされないよう、メッセージを送らないAPIのみ使う
+    fn enum_window_base(hwnd: HWND, known: &[WindowInfo], rules: &[WinFilterRule]) -> Option<WindowInfo> {
         let mut buf = [0u16; 512];
 
-        let len = unsafe { GetWindowTextW(hwnd, &mut buf) };
+        let len = unsafe { InternalGetWindowText(hwnd, &mut buf) };
         let text = WSTR::from_slice_to_string(&buf, len as usize);
         if text.is_empty() { return None }
 
@@ -265,33 +549,52 @@ impl WindowInfo {
 
         let len = unsafe { GetClassNameW(hwnd, &mut buf) };
         let class = WSTR::from_slice_to_string(&buf, len as usize);
-        for title_ignore in WINLIST_IGNORE {
-            if class.eq(title_ignore) { return None }
-        }
+        // 既定では無効(モーダル表示中など)のウィンドウと除外クラスを表示しない。表示ルールがあれば照合まで保留
+        let b_default_excluded = !unsafe { IsWindowEnabled(hwnd) }.as_bool() || WINLIST_IGNORE.iter().any(|c| class.eq(c));
+        if b_default_excluded && !rules.iter().any(|r| r.b_include) { return None }
 
         let mut pid = 0u32;
         let r = unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
         if r == 0 { return None }
 
-        let hp = Handle(unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) }.ok()?); // auto drop resource
+        let b_hung = unsafe { IsHungAppWindow(hwnd) }.as_bool();
 
-        let mut len = buf.len() as u32;
-        unsafe { QueryFullProcessImageNameW(hp.0, PROCESS_NAME_FORMAT(0), PWSTR::from_raw(&mut buf as _), &mut len) }.ok()?;
+        // 同一プロセスは列挙済みの結果を使う
+        let known_wi = known.iter().find(|wi| wi.pid == pid);
+        let proc_img_fname = if let Some(wi) = known_wi {
+            wi.proc_img_fname.clone()
+        } else {
+            let hp = Handle(unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) }.ok()?); // auto drop resource
 
-        let proc_img_fname = WSTR::from_slice_to_string(&buf, len as usize);
+            let mut len = buf.len() as u32;
+            unsafe { QueryFullProcessImageNameW(hp.0, PROCESS_NAME_FORMAT(0), PWSTR::from_raw(&mut buf as _), &mut len) }.ok()?;
+            WSTR::from_slice_to_string(&buf, len as usize)
+        };
         if proc_img_fname.is_empty() { return None }
 
-        Some(WindowInfo { group_type: u32::MAX, handle: hwnd, proc_img_fname: proc_img_fname, title: text })
+        if !rules.is_empty() {
+            let exstyle = WINDOW_EX_STYLE(unsafe { GetWindowLongPtrW(hwnd, GWL_EXSTYLE) } as u32);
+            let target = WinFilterTarget { class: &class, title: &text, proc_img: &proc_img_fname,
+                b_tool: exstyle.contains(WS_EX_TOOLWINDOW), b_owned: unsafe { GetWindow(hwnd, GW_OWNER) }.0 != 0,
+                b_topmost: exstyle.contains(WS_EX_TOPMOST) };
+            if !WinFilterRule::verdict(rules, &target).unwrap_or(!b_default_excluded) { return None }
+        }
+
+        let aumid = match known_wi {
+            Some(wi) if !wi.aumid_per_window() => wi.aumid.clone(),
+            _ => Self::get_aumid(hwnd).unwrap_or_default(),
+        };
+        Some(WindowInfo { group_type: u32::MAX, handle: hwnd, proc_img_fname, aumid, disp_title: text.clone(), title: text, pid, b_hung })
     }
 
-    pub extern "system" fn enum_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
-        if !unsafe { IsWindowVisible(hwnd).into() } || !unsafe { IsWindowEnabled(hwnd).into() } {
+    extern "system" fn enum_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        if !unsafe { IsWindowVisible(hwnd).into() } {
             return TRUE
         }
 
-        let r = Self::enum_window_base(hwnd);
+        let (v, rules) =  unsafe { &mut *(lparam.0 as *mut (Vec<WindowInfo>, &[WinFilterRule])) };
+        let r = Self::enum_window_base(hwnd, v, rules);
         if let Some(i) = r {
-            let v =  unsafe { &mut *(lparam.0 as *mut Vec<WindowInfo>) };
             v.push(i);
         }
         TRUE
@@ -310,4 +613,111 @@ impl WindowInfo {
         }
         TRUE
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wi(h: isize, proc_img: &str, aumid: &str) -> WindowInfo {
+        WindowInfo { group_type: u32::MAX, handle: HWND(h), proc_img_fname: proc_img.into(), aumid: aumid.into(), title: String::new(), disp_title: String::new(), pid: 0, b_hung: false }
+    }
+
+    #[test]
+    fn tooltip_lists_details() {
+        let mut w = wi(1, r"C:\Tools\app.exe", "");
+        (w.title, w.pid) = ("Doc".into(), 42);
+        let st = WindowState { monitor: "DISPLAY1".into(), ..Default::default() };
+        let lines = w.tooltip_text(&st).split("\r\n").map(String::from).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "Doc");
+        assert_eq!(lines[1], format!("{}C:\\Tools\\app.exe", WINITEM_TIP_LABELS[0]));
+        assert_eq!(lines[3], format!("{}{}", WINITEM_TIP_LABELS[2], WINITEM_TIP_STATES[0]));
+
+        w.aumid = "App!Main".into();
+        let st = WindowState { b_minimized: true, b_elevated: true, ..Default::default() };
+        let lines = w.tooltip_text(&st).split("\r\n").map(String::from).collect::<Vec<_>>();
+        assert_eq!(lines[3], format!("{}{}, {}", WINITEM_TIP_LABELS[2], WINITEM_TIP_STATES[1], WINITEM_TIP_STATES[4]));
+        assert_eq!(lines[5], format!("{}App!Main", WINITEM_TIP_LABELS[4]));
+        assert_eq!(w.proc_name(), "app.exe");
+    }
+
+    #[test]
+    fn aumid_reused_except_per_window_hosts() {
+        assert!(!wi(1, r"C:\Tools\app.exe", "App!Main").aumid_per_window());
+        assert!(wi(1, r"C:\Windows\System32\APPLICATIONFRAMEHOST.EXE", "").aumid_per_window());
+        assert!(wi(1, r"C:/Program Files/Google/Chrome/Application/chrome.exe", "").aumid_per_window());
+    }
+
+    #[test]
+    fn slots_rebind_to_same_process() {
+        let mut list = vec![wi(1, r"C:\a.exe", ""), wi(2, r"C:\a.exe", ""), wi(3, r"C:\b.exe", "")];
+        (list[0].title, list[1].title) = ("One".into(), "Two".into());
+        let mut slots = vec![
+            WinSlot { slot: 1, proc_img: r"C:\a.exe".into(), title: "Gone".into(), hwnd: 9 },
+            WinSlot { slot: 2, proc_img: r"C:\a.exe".into(), title: "One".into(), hwnd: 0 },
+            WinSlot { slot: 3, proc_img: r"C:\b.exe".into(), title: String::new(), hwnd: 3 },
+            WinSlot { slot: 4, proc_img: r"C:\c.exe".into(), title: String::new(), hwnd: 0 },
+        ];
+        WindowInfo::resolve_slots(&mut slots, &list);
+        // タイトルが一致するスロット2が先に1を取り、スロット1は残りの2になる
+        assert_eq!(slots.iter().map(|s| s.hwnd).collect::<Vec<_>>(), vec![2, 1, 3, 0]);
+
+        list.remove(1);
+        WindowInfo::resolve_slots(&mut slots, &list);
+        assert_eq!(slots.iter().map(|s| s.hwnd).collect::<Vec<_>>(), vec![0, 1, 3, 0]);
+    }
+
+    #[test]
+    fn direct_action_targets() {
+        let list = vec![wi(1, r"C:\a.exe", ""), wi(2, r"C:\b.exe", ""), wi(3, r"C:\A.EXE", ""), wi(4, r"C:\host.exe", "App!Main"), wi(5, r"C:\a.exe", "")];
+        assert_eq!(WindowInfo::raise_candidates(&list, r"c:\a.exe"), vec![HWND(1), HWND(3), HWND(5)]);
+        assert_eq!(WindowInfo::raise_candidates(&list, r"shell:AppsFolder\App!Main"), vec![HWND(4)]);
+        assert!(WindowInfo::raise_candidates(&list, r"C:\c.exe").is_empty());
+
+        // ホットキーのターゲットそのままで照合する(環境変数は展開し、shell:AppsFolderは展開しない)
+        std::env::set_var("MYTASKBAR_TEST_RAISE_DIR", r"C:\");
+        assert_eq!(WindowInfo::raise_candidates(&list, r"%MYTASKBAR_TEST_RAISE_DIR%b.exe"), vec![HWND(2)]);
+        assert_eq!(ObjectHolder::expand_env(r"shell:AppsFolder\App!Main"), r"shell:AppsFolder\App!Main");
+
+        assert_eq!(WindowInfo::cycle_target(&list, HWND(1)), Some(HWND(5)));
+        assert_eq!(WindowInfo::cycle_target(&list, HWND(2)), None);
+        assert_eq!(WindowInfo::cycle_target(&list, HWND(9)), None);
+    }
+
+    #[test]
+    fn groups_by_aumid() {
+        let chrome = r"C:\Chrome\chrome.exe";
+        let host = r"C:\Windows\System32\ApplicationFrameHost.exe";
+        let nowlist = vec![wi(1, chrome, ""), wi(2, chrome, "Chrome._crx_pwa"), wi(3, chrome, ""),
+            wi(4, host, "Microsoft.WindowsCalculator_8wekyb3d8bbwe!App"), wi(5, host, "Microsoft.WindowsNotepad_8wekyb3d8bbwe!App")];
+        let mut grp = VecDeque::default();
+        WindowInfo::sort_window_list(&vec![], nowlist, &mut grp);
+        let mut keys = grp.iter().map(|v| (v[0].group_key().to_string(), v.len())).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, [
+            ("C:\\Chrome\\chrome.exe".to_string(), 2), ("Chrome._crx_pwa".to_string(), 1),
+            ("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App".to_string(), 1), ("Microsoft.WindowsNotepad_8wekyb3d8bbwe!App".to_string(), 1)]);
+    }
+
+    #[test]
+    fn sort_score_prefers_aumid() {
+        let sortlist = vec![WinSortList::IMGFILE("Chrome._crx_pwa".into()), WinSortList::IMGFILE(r"C:\Chrome\chrome.exe".into())];
+        assert_eq!(wi(1, r"C:\Chrome\chrome.exe", "Chrome._crx_pwa").sort_score(&sortlist), Some(0));
+        assert_eq!(wi(2, r"C:\Chrome\chrome.exe", "Chrome._crx_other").sort_score(&sortlist), Some(1)); // IDの指定が無ければイメージで照合
+        assert_eq!(wi(3, r"C:\Chrome\chrome.exe", "").sort_score(&sortlist), Some(1));
+        assert_eq!(wi(4, r"C:\other.exe", "").sort_score(&sortlist), None);
+    }
+
+    #[test]
+    fn aumid_key() {
+        assert!(WindowInfo::is_aumid("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App"));
+        assert!(!WindowInfo::is_aumid(r"C:\Windows\notepad.exe"));
+        assert!(!WindowInfo::is_aumid(r"*\chrome.exe"));
+        assert!(!WindowInfo::is_aumid("Chrome.*"));
+        assert!(!WindowInfo::is_aumid(r"shell:AppsFolder\App!Main"));
+        assert!(!WindowInfo::is_aumid(""));
+        assert_eq!(wi(1, r"C:\a.exe", "App!Main").icon_source(), r"shell:AppsFolder\App!Main");
+        assert_eq!(wi(1, r"C:\a.exe", "").icon_source(), r"C:\a.exe");
+    }
+}
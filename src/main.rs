@@ -25,6 +25,10 @@ const WMU_FILE_RENAME: u32 = WM_USER + 103;
 const WMU_DIR_PROPERTY: u32 = WM_USER + 104;
 const WMU_DIR_SORT_RESET: u32 = WM_USER + 105;
 const WMU_HOTKEY_RELOAD: u32 = WM_USER + 106;
+const WMU_ICON_LOADED: u32 = WM_USER + 107;
+const WMU_WINICON_LOADED: u32 = WM_USER + 108;
+const WMU_SHELL_CHANGE: u32 = WM_USER + 109;
+const WMU_FLO_PROBED: u32 = WM_USER + 110;
 const ID_TASKTRAY: u32 = 0;
 const ID_TASK_ARG: u32 = 1;
 const ID_HOTKEY_1: i32 = 100;
@@ -34,6 +38,10 @@ mod dlg_fileview_prop;
 mod dlg_hotkey_prop;
 mod lib_common;
 mod lib_gui_layout_container;
+mod lib_gui_layout_define;
+mod lib_gui_layout_tree;
+mod lib_icon_cache;
+mod lib_pattern;
 mod lib_property;
 mod lib_shell;
 mod lib_window;
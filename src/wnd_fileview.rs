@@ -4,10 +4,13 @@ use windows::Win32::{
 };
 
 use super::*;
-use crate::{lib_property::{PropertyHolder, HotkeyType}, lib_shell::*, lib_gui_layout_container::{adjust_window_rect, sys_font_init, sys_metrics}};
+use crate::{lib_property::{PropertyHolder, HotkeyType, SortMode}, lib_shell::*, lib_gui_layout_container::{adjust_window_rect, sys_font_init, sys_metrics}};
 
 static ONCE: Once = Once::new();
 
+const ID_TIMER_REFRESH: usize = 1;
+const REFRESH_DELAY: u32 = 200; // 変更通知をまとめるための遅延(ms)
+
 #[derive(::core::cmp::PartialEq)]
 enum MouseBtnState {
     NONE, LDOWN, MDOWN, RDOWN,
@@ -49,6 +52,9 @@ pub struct FileViewWnd {
     scroll_num: i32,
 
     obj: ObjectHolder,
+    icon_loader: Option<IconLoader>,
+    shell_notify: Option<ShellChangeNotify>,
+    vec_renames: Vec<(String, String)>, // 次回リフレッシュで反映する名前変更(旧名, 新名)
     idrop_target: Option<Box<MyDropTargetHolder>>,
     drop_idx: i32,
 
@@ -63,7 +69,7 @@ impl Drop for FileViewWnd {
     fn drop(&mut self) {
         let mut list_parse_name = Vec::<String>::with_capacity(self.obj.list_items.len());
         self.obj.list_items.iter().for_each(|i| list_parse_name.push(i.str_parse_name.to_string_null_search()));
-        let _ = PropertyHolder::store_filesort_param(self.b_file_sorted, &self.obj.parse_name, &mut list_parse_name);
+        let _ = PropertyHolder::store_filesort_param(self.b_file_sorted, &self.obj.order_path(), &mut list_parse_name);
 
         if let Some(c) = &self.child_wnd {
             c.close();
@@ -133,6 +139,9 @@ impl FileViewWnd {
             scroll_num: 0,
 
             obj: obj_hld,
+            icon_loader: None,
+            shell_notify: None,
+            vec_renames: Vec::<(String, String)>::default(),
             idrop_target: None,
             drop_idx: -1,
 
@@ -296,6 +305,75 @@ impl FileViewWnd {
         Ok(())
     }
 
+    // キャッシュ済みのアイコンを割り当て、残りはバックグラウンドで読み込む
+    fn icon_load_start(&mut self) {
+        let s = self;
+        let mut main_wnd = s.app().main_wnd();
+        let mut jobs = Vec::<(String, ItemIDList)>::default();
+
+        for idx in 0..s.obj.list_items.len() {
+            let key = s.obj.icon_key(idx);
+            if let Some((icon_lr, icon_sm)) = main_wnd.get_mut().cached_icon(&key, s.obj.list_items[idx].mtime) {
+                s.obj.list_items[idx].icon_lr = icon_lr;
+                s.obj.list_items[idx].icon_sm = icon_sm;
+                continue;
+            }
+            if let Some(pidl) = s.obj.abs_pidl(idx) {
+                jobs.push((key, pidl));
+            }
+        }
+        if jobs.len() > 0 {
+            s.icon_loader = Some(IconLoader::start(s.handle, jobs));
+        }
+    }
+
+    fn icon_loaded(&mut self, loaded: Box<IconLoaded>) {
+        let s = self;
+        let IconLoaded { key, icon_lr, icon_sm } = *loaded;
+        let (icon_lr, icon_sm) = (Rc::new(icon_lr), Rc::new(icon_sm));
+
+        // 読込中にソートされている可能性があるため、キーで対象を探す
+        let name = WSTR::from(key.strip_prefix(&format!("{}\\", s.obj.parse_name)).unwrap_or(""));
+        let idx = s.obj.list_items.iter().position(|i| i.str_parse_name.0.starts_with(&name.0));
+        if let Some(idx) = idx {
+            let mtime = s.obj.list_items[idx].mtime;
+            s.obj.list_items[idx].icon_lr = icon_lr.clone();
+            s.obj.list_items[idx].icon_sm = icon_sm.clone();
+            s.app().main_wnd().get_mut().icon_cache.insert(key, (mtime, icon_lr, icon_sm));
+
+            let i = idx as i32 - s.scroll_idx;
+            if i >= 0 && i < s.scroll_num {
+                let rc = s.calc_idx2rect(i);
+                unsafe { InvalidateRect(s.handle, Some(&rc), TRUE); }
+            }
+        }
+    }
+
+    // フォルダの変更を、ホバー・子ウィンドウ・スクロール位置を保ったまま反映する
+    fn view_refresh(&mut self) {
+        let s = self;
+        let name_of = |s: &Self, idx: i32| if idx >= 0 { s.obj.list_items.get(idx as usize).map(|i| i.str_parse_name.to_string_null_search()) } else { None };
+        let (name_hover, name_child) = (name_of(s, s.btn_idx_hover), name_of(s, s.child_idx));
+
+        if s.obj.refresh(&s.vec_renames).is_err() { return } // 失敗した時の名前変更は次回に持ち越す
+        s.vec_renames.clear();
+
+        let idx_of = |s: &Self, name: Option<String>| name.and_then(|n|
+            s.obj.list_items.iter().position(|i| i.str_parse_name.to_string_null_search().eq(&n))).map_or(-1, |i| i as i32);
+        if s.btn_idx_hover >= 0 { s.btn_idx_hover = idx_of(s, name_hover); }
+        if s.child_idx >= 0 { s.child_idx = idx_of(s, name_child); }
+        s.btn_idx_push = -1;
+
+        let num = s.obj.list_items.len() as i32;
+        if s.scroll_idx + s.scroll_num > num {
+            s.scroll_idx = std::cmp::max(num - s.scroll_num, 0);
+        }
+
+        s.icon_load_start();
+        if s.b_icon_mode { s.tooltip_toolset(); }
+        unsafe { InvalidateRect(s.handle, None, TRUE); }
+    }
+
     fn tooltip_init(&mut self) -> Result<()> {
         let s = self;
 
@@ -358,7 +436,7 @@ impl FileViewWnd {
             } else if !s.b_block_destroy {
                 let r = s.obj.child(s.btn_idx_hover as usize);
                 if r.is_ok() && r.as_ref().unwrap().list_items.len() > 0 {
-                    let p = PropertyHolder::parse_string(&r.as_ref().unwrap().str_param);
+                    let p = r.as_ref().unwrap().param.clone();
                     let mut rc = RECT::default();
                     unsafe { GetWindowRect(s.handle, &mut rc) }?;
                     let child_xpos = if s.child_xpos.is_some() { (rc.left as u64) << 32 } else { (rc.left as u64) << 32 | (rc.right- rc.left) as u64 };
@@ -385,7 +463,7 @@ impl FileViewWnd {
                 return r
             }
             if s.btn_idx_hover <= 0 && errcode == HRESULT(WMU_DIR_SORT_RESET as i32) {
-                PropertyHolder::sort_reset(&s.obj.parse_name)?;
+                PropertyHolder::sort_reset(&s.obj.order_path())?;
                 return Ok(())
             }
 
@@ -535,6 +613,7 @@ impl FileViewWnd {
         let idx = s.calc_pt2idx(mx, my);
 
         if btn == MouseBtnState::MDOWN { // 中クリック初回。ソート実行中へ移行
+            if s.obj.b_sort_fixed { return } // ホットキーの並び順が優先されるため並べ替えない
             s.sort_idx_target = idx;
             unsafe { InvalidateRect(s.handle, None, TRUE); }
             return;
@@ -777,6 +856,8 @@ impl WndMsgHandler for FileViewWnd {
             WM_CREATE => {
                 s.idrop_target.as_mut().unwrap().regist(s.handle).ok()?;
                 s.view_init().ok()?;
+                s.icon_load_start();
+                s.shell_notify = ShellChangeNotify::regist(s.handle, &s.obj);
             }
             WM_DRAWITEM | WM_MEASUREITEM | WM_MENUCHAR | WM_INITMENUPOPUP => {
                 s.obj.do_menu_handle(message, wparam, lparam).ok()?;
@@ -845,6 +926,7 @@ impl WndMsgHandler for FileViewWnd {
                         s.obj.list_items.insert(s.sort_idx_hover as usize, o);
                         if s.b_icon_mode { s.tooltip_toolset(); }
                         s.b_file_sorted = true;
+                        s.obj.param.sort_mode = SortMode::Manual; // 以後の再列挙で並びが戻らないように
                     }
 
                     s.sort_idx_target = -1;
@@ -935,7 +1017,30 @@ impl WndMsgHandler for FileViewWnd {
                     }
                 }
             }
+            WMU_SHELL_CHANGE => {
+                if let Some(r) = s.obj.read_change_notify(wparam, lparam) {
+                    s.vec_renames.push(r);
+                }
+                unsafe { SetTimer(s.handle, ID_TIMER_REFRESH, REFRESH_DELAY, None); }
+                return Some(LRESULT(0))
+            }
+            WM_TIMER => {
+                if wparam.0 == ID_TIMER_REFRESH {
+                    if let MouseBtnState::NONE = s.btn_state {
+                        let _ = unsafe { KillTimer(s.handle, ID_TIMER_REFRESH) };
+                        s.view_refresh();
+                    } // ドラッグ中はそのまま次のタイマーを待つ
+                    return Some(LRESULT(0))
+                }
+            }
+            WMU_ICON_LOADED => {
+                s.icon_loaded(take_boxed::<IconLoaded>(lparam));
+                return Some(LRESULT(0))
+            }
             WM_DESTROY => {
+                s.icon_loader = None; // 読込スレッドへ中断を通知
+                s.shell_notify = None;
+                let _ = unsafe { KillTimer(s.handle, ID_TIMER_REFRESH) };
                 s.idrop_target.as_ref().unwrap().unregist(s.handle).ok()?;
             }
             _ => { }
@@ -5,7 +5,9 @@ use self::{dlg_fileview_prop::FileViewPropWnd, dlg_hotkey_prop::HotkeyPropWnd, w
 
 use super::*;
 use crate::{
-    lib_property::{PropertyHolder, HotkeyType},
+    lib_icon_cache::{self, StoredIcon},
+    lib_property::{PropertyHolder, HotkeyType, ProcHistory, TitleRule, WinFilterRule, WinSlot},
+    lib_pattern::PathPattern,
     lib_shell::ObjectHolder,
     lib_window::WindowInfo,
     dlg_fileview_prop::DlgType,
@@ -29,6 +31,11 @@ pub const COLOR_CURSOR_HIGHLIGHT: COLORREF = COLORREF(0xffffff);
 #[cfg(feature = "dark")]
 pub const COLOR_CURSOR_HIGHLIGHT: COLORREF = COLORREF(0x404040);
 
+#[cfg(feature = "light")]
+pub const COLOR_ATTENTION: COLORREF = COLORREF(0x0080ff);
+#[cfg(feature = "dark")]
+pub const COLOR_ATTENTION: COLORREF = COLORREF(0x40a0ff);
+
 // ライトモード・ダークモード共通
 pub const COLOR_CTRL_EDGE: COLORREF = COLORREF(0x404040);
 pub const COLOR_HIGHLIGHT_BORDER: COLORREF = COLORREF(0xd0d0d0);
@@ -45,6 +52,14 @@ pub const COLOR_DEFAULT_CURSOR_HIGHLIGHT: COLORREF = COLORREF(0xffffff);
 
 //////////////////////////////////////// LOCAL
 
+pub const ICON_CACHE_MAX: usize = 4096; // アイコンキャッシュの上限(超えたら使っていないものから捨てる)
+pub const ICON_STORE_MAX: usize = 1024; // 終了時に保存するアイコンの上限(最近使ったものから)
+pub const PROC_ICON_CACHE_MAX: usize = 512; // プロセスアイコンキャッシュの上限(超えたら使っていないものから捨てる)
+
+// windowsクレートに定義がないシェルフックコード
+pub const HSHELL_FLASH: u32 = HSHELL_REDRAW | HSHELL_HIGHBIT;
+pub const HSHELL_RUDEAPPACTIVATED: u32 = HSHELL_WINDOWACTIVATED | HSHELL_HIGHBIT;
+
 const MAIN_WINDOW_CLASS: PCWSTR = if cfg!(debug_assertions) {
     w!("mytaskbar_main_window_class_D")
 } else {
@@ -59,6 +74,7 @@ const TASKTRAY_ICON_TEXT: PCWSTR = if cfg!(debug_assertions) {
 pub trait ViewWindow {
     fn close(&self);
     fn is_close_blocking(&self) -> bool { return false }
+    fn shell_event(&self, _code: u32, _hwnd: HWND) { } // シェルフック通知(HSHELL_*)
 }
 
 pub enum WinSortList {
@@ -70,11 +86,20 @@ pub struct MainWnd {
     app: Weak<RefCell<App>>,
     handle: HWND,
     msg_taskbar_restart: u32,
+    msg_shellhook: u32,
 
     pub vec_window_items: VecDeque<VecDeque<WindowInfo>>, // ウィンドウリスト
     pub vec_window_sortlist: Vec<WinSortList>, // ウィンドウをプロセスグループでソートする時の順序
+    pub vec_window_filter: Vec<WinFilterRule>, // ウィンドウリストの表示対象ルール
+    pub vec_title_rule: Vec<TitleRule>, // ウィンドウリストのタイトル書き換えルール
+    pub vec_proc_history: Vec<ProcHistory>, // ウィンドウリストで見かけたプロセスの履歴
+    pub vec_win_slot: Vec<WinSlot>, // ウィンドウのクイック切替スロット
     pub hash_window_hide: FxHashSet<isize>, // 現在hideしているHWND一覧
+    pub hash_window_attention: FxHashSet<isize>, // 点滅(通知)中でまだアクティブ化されていないHWND一覧
     pub vec_auto_hide: Vec<String>, // autohideするプロセスイメージ名
+    pub icon_cache: LruCache<(u64/*mtime*/, Rc<Icon>/*lr*/, Rc<Icon>/*sm*/)>, // ランチャーのアイコンキャッシュ(K:ObjectHolder::icon_key)
+    pub map_icon_stored: FxHashMap<String, StoredIcon>, // 前回保存したアイコンのうちまだ使っていないもの(K:ObjectHolder::icon_key)
+    pub proc_icon_cache: LruCache<Rc<Icon>>, // ウィンドウリストのアイコンキャッシュ(K:プロセスイメージ名、AppUserModelIDを持つものはshell:AppsFolder\AUMID)。実行中のみ保持
 
     // fileviewからプロパティウィンドウへの値引き渡し用
     pub rename_parentpath: String,
@@ -85,6 +110,8 @@ pub struct MainWnd {
     hash_hotkey_params: FxHashMap<String, String>, // K:ホットキー文字列, V:パラメータ文字列
     view_wnd: Option<Box<dyn ViewWindow>>, // 子ウィンドウ(同時に一つ。実態はウィンドウのオブジェクトの弱参照)
     b_last_auto_window: bool, // autowindowは、もう一度ホットキー(AW)を押すと消える挙動にするためのフラグ
+    hwnd_active: isize, // 最後にアクティブになったウィンドウ(自プロセスのものは除く)
+    hwnd_last_active: isize, // その前にアクティブだったウィンドウ(直前のウィンドウへ戻るホットキー用)
 }
 
 impl Drop for MainWnd {
@@ -103,11 +130,20 @@ impl MainWnd {
             app: app,
             handle: HWND(0), // WM_NCCREATEの処理の中で設定される
             msg_taskbar_restart: 0u32,
+            msg_shellhook: 0u32,
 
             vec_window_items: VecDeque::<VecDeque<WindowInfo>>::default(),
             vec_window_sortlist: Vec::<WinSortList>::default(),
+            vec_window_filter: Vec::<WinFilterRule>::default(),
+            vec_title_rule: Vec::<TitleRule>::default(),
+            vec_proc_history: Vec::<ProcHistory>::default(),
+            vec_win_slot: Vec::<WinSlot>::default(),
             hash_window_hide: FxHashSet::<isize>::default(),
+            hash_window_attention: FxHashSet::<isize>::default(),
             vec_auto_hide: Vec::<String>::default(),
+            icon_cache: LruCache::new(ICON_CACHE_MAX),
+            map_icon_stored: FxHashMap::<String, StoredIcon>::default(),
+            proc_icon_cache: LruCache::new(PROC_ICON_CACHE_MAX),
 
             rename_parentpath: String::default(),
             rename_filename: String::default(),
@@ -117,6 +153,8 @@ impl MainWnd {
             hash_hotkey_params: FxHashMap::<String, String>::default(),
             view_wnd: None,
             b_last_auto_window: false,
+            hwnd_active: 0,
+            hwnd_last_active: 0,
         });
 
         let window_class = MAIN_WINDOW_CLASS;
@@ -141,6 +179,25 @@ impl MainWnd {
         ret
     }
 
+    fn shell_hook(&mut self, code: u32, hwnd: HWND) {
+        match code {
+            HSHELL_FLASH => {
+                self.hash_window_attention.insert(hwnd.0);
+            }
+            HSHELL_WINDOWACTIVATED | HSHELL_RUDEAPPACTIVATED => {
+                self.hash_window_attention.remove(&hwnd.0);
+                if hwnd.0 != 0 && hwnd.0 != self.hwnd_active && !WindowInfo::is_own_window(hwnd) {
+                    (self.hwnd_last_active, self.hwnd_active) = (self.hwnd_active, hwnd.0);
+                }
+            }
+            HSHELL_WINDOWDESTROYED => {
+                self.hash_window_attention.remove(&hwnd.0);
+            }
+            _ => { }
+        }
+        if let Some(v) = &self.view_wnd { v.shell_event(code, hwnd); }
+    }
+
     pub fn set_view_wnd(&mut self, v: Option<Box<dyn ViewWindow>>) {
         if let Some(c) = &self.view_wnd { c.close(); }
         self.view_wnd = v;
@@ -198,19 +255,88 @@ impl MainWnd {
         self.set_view_wnd(None);
         match p.hotkey_type {
             HotkeyType::WinTaskList => {
-                self.set_view_wnd(Some(Box::new(WindowViewWnd::init(self.app.clone(), p.w, p.h))));
+                self.set_view_wnd(Some(Box::new(WindowViewWnd::init(self.app.clone(), p.w, p.h, p.b_attention_top, p.b_proc_line))));
             }
+            HotkeyType::WinSlot => self.slot_activate(p.slot),
+            HotkeyType::RunOrRaise => self.run_or_raise(&p.path),
+            HotkeyType::CycleApp => self.cycle_app(),
+            HotkeyType::ToggleLast => self.toggle_last(),
             _ => { // Icon/List Launcher
-                let r = ObjectHolder::bind(&p.path, p.b_sysfile_hidden);
-                if r.is_ok() && r.as_ref().unwrap().list_items.len() > 0 {
+                let r = ObjectHolder::bind(Rc::new(p.clone()));
+                if let Some(obj) = r.ok().filter(|o| o.list_items.len() > 0) {
+                    let p = obj.param.clone(); // ホットキーとフォルダの設定から決めた表示設定
                     self.set_view_wnd(Some(Box::new(FileViewWnd::init(self.app.clone(), p.hotkey_type == HotkeyType::IconLauncher,
-                        p.b_icon_large, p.w as i32, p.h as i32, r.unwrap(), None))));
+                        p.b_icon_large, p.w as i32, p.h as i32, obj, None))));
                 }
             }
         }
         self.b_last_auto_window = false;
     }
 
+    // 更新日時が一致するキャッシュ済みのアイコン。前回保存したものは最初に使うときにアイコンにする
+    pub fn cached_icon(&mut self, key: &str, mtime: u64) -> Option<(Rc<Icon>, Rc<Icon>)> {
+        if let Some(stored) = self.map_icon_stored.remove(key) {
+            if let Some((icon_lr, icon_sm)) = stored.restore().filter(|_| stored.mtime == mtime) {
+                self.icon_cache.insert(key.to_string(), (mtime, Rc::new(icon_lr), Rc::new(icon_sm)));
+            }
+        }
+        let (m, icon_lr, icon_sm) = self.icon_cache.get(key)?;
+        if *m == mtime { Some((icon_lr.clone(), icon_sm.clone())) } else { None }
+    }
+
+    // 最近使ったものから保存し、残りは前回保存したもので埋める。更新日時の無いアイテムは変化を検出できないため保存しない
+    fn store_icon_cache(&mut self) {
+        let recent = self.icon_cache.iter_recent().into_iter().filter(|(_, (mtime, _, _))| *mtime != 0)
+            .filter_map(|(key, (mtime, icon_lr, icon_sm))| {
+                Some((key.as_str(), StoredIcon { mtime: *mtime, lr: icon_lr.to_pixels()?, sm: icon_sm.to_pixels()? }))
+            }).take(ICON_STORE_MAX).collect::<Vec<_>>();
+        let set_recent = recent.iter().map(|(key, _)| *key).collect::<FxHashSet<_>>();
+        let entries = recent.iter().map(|(key, si)| (*key, si))
+            .chain(self.map_icon_stored.iter().filter(|(key, _)| !set_recent.contains(key.as_str())).map(|(key, si)| (key.as_str(), si)))
+            .take(ICON_STORE_MAX).collect::<Vec<_>>();
+        lib_icon_cache::store(&entries);
+    }
+
+    // スロットのウィンドウへ切り替える(UIは表示しない)
+    fn slot_activate(&mut self, slot: u32) {
+        let nowlist = WindowInfo::enum_windows(&self.vec_window_filter);
+        WindowInfo::resolve_slots(&mut self.vec_win_slot, &nowlist);
+        if let Some(s) = self.vec_win_slot.iter().find(|s| s.slot == slot && s.hwnd != 0) {
+            WindowInfo::activate(HWND(s.hwnd));
+        }
+    }
+
+    // プログラムのウィンドウへ切り替える(前面のものは除いて最近アクティブだった順)。ウィンドウが無ければ起動する
+    fn run_or_raise(&mut self, target: &str) {
+        let nowlist = WindowInfo::enum_windows(&self.vec_window_filter);
+        let fg = unsafe { GetForegroundWindow() };
+        let candidates = WindowInfo::raise_candidates(&nowlist, target);
+        if let Some(h) = candidates.iter().find(|h| **h != fg) {
+            WindowInfo::activate(*h);
+        } else if candidates.is_empty() && !PathPattern::is_pattern(target) {
+            let target = ObjectHolder::expand_path(target); // 起動には既知フォルダも展開したパスを使う
+            unsafe { ShellExecuteW(self.handle, w!("open"), WSTR::from(&target).PCWSTR(), PCWSTR::null(), PCWSTR::null(), SW_SHOWNORMAL); }
+        }
+    }
+
+    // 前面のアプリのウィンドウを順に切り替える
+    fn cycle_app(&mut self) {
+        let nowlist = WindowInfo::enum_windows(&self.vec_window_filter);
+        if let Some(h) = WindowInfo::cycle_target(&nowlist, unsafe { GetForegroundWindow() }) {
+            WindowInfo::activate(h);
+        }
+    }
+
+    // 直前にアクティブだったウィンドウへ戻る。記録が無ければ重なり順で次のウィンドウにする
+    fn toggle_last(&mut self) {
+        let nowlist = WindowInfo::enum_windows(&self.vec_window_filter);
+        let fg = unsafe { GetForegroundWindow() };
+        let last = HWND(self.hwnd_last_active);
+        let target = if last != fg && nowlist.iter().any(|wi| wi.handle == last) { Some(last) }
+            else { nowlist.iter().map(|wi| wi.handle).find(|h| *h != fg) };
+        if let Some(h) = target { WindowInfo::activate(h); }
+    }
+
     fn kick_arg_default(&mut self) {
         let mut str_hotkey = String::default();
         str_hotkey.push(PropertyHolder::conv_vmod2char(HOT_KEY_MODIFIERS(0)).unwrap());
@@ -250,6 +376,18 @@ impl MainWnd {
                 self.set_view_wnd(Some(Box::new(FileViewPropWnd::init(self.app.clone(), DlgType::SortEdit, String::default(), String::default()))));
             }
             3 => {
+                self.set_view_wnd(Some(Box::new(FileViewPropWnd::init(self.app.clone(), DlgType::WinFilter, String::default(), String::default()))));
+            }
+            4 => {
+                self.set_view_wnd(Some(Box::new(FileViewPropWnd::init(self.app.clone(), DlgType::TitleRule, String::default(), String::default()))));
+            }
+            5 => {
+                self.set_view_wnd(Some(Box::new(FileViewPropWnd::init(self.app.clone(), DlgType::WinSlot, String::default(), String::default()))));
+            }
+            6 => {
+                self.set_view_wnd(Some(Box::new(FileViewPropWnd::init(self.app.clone(), DlgType::FloManage, String::default(), String::default()))));
+            }
+            7 => {
                 let _ = unsafe { DestroyWindow(self.handle) };
             }
             _ => {}
@@ -270,8 +408,16 @@ impl WndMsgHandler for MainWnd {
         match message {
             WM_CREATE => {
                 PropertyHolder::load_winsort_param(&mut self.vec_window_sortlist);
+                self.vec_window_filter = PropertyHolder::load_winfilter_param();
+                self.vec_title_rule = PropertyHolder::load_titlerule_param();
+                self.vec_proc_history = PropertyHolder::load_proc_history();
+                self.vec_win_slot = PropertyHolder::load_winslot_param();
+                self.map_icon_stored = lib_icon_cache::load().into_iter().collect();
+                PropertyHolder::migrate_filesort_keys();
 
                 self.msg_taskbar_restart = unsafe { RegisterWindowMessageW(w!("TaskbarCreated")) };
+                self.msg_shellhook = unsafe { RegisterWindowMessageW(w!("SHELLHOOK")) };
+                let _ = unsafe { RegisterShellHookWindow(self.handle) };
                 self.notify_icon(self.handle, NIM_ADD);
                 self.init_hotkey();
             }
@@ -317,14 +463,19 @@ impl WndMsgHandler for MainWnd {
                 return Some(LRESULT(0))
             }
             WM_DESTROY => {
+                let _ = unsafe { DeregisterShellHookWindow(self.handle) };
                 self.term_hotkey();
                 self.notify_icon(self.handle, NIM_DELETE);
+                PropertyHolder::store_proc_history(&self.vec_proc_history); // 最終確認時刻の更新分
+                self.store_icon_cache();
                 unsafe { PostQuitMessage(0); }
             }
             _ => {
                 if message == self.msg_taskbar_restart {
                     self.notify_icon(self.handle, NIM_DELETE);
                     self.notify_icon(self.handle, NIM_ADD);
+                } else if message == self.msg_shellhook {
+                    self.shell_hook(wparam.0 as u32, HWND(lparam.0));
                 }
             }
         }
@@ -1,14 +1,18 @@
 use std::collections::VecDeque;
-use fxhash::FxHashMap;
-use windows::Win32::UI::Controls::{HOVER_DEFAULT, WM_MOUSEHOVER, WM_MOUSELEAVE};
-
-use self::lib_property::PropertyHolder;
+use fxhash::{FxHashMap, FxHashSet};
+use windows::Win32::System::SystemServices::MK_SHIFT;
+use windows::Win32::UI::Controls::{HOVER_DEFAULT, WM_MOUSEHOVER, WM_MOUSELEAVE, TOOLTIPS_CLASSW, TTS_NOPREFIX, TTS_ALWAYSTIP,
+    TTTOOLINFOW, TTF_SUBCLASS, TTM_ACTIVATE, TTM_ADDTOOLW, TTM_DELTOOLW, TTM_SETMAXTIPWIDTH, TTN_GETDISPINFOW, NMHDR, NMTTDISPINFOW};
 
 use super::*;
-use crate::{lib_window::WindowInfo, lib_gui_layout_container::{adjust_window_rect, sys_font_init, sys_metrics}};
+use crate::{lib_property::{PropertyHolder, TitleRule, WinSlot}, lib_window::{WindowInfo, WinIconLoader, WinIconLoaded}, lib_gui_layout_container::{adjust_window_rect, sys_font_init, sys_metrics}};
 
 static ONCE: Once = Once::new();
 
+const ID_TIMER_REFRESH: usize = 1;
+const REFRESH_DELAY: u32 = 100; // シェルフック通知をまとめるための遅延(ms)
+const LPSTR_TEXTCALLBACKW: PWSTR = PWSTR(-1isize as *mut u16); // 表示するときにTTN_GETDISPINFOWで文字列を問い合わせる(windowsクレートに定義がない)
+
 #[derive(::core::cmp::PartialEq)]
 enum MouseBtnState {
     NONE, LDOWN, MDOWN, RDOWN,
@@ -17,8 +21,13 @@ enum MouseBtnState {
 pub struct WindowViewWnd {
     app: AppWeak,
     handle: HWND,
+    handle_tooltip: HWND,
+    num_tooltip: i32, // 登録済みのツール数(表示行ごとに1つ)
+    tooltip_buf: WSTR, // TTN_GETDISPINFOWで返した文字列(次の問い合わせまで保持する)
     hfont: Font,
     b_block_destroy: bool,
+    b_attention_top: bool,
+    b_proc_line: bool, // 2行目にプロセス名を表示
 
     wnd_width: i32,
     wnd_height: i32,
@@ -44,7 +53,10 @@ pub struct WindowViewWnd {
     scroll_height: i32,
 
     vec_items: VecDeque<VecDeque<WindowInfo>>,
-    map_icons: FxHashMap<isize/* HWND */, Icon>,
+    map_icons: FxHashMap<isize/* HWND */, Rc<Icon>>,
+    map_slot: FxHashMap<isize/* HWND */, u32>, // 割り当て中のスロット番号
+    icon_fallback: Rc<Icon>,
+    vec_icon_loader: Vec<WinIconLoader>,
 }
 
 impl Drop for WindowViewWnd {
@@ -64,15 +76,34 @@ impl ViewWindow for WindowViewWndWeak {
             let _ = unsafe { DestroyWindow(s.handle) };
         }
     }
+
+    fn shell_event(&self, code: u32, _hwnd: HWND) {
+        if let Some(s) = self.upgrade() {
+            match code {
+                HSHELL_WINDOWCREATED | HSHELL_WINDOWDESTROYED | HSHELL_WINDOWREPLACED | HSHELL_REDRAW | HSHELL_FLASH => {
+                    unsafe { SetTimer(s.handle, ID_TIMER_REFRESH, REFRESH_DELAY, None); }
+                }
+                HSHELL_WINDOWACTIVATED | HSHELL_RUDEAPPACTIVATED => { // 通知表示の解除
+                    unsafe { InvalidateRect(s.handle, None, TRUE); }
+                }
+                _ => { }
+            }
+        }
+    }
 }
 
 impl WindowViewWnd {
-    pub fn init(app: AppWeak, w: u32, h: u32) -> WindowViewWndWeak {
+    pub fn init(app: AppWeak, w: u32, h: u32, b_attention_top: bool, b_proc_line: bool) -> WindowViewWndWeak {
         let wnd = Rc::new(Self {
             app: app,
             handle: HWND(0), // WM_NCCREATEの処理の中で設定される
+            handle_tooltip: HWND(0),
+            num_tooltip: 0,
+            tooltip_buf: WSTR(vec![0]),
             hfont: Font(HFONT(0)),
             b_block_destroy: false,
+            b_attention_top,
+            b_proc_line,
 
             wnd_width: w as _,
             wnd_height: h as _,
@@ -99,7 +130,10 @@ impl WindowViewWnd {
             scroll_height: 16,
 
             vec_items: VecDeque::<VecDeque<WindowInfo>>::default(),
-            map_icons: FxHashMap::<isize/* HWND */, Icon>::default(),
+            map_icons: FxHashMap::<isize/* HWND */, Rc<Icon>>::default(),
+            map_slot: FxHashMap::<isize/* HWND */, u32>::default(),
+            icon_fallback: Rc::new(Icon::load_stock_icon(SIID_APPLICATION).1),
+            vec_icon_loader: Vec::<WinIconLoader>::default(),
         });
 
         let window_class = w!("window_view_window");
@@ -138,10 +172,10 @@ impl WindowViewWnd {
         s.icon_height = sys_metrics(s.handle, SM_CYSMICON);
 
         s.windowlist_init();
-        s.icon_init();
+        s.icon_update();
 
         (s.hfont, _) = sys_font_init(s.handle);
-        let textwidth = unsafe { Self::check_item_maxsize(&mut s.vec_items, GetDC(s.handle), s.hfont.0) };
+        let (textwidth, textheight) = unsafe { Self::check_item_maxsize(&mut s.vec_items, GetDC(s.handle), s.hfont.0, s.b_proc_line) };
 
         // 画面サイズ取得
         let mut pt = POINT::default();
@@ -156,7 +190,7 @@ impl WindowViewWnd {
         if s.wnd_height == 0 || mi.rcWork.bottom - mi.rcWork.top < s.wnd_height { s.wnd_height = mi.rcWork.bottom - mi.rcWork.top; }
 
         let item_width = s.pad * 2 + s.group_bar_width + s.pad * 2 + s.icon_width + s.pad + textwidth + s.pad;
-        s.item_height = s.pad + s.icon_height + s.pad;
+        s.item_height = s.pad + if s.b_proc_line { std::cmp::max(s.icon_height, textheight * 2) } else { s.icon_height } + s.pad;
 
         if item_width < s.wnd_width { s.wnd_width = item_width; }
 
@@ -179,45 +213,231 @@ impl WindowViewWnd {
 
         unsafe { SetWindowPos(s.handle, None, pt.x, pt.y, win_w, win_h, SWP_NOZORDER) }?;
 
+        s.tooltip_init()
+    }
+
+    // 書き換え前のタイトルと、プロセス・状態などの詳細をツールチップで確認できるようにする
+    fn tooltip_init(&mut self) -> Result<()> {
+        let s = self;
+
+        let htt = unsafe { CreateWindowExW(
+            WINDOW_EX_STYLE::default(), TOOLTIPS_CLASSW, None, WINDOW_STYLE(TTS_NOPREFIX | TTS_ALWAYSTIP),
+            CW_USEDEFAULT, CW_USEDEFAULT, CW_USEDEFAULT, CW_USEDEFAULT, s.handle, None, None, None) };
+
+        unsafe { SetWindowPos(htt, HWND_TOPMOST, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE) }?;
+
+        s.handle_tooltip = htt;
+        unsafe { SendMessageW(htt, TTM_SETMAXTIPWIDTH, WPARAM(0), LPARAM(i32::MAX as isize)) }; // 改行で複数行にする
+        s.tooltip_toolset();
+        unsafe { SendMessageW(htt, TTM_ACTIVATE, WPARAM(TRUE.0 as usize), LPARAM(0)) };
         Ok(())
     }
 
+    // 表示中の行ごとにツールを登録し直す(スクロール・並べ替え・リスト更新のたびに呼ぶ)。
+    // 文字列はプロセスの状態などを調べるため、表示するときにその行の分だけ作る
+    fn tooltip_toolset(&mut self) {
+        let s = self;
+        if s.handle_tooltip.0 == 0 { return }
+
+        let mut ti = TTTOOLINFOW {
+            cbSize: std::mem::size_of::<TTTOOLINFOW>() as u32,
+            uFlags: TTF_SUBCLASS,
+            hwnd: s.handle,
+            hinst: HINSTANCE::default(),
+            ..Default::default()
+        };
+
+        for idx in 0..s.num_tooltip {
+            ti.uId = idx as usize;
+            unsafe { SendMessageW(s.handle_tooltip, TTM_DELTOOLW, WPARAM(0), LPARAM(&ti as *const _ as _)) };
+        }
+
+        let num = s.vec_items.iter().flatten().skip(s.scroll_idx as usize).take(s.scroll_num as usize).count();
+        for idx in 0..num {
+            ti.uId = idx;
+            ti.rect = s.calc_idx2rect(idx as i32);
+            ti.lpszText = LPSTR_TEXTCALLBACKW;
+            unsafe { SendMessageW(s.handle_tooltip, TTM_ADDTOOLW, WPARAM(0), LPARAM(&ti as *const _ as _)) };
+        }
+        s.num_tooltip = num as i32;
+    }
+
+    // ツールチップを表示する行(表示中の行番号)の文字列を作る
+    fn tooltip_getdispinfo(&mut self, di: &mut NMTTDISPINFOW) {
+        let s = self;
+        let text = s.vec_items.iter().flatten().skip(s.scroll_idx as usize).nth(di.hdr.idFrom)
+            .map(|wi| wi.tooltip_text(&wi.get_state())).unwrap_or_default();
+        s.tooltip_buf = WSTR::from(&text);
+        di.lpszText = s.tooltip_buf.PWSTR();
+    }
+
+    // タイトル書き換えルールを適用して表示用のタイトルを作る
+    fn title_rewrite(&mut self) {
+        let main_wnd = self.app().main_wnd();
+        for wi in self.vec_items.iter_mut().flatten() {
+            wi.disp_title = TitleRule::rewrite(&main_wnd.vec_title_rule, &wi.proc_img_fname, &wi.aumid, &wi.title);
+        }
+    }
+
+    // スロットを今のウィンドウに割り当て直し、表示用の番号を作る
+    fn slot_update(&mut self, nowlist: &[WindowInfo]) {
+        WindowInfo::resolve_slots(&mut self.app().main_wnd().get_mut().vec_win_slot, nowlist);
+        self.map_slot = self.app().main_wnd().vec_win_slot.iter().filter(|s| s.hwnd != 0).map(|s| (s.hwnd, s.slot)).collect();
+    }
+
+    // Shift+右クリック：項目のウィンドウをスロットに割り当てる(割り当てを外す)メニュー
+    fn slot_menu(&mut self, mut pt: POINT) {
+        let hwnd = self.calc_idx2hwnd(self.btn_idx_hover);
+        let Some(wi) = self.vec_items.iter().flatten().find(|wi| wi.handle == hwnd).cloned() else { return };
+        let r = unsafe { CreatePopupMenu() };
+        let hmenu = if r.is_err() { return } else { Menu(r.unwrap()) }; // auto drop resource
+        let cur = self.map_slot.get(&hwnd.0).copied();
+
+        let mut mii = MENUITEMINFOW::default();
+        mii.cbSize = std::mem::size_of::<MENUITEMINFOW>() as u32;
+        mii.fMask = MIIM_ID | MIIM_STRING | MIIM_STATE;
+        mii.fType = MFT_STRING;
+        for n in 1..=WinSlot::MAX {
+            let mut text = WSTR::from(&format!("{}&{}", WINITEM_MENU_SLOT, n));
+            mii.wID = n;
+            mii.fState = if cur == Some(n) { MFS_CHECKED } else { MFS_UNCHECKED };
+            mii.dwTypeData = text.PWSTR();
+            let _ = unsafe { InsertMenuItemW(hmenu.0, n - 1, TRUE, &mii) };
+        }
+        let mut sep = mii;
+        sep.fMask = MIIM_FTYPE;
+        sep.fType = MFT_SEPARATOR;
+        let _ = unsafe { InsertMenuItemW(hmenu.0, WinSlot::MAX, TRUE, &sep) };
+        mii.wID = WinSlot::MAX + 1;
+        mii.fState = if cur.is_some() { MFS_ENABLED } else { MFS_DISABLED };
+        mii.dwTypeData = PWSTR(WINITEM_MENU_SLOT_CLEAR.as_ptr() as _);
+        let _ = unsafe { InsertMenuItemW(hmenu.0, WinSlot::MAX + 1, TRUE, &mii) };
+
+        unsafe { ClientToScreen(self.handle, &mut pt); }
+        let cmd = unsafe { TrackPopupMenu(hmenu.0, TPM_LEFTALIGN | TPM_RETURNCMD, pt.x, pt.y, 0, self.handle, None).0 as u32 };
+        if cmd == 0 { return }
+
+        let mut main_wnd = self.app().main_wnd();
+        let slots = &mut main_wnd.get_mut().vec_win_slot;
+        if cmd <= WinSlot::MAX {
+            WinSlot::bind(slots, cmd, &wi.proc_img_fname, &wi.title, hwnd.0);
+        } else {
+            WinSlot::unbind(slots, hwnd.0);
+        }
+        PropertyHolder::store_winslot_param(slots);
+        self.map_slot = slots.iter().filter(|s| s.hwnd != 0).map(|s| (s.hwnd, s.slot)).collect();
+    }
+
     fn windowlist_init(&mut self) {
         let main_wnd = self.app().main_wnd();
         self.vec_items = main_wnd.vec_window_items.clone();
         let sortlist = &main_wnd.vec_window_sortlist;
 
-        let mut nowlist = Vec::<WindowInfo>::default();
-        let _ = unsafe { EnumWindows(Some(WindowInfo::enum_window), LPARAM(&mut nowlist as *mut _ as _)) };
+        let nowlist = WindowInfo::enum_windows(&main_wnd.vec_window_filter);
         self.num_item = nowlist.len() as i32;
+        WindowInfo::record_history(&mut self.app().main_wnd().get_mut().vec_proc_history, &nowlist);
+        self.slot_update(&nowlist);
 
         WindowInfo::sort_window_list(&sortlist, nowlist, &mut self.vec_items);
+        self.title_rewrite();
+        if self.b_attention_top {
+            WindowInfo::raise_attention(&mut self.vec_items, &main_wnd.hash_window_attention);
+        }
+    }
+
+    // 表示中に開閉・タイトル変更されたウィンドウを、ホバーとスクロール位置を保ったままリストに反映する
+    fn windowlist_refresh(&mut self) {
+        let s = self;
+        let hwnd_hover = s.calc_idx2hwnd(s.btn_idx_hover);
+
+        let main_wnd = s.app().main_wnd();
+        let nowlist = WindowInfo::enum_windows(&main_wnd.vec_window_filter);
+        WindowInfo::record_history(&mut s.app().main_wnd().get_mut().vec_proc_history, &nowlist);
+        s.slot_update(&nowlist);
+        WindowInfo::sort_window_list(&main_wnd.vec_window_sortlist, nowlist, &mut s.vec_items);
+        s.title_rewrite();
+        if s.b_attention_top {
+            WindowInfo::raise_attention(&mut s.vec_items, &main_wnd.hash_window_attention);
+        }
+        s.num_item = s.vec_items.iter().map(|v| v.len() as i32).sum();
+        s.icon_update();
+
+        if s.btn_idx_hover >= 0 {
+            s.btn_idx_hover = s.calc_hwnd2idx(hwnd_hover);
+        }
+        s.scroll_num = std::cmp::min((s.wnd_height - s.scroll_height) / s.item_height, s.num_item);
+        if s.scroll_idx + s.scroll_num > s.num_item {
+            s.scroll_idx = std::cmp::max(s.num_item - s.scroll_num, 0);
+        }
+        s.tooltip_toolset();
+        unsafe { InvalidateRect(s.handle, None, TRUE); }
     }
 
-    fn icon_init(&mut self) {
+    fn calc_idx2hwnd(&self, idx: i32) -> HWND {
+        if idx < 0 { return HWND(0) }
+        self.vec_items.iter().flatten().nth(idx as usize).map_or(HWND(0), |wi| wi.handle)
+    }
+
+    fn calc_hwnd2idx(&self, hwnd: HWND) -> i32 {
+        if hwnd.0 == 0 { return -1 }
+        self.vec_items.iter().flatten().position(|wi| wi.handle == hwnd).map_or(-1, |i| i as i32)
+    }
+
+    // 未取得のウィンドウはキャッシュ済みのプロセスアイコン(無ければ既定アイコン)で先に表示し、実アイコンはバックグラウンドで取得する
+    fn icon_update(&mut self) {
         let s = self;
+        let main_wnd = s.app().main_wnd();
+        let mut jobs = Vec::<(isize, String)>::default();
+        let mut set_alive = FxHashSet::<isize>::default();
 
         for v in &s.vec_items {
             for wi in v {
-                let ic = Icon::load_win_icon(wi.handle, &wi.proc_img_fname);
+                set_alive.insert(wi.handle.0);
+                if s.map_icons.contains_key(&wi.handle.0) { continue }
+                let ic = main_wnd.proc_icon_cache.get(&wi.icon_source()).unwrap_or(&s.icon_fallback).clone();
                 s.map_icons.insert(wi.handle.0, ic);
+                if !wi.b_hung { jobs.push((wi.handle.0, wi.icon_source())); }
             }
         }
+        s.map_icons.retain(|k, _| set_alive.contains(k));
+        if !jobs.is_empty() {
+            s.vec_icon_loader.push(WinIconLoader::start(s.handle, jobs));
+        }
+    }
+
+    fn icon_loaded(&mut self, r: Box<WinIconLoaded>) {
+        let s = self;
+        let WinIconLoaded { handle, icon_source, icon } = *r;
+        let icon = Rc::new(icon);
+        s.vec_icon_loader.retain_mut(|l| !l.received(handle)); // 読み終えたスレッドの分は捨てる
+
+        let mut main_wnd = s.app().main_wnd();
+        main_wnd.get_mut().proc_icon_cache.insert(icon_source, icon.clone());
+
+        if s.map_icons.contains_key(&handle) { s.map_icons.insert(handle, icon); }
+        unsafe { InvalidateRect(s.handle, None, FALSE); }
     }
 
-    pub fn check_item_maxsize(v: &mut VecDeque<VecDeque<WindowInfo>>, hdc: HDC, hfont: HFONT) -> i32 {
+    // テキストの最大幅と1行の高さ
+    pub fn check_item_maxsize(v: &mut VecDeque<VecDeque<WindowInfo>>, hdc: HDC, hfont: HFONT, b_proc_line: bool) -> (i32, i32) {
         let mut max_width = 0;
+        let mut line_height = 0;
         let mut rc: RECT = Default::default();
 
         let objold = unsafe { SelectObject(hdc, hfont) };
         for vv in v.iter_mut() {
             for i in vv.iter_mut() {
-                unsafe {DrawTextW(hdc, &mut WSTR::from(&i.title).0, &mut rc, DT_CALCRECT | DT_TOP | DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX); }
-                if max_width < rc.right { max_width = rc.right };
+                let mut texts = vec![i.disp_title.as_str()];
+                if b_proc_line { texts.push(i.proc_name()); }
+                for text in texts {
+                    unsafe {DrawTextW(hdc, &mut WSTR::from(text).0, &mut rc, DT_CALCRECT | DT_TOP | DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX); }
+                    if max_width < rc.right { max_width = rc.right };
+                    if line_height < rc.bottom { line_height = rc.bottom };
+                }
             }
         }
         unsafe { SelectObject(hdc, objold) };
-        max_width
+        (max_width, line_height)
     }
 
     fn item_handle(&mut self, mut pt: POINT, b_popup: bool) -> Result<()> {
@@ -231,11 +451,12 @@ impl WindowViewWnd {
                 v_lastidx += v.len() as i32;
                 if v_lastidx >= s.btn_idx_hover { unsafe {
                     let idx = v.len() as i32 - 1 - (v_lastidx - s.btn_idx_hover);
+                    if IsWindow(v[idx as usize].handle) == FALSE { // 表示中に閉じられたウィンドウ
+                        s.windowlist_refresh();
+                        break;
+                    }
                     if !b_popup {
-                        if IsIconic(v[idx as usize].handle) == TRUE {
-                            SendMessageTimeoutW(v[idx as usize].handle, WM_SYSCOMMAND, WPARAM(SC_RESTORE as usize), LPARAM(0), SMTO_ABORTIFHUNG | SMTO_BLOCK, 500, None);
-                        }
-                        SetForegroundWindow(v[idx as usize].handle);
+                        WindowInfo::activate(v[idx as usize].handle);
                     } else {
                         SetWindowPos(s.handle, HWND_TOPMOST, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE)?;
                         SetForegroundWindow(v[idx as usize].handle);
@@ -280,15 +501,12 @@ impl WindowViewWnd {
             if s.vec_items[push_grp as usize][push_idx as usize].group_type == 2 && s.vec_items[push_grp as usize].len() == 1 {
                 for (g, v) in s.vec_items.iter().enumerate() {
                     if g != push_grp as usize && (v[0].group_type == 0 || v[0].group_type == 1) &&
-                        s.vec_items[push_grp as usize][push_idx as usize].proc_img_fname.eq(&v[0].proc_img_fname) {
+                        s.vec_items[push_grp as usize][push_idx as usize].group_key().eq(v[0].group_key()) {
                         return
                     }
                 }
 
-                if PropertyHolder::contains_window_sort_list(
-                    &s.app().main_wnd().vec_window_sortlist,
-                    &s.vec_items[push_grp as usize][push_idx as usize].proc_img_fname)
-                    .is_some() {
+                if s.vec_items[push_grp as usize][push_idx as usize].sort_score(&s.app().main_wnd().vec_window_sortlist).is_some() {
                     s.vec_items[push_grp as usize][push_idx as usize].group_type = 0;
                 } else {
                     s.vec_items[push_grp as usize][push_idx as usize].group_type = 1;
@@ -324,9 +542,9 @@ impl WindowViewWnd {
         let target_group_type = s.vec_items[target_grp as usize][0].group_type;
         // プロセスグループ(PG)に所属するアイテムは別のPGへ移動できない
         if push_grp != target_grp && s.vec_items[push_grp as usize][0].group_type != 2 && target_group_type != 2 { return }
-        // フリーグループに所属するアイテムが他のPGへ移動するときはproc_imgが一致必要
+        // フリーグループに所属するアイテムが他のPGへ移動するときはgroup_key(AppUserModelID or proc_img)が一致必要
         if push_grp != target_grp && s.vec_items[push_grp as usize][0].group_type == 2 && target_group_type != 2 &&
-            s.vec_items[push_grp as usize][push_idx as usize].proc_img_fname.ne(&s.vec_items[target_grp as usize][0].proc_img_fname) { return }
+            s.vec_items[push_grp as usize][push_idx as usize].group_key().ne(s.vec_items[target_grp as usize][0].group_key()) { return }
 
         // 個別ウィンドウのソートを実行(グループ内 or グループまたぎ　問わず)
         let mut wnd = s.vec_items[push_grp as usize].remove(push_idx as usize).unwrap();
@@ -400,6 +618,7 @@ impl WindowViewWnd {
             s.scroll_idx = s.num_item - s.scroll_num;
         }
         if old != s.scroll_idx {
+            s.tooltip_toolset();
             unsafe { InvalidateRect(s.handle, None, TRUE); }
         }
     }
@@ -505,6 +724,7 @@ impl WindowViewWnd {
             SelectObject(hdc, GetStockObject(DC_PEN)))
         };
 
+        let main_wnd = s.app().main_wnd();
         let mut idx = 0;
         let mut grpidx = 0;
         let mut y = 0;
@@ -600,10 +820,36 @@ impl WindowViewWnd {
                 unsafe { SetDCBrushColor(hdc, old_brs_clr); }
                 let ii = s.map_icons.get(&i.handle.0);
                 if ii.is_some() {
-                    let _ = unsafe { DrawIconEx(hdc, s.pad * 2 + s.group_bar_width + s.pad * 2, y + s.pad, ii.unwrap().0, s.icon_width, s.icon_height, 0, None, DI_NORMAL) };
+                    let _ = unsafe { DrawIconEx(hdc, s.pad * 2 + s.group_bar_width + s.pad * 2, y + (s.item_height - s.icon_height) / 2, ii.unwrap().0, s.icon_width, s.icon_height, 0, None, DI_NORMAL) };
                 }
+                if main_wnd.hash_window_attention.contains(&i.handle.0) { unsafe { // 通知バッジ(アイコン右上)
+                    let x = s.pad * 2 + s.group_bar_width + s.pad * 2 + s.icon_width;
+                    let prev_brs_clr = SetDCBrushColor(hdc, COLOR_ATTENTION);
+                    let prev_pen_clr = SetDCPenColor(hdc, COLOR_ATTENTION);
+                    Ellipse(hdc, x - s.icon_width / 3, y + s.pad / 2, x + s.pad / 2, y + s.pad / 2 + s.icon_width / 3 + s.pad / 2);
+                    SetDCBrushColor(hdc, prev_brs_clr);
+                    SetDCPenColor(hdc, prev_pen_clr);
+                }}
                 let mut rc = RECT{ left: s.pad * 2 + s.group_bar_width + s.pad * 2 + s.icon_width + s.pad, top: y, right: s.wnd_width - s.pad, bottom: y + s.item_height};
-                unsafe { DrawTextExW(hdc, &mut WSTR::from(&i.title).0,  &mut rc, DT_TOP | DT_VCENTER |  DT_SINGLELINE |  DT_NOPREFIX | DT_PATH_ELLIPSIS | DT_END_ELLIPSIS, None); }
+                if let Some(n) = s.map_slot.get(&i.handle.0) { unsafe { // スロット番号(右端)
+                    let mut text = WSTR::from(&n.to_string());
+                    let mut rc_n = RECT::default();
+                    DrawTextW(hdc, &mut text.0, &mut rc_n, DT_CALCRECT | DT_SINGLELINE | DT_NOPREFIX);
+                    let mut rc_slot = RECT { left: rc.right - rc_n.right, top: y, right: rc.right, bottom: y + s.item_height };
+                    SetTextColor(hdc, COLOR_GROUPBOX);
+                    DrawTextW(hdc, &mut text.0, &mut rc_slot, DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX);
+                    SetTextColor(hdc, COLOR_TEXT);
+                    rc.right -= rc_n.right + s.pad;
+                }}
+                if s.b_proc_line { rc.bottom = y + s.item_height / 2; }
+                let title = if i.b_hung { i.disp_title.clone() + WINITEM_HUNG_MARK } else { i.disp_title.clone() };
+                unsafe { DrawTextExW(hdc, &mut WSTR::from(&title).0,  &mut rc, DT_TOP | DT_VCENTER |  DT_SINGLELINE |  DT_NOPREFIX | DT_PATH_ELLIPSIS | DT_END_ELLIPSIS, None); }
+                if s.b_proc_line { unsafe { // 2行目：プロセス名
+                    (rc.top, rc.bottom) = (y + s.item_height / 2, y + s.item_height);
+                    SetTextColor(hdc, COLOR_GROUPBOX);
+                    DrawTextExW(hdc, &mut WSTR::from(i.proc_name()).0, &mut rc, DT_TOP | DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX | DT_END_ELLIPSIS, None);
+                    SetTextColor(hdc, COLOR_TEXT);
+                }}
                 y += s.item_height;
                 idx += 1;
                 count += 1;
@@ -697,6 +943,7 @@ impl WndMsgHandler for WindowViewWnd {
 
                     } else if s.grp_idx_push >= 0 {
                         s.sort_group();
+                        s.tooltip_toolset();
 
                     } else if s.btn_idx_push == s.btn_idx_hover && s.btn_idx_hover >= 0 {
                         let h = s.app().main_wnd().handle(); // destroyが走ってもいいように先にハンドルを取得しておく
@@ -717,6 +964,7 @@ impl WndMsgHandler for WindowViewWnd {
 
                     if s.wnd_idx_push >= 0 {
                         s.sort_window();
+                        s.tooltip_toolset();
                     }
                     (s.wnd_idx_push, s.wnd_idx_target) = (-1, -1);
                     unsafe { InvalidateRect(s.handle, None, TRUE) };
@@ -727,7 +975,9 @@ impl WndMsgHandler for WindowViewWnd {
                     let _ = unsafe { ReleaseCapture() };
                     s.btn_state = MouseBtnState::NONE;
 
-                    if s.btn_idx_push == s.btn_idx_hover && s.btn_idx_hover != -1 {
+                    if s.btn_idx_push == s.btn_idx_hover && s.btn_idx_hover >= 0 && wparam.0 & MK_SHIFT.0 as usize != 0 {
+                        s.slot_menu(POINT { x:lparam.0 as i32 & u16::MAX as i32, y:lparam.0 as i32 >> u16::BITS });
+                    } else if s.btn_idx_push == s.btn_idx_hover && s.btn_idx_hover != -1 {
                         let h = s.app().main_wnd().handle(); // destroyが走ってもいいように先にハンドルを取得しておく
                         let r = s.item_handle(POINT { x:lparam.0 as i32 & u16::MAX as i32, y:lparam.0 as i32 >> u16::BITS }, true);
                         if r.is_ok() {
@@ -772,6 +1022,13 @@ impl WndMsgHandler for WindowViewWnd {
                     unsafe { InvalidateRect(s.handle, None, TRUE); }
                 }
             }
+            WM_NOTIFY => {
+                let hdr = unsafe { &*(lparam.0 as *const NMHDR) };
+                if hdr.hwndFrom == s.handle_tooltip && hdr.code == TTN_GETDISPINFOW {
+                    s.tooltip_getdispinfo(unsafe { &mut *(lparam.0 as *mut NMTTDISPINFOW) });
+                    return Some(LRESULT(0))
+                }
+            }
             WM_MOUSEHOVER => {
                 // ソート実行中のスクロール
                 s.scroll_chk(lparam.0 as i32 & u16::MAX as i32, lparam.0 as i32 >> u16::BITS, true);
@@ -803,7 +1060,22 @@ impl WndMsgHandler for WindowViewWnd {
             }
             WM_ACTIVATE => {
             }
+            WM_TIMER => {
+                if wparam.0 == ID_TIMER_REFRESH {
+                    if let MouseBtnState::NONE = s.btn_state {
+                        let _ = unsafe { KillTimer(s.handle, ID_TIMER_REFRESH) };
+                        s.windowlist_refresh();
+                    } // ドラッグ中はそのまま次のタイマーを待つ
+                    return Some(LRESULT(0))
+                }
+            }
+            WMU_WINICON_LOADED => {
+                s.icon_loaded(take_boxed::<WinIconLoaded>(lparam));
+                return Some(LRESULT(0))
+            }
             WM_DESTROY => {
+                let _ = unsafe { KillTimer(s.handle, ID_TIMER_REFRESH) };
+                s.vec_icon_loader.clear(); // 読込スレッドへ中断を通知
                 s.app().main_wnd().get_mut().vec_window_items = s.vec_items.clone();
             }
             _ => { }